@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS image_metadata (
+    path TEXT PRIMARY KEY,
+    width INT NOT NULL,
+    height INT NOT NULL,
+    dominant_color VARCHAR(7) NOT NULL,
+    blurhash VARCHAR(64) NOT NULL,
+    lqip TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    file_modified_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+ALTER TABLE news_posts ADD COLUMN cover_image TEXT;
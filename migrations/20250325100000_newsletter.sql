@@ -0,0 +1,31 @@
+CREATE TYPE subscriber_status AS ENUM ('pending', 'confirmed', 'unsubscribed');
+
+CREATE TABLE IF NOT EXISTS subscribers (
+    id UUID PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    user_id UUID,
+    locale VARCHAR(10) NOT NULL DEFAULT 'en',
+    status subscriber_status NOT NULL DEFAULT 'pending',
+    confirmation_token VARCHAR(255) UNIQUE,
+    confirmation_expires_at TIMESTAMP WITH TIME ZONE,
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    unsubscribed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX subscribers_status_idx ON subscribers (status);
+
+CREATE TABLE IF NOT EXISTS newsletter_issues (
+    id UUID PRIMARY KEY,
+    subject TEXT NOT NULL,
+    html_content TEXT NOT NULL,
+    text_content TEXT NOT NULL,
+    recipients INT NOT NULL DEFAULT 0,
+    sent_by UUID,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    FOREIGN KEY (sent_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+ALTER TABLE email_outbox ADD COLUMN headers JSONB NOT NULL DEFAULT '{}';
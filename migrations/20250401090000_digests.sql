@@ -0,0 +1,28 @@
+CREATE TYPE digest_frequency AS ENUM ('off', 'daily', 'weekly');
+CREATE TYPE digest_recipient AS ENUM ('user', 'subscriber');
+
+ALTER TABLE users ADD COLUMN digest_frequency digest_frequency NOT NULL DEFAULT 'off';
+ALTER TABLE users ADD COLUMN last_digest_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE subscribers ADD COLUMN digest_frequency digest_frequency NOT NULL DEFAULT 'weekly';
+ALTER TABLE subscribers ADD COLUMN last_digest_at TIMESTAMP WITH TIME ZONE;
+
+-- Existing videos keep a NULL publish date so the first digests don't resend the whole catalogue.
+ALTER TABLE videos ADD COLUMN created_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE videos ALTER COLUMN created_at SET DEFAULT NOW();
+
+CREATE INDEX news_posts_created_at_idx ON news_posts (created_at);
+CREATE INDEX videos_created_at_idx ON videos (created_at);
+
+CREATE TABLE IF NOT EXISTS digest_deliveries (
+    id UUID PRIMARY KEY,
+    recipient_kind digest_recipient NOT NULL,
+    recipient_id UUID NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    period_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    post_ids UUID[] NOT NULL DEFAULT '{}',
+    video_ids UUID[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    UNIQUE (recipient_kind, recipient_id, period_start)
+);
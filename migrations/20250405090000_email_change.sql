@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS email_change_requests (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    new_email VARCHAR(255) NOT NULL,
+    confirm_token_hash CHAR(64) NOT NULL UNIQUE,
+    cancel_token_hash CHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    cancelled_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX email_change_requests_pending_idx ON email_change_requests (user_id)
+    WHERE confirmed_at IS NULL AND cancelled_at IS NULL;
+CREATE INDEX email_change_requests_new_email_idx ON email_change_requests (LOWER(new_email));
@@ -0,0 +1,26 @@
+CREATE TYPE token_purpose AS ENUM ('verify_email', 'reset_password', 'change_email', 'magic_link');
+
+CREATE TABLE IF NOT EXISTS user_tokens (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    purpose token_purpose NOT NULL,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    email VARCHAR(255),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX user_tokens_user_purpose_idx ON user_tokens (user_id, purpose, created_at);
+
+-- Outstanding plaintext tokens can't be migrated to hashes; affected users can request new ones.
+ALTER TABLE users DROP COLUMN verification_token;
+ALTER TABLE users DROP COLUMN token_expires_at;
+
+INSERT INTO user_tokens (id, user_id, purpose, token_hash, email, expires_at)
+SELECT gen_random_uuid(), user_id, 'change_email', confirm_token_hash, new_email, expires_at
+FROM email_change_requests
+WHERE confirmed_at IS NULL AND cancelled_at IS NULL;
+
+ALTER TABLE email_change_requests DROP COLUMN confirm_token_hash;
@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN magic_link_enabled BOOLEAN NOT NULL DEFAULT FALSE;
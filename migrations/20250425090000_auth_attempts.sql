@@ -0,0 +1,18 @@
+CREATE TYPE auth_action AS ENUM ('login', 'magic_link', 'forgot_password', 'reset_password');
+
+CREATE TABLE IF NOT EXISTS auth_attempts (
+    id UUID PRIMARY KEY,
+    action auth_action NOT NULL,
+    key VARCHAR(320) NOT NULL,
+    failed BOOLEAN NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX auth_attempts_key_idx ON auth_attempts (key, action, created_at);
+CREATE INDEX auth_attempts_created_at_idx ON auth_attempts (created_at);
+
+CREATE TABLE IF NOT EXISTS account_lockouts (
+    key VARCHAR(320) PRIMARY KEY,
+    locked_until TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Sent emails no longer keep their content; clear what is already there.
+UPDATE email_outbox
+SET html_body = '', text_body = NULL, headers = '{}'
+WHERE status = 'sent';
+
+CREATE INDEX email_outbox_sent_at_idx ON email_outbox (sent_at) WHERE status = 'sent';
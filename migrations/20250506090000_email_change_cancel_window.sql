@@ -0,0 +1,11 @@
+-- A confirmed change can still be cancelled, which restores the old address, until
+-- cancel_until; the old address stays reserved for the account until then.
+ALTER TABLE email_change_requests ADD COLUMN old_email VARCHAR(255);
+ALTER TABLE email_change_requests ADD COLUMN cancel_until TIMESTAMP WITH TIME ZONE;
+
+UPDATE email_change_requests SET cancel_until = expires_at;
+
+ALTER TABLE email_change_requests ALTER COLUMN cancel_until SET NOT NULL;
+
+CREATE INDEX email_change_requests_old_email_idx ON email_change_requests (LOWER(old_email))
+    WHERE old_email IS NOT NULL AND cancelled_at IS NULL;
@@ -0,0 +1,2 @@
+-- Peppered Argon2 hashes carry a keyid and no longer fit in 100 characters.
+ALTER TABLE users ALTER COLUMN password TYPE TEXT;
@@ -0,0 +1,15 @@
+-- Confirmation tokens are stored as SHA-256 hashes; pending links keep working because the
+-- raw token they carry hashes to the converted value.
+ALTER TABLE subscribers RENAME COLUMN confirmation_token TO confirmation_token_hash;
+
+UPDATE subscribers
+SET confirmation_token_hash = encode(sha256(convert_to(confirmation_token_hash, 'UTF8')), 'hex')
+WHERE confirmation_token_hash IS NOT NULL;
+
+ALTER TABLE subscribers ALTER COLUMN confirmation_token_hash TYPE CHAR(64);
+
+ALTER TABLE subscribers ADD COLUMN confirmation_sent_at TIMESTAMPTZ;
+
+UPDATE subscribers
+SET confirmation_sent_at = updated_at
+WHERE status = 'pending';
@@ -0,0 +1,3 @@
+-- Addresses are compared case-insensitively, so two accounts must not differ only in case.
+DROP INDEX IF EXISTS users_email_idx;
+CREATE UNIQUE INDEX users_email_lower_idx ON users (LOWER(email));
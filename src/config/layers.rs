@@ -0,0 +1,426 @@
+use std::{env, fs, iter, path::Path};
+
+use serde_json::{Map, Value};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Environment variables and the config path they override. The names predate the config
+/// file and are kept so existing deployments keep working.
+const ENV_VARS: [(&str, &str); 93] = [
+    ("PORT", "server.port"),
+    ("API_URL", "server.api_url"),
+    ("FRONT_URL", "server.front_url"),
+    ("API_KEY", "server.api_key"),
+    ("TRUST_PROXY_HEADERS", "server.trust_proxy_headers"),
+    ("DATABASE_URL", "database.url"),
+    ("DATABASE_MAX_CONNECTIONS", "database.max_connections"),
+    ("JWT_SECRET_KEY", "auth.jwt.secret"),
+    ("JWT_KEYRING", "auth.jwt.keyring"),
+    ("JWT_ISSUER", "auth.jwt.issuer"),
+    ("JWT_AUDIENCE", "auth.jwt.audience"),
+    (
+        "JWT_RETIRED_KEY_GRACE_SECS",
+        "auth.jwt.retired_key_grace_secs",
+    ),
+    ("JWT_MAXAGE", "auth.jwt.session_ttl"),
+    ("JWT_LEEWAY_SECS", "auth.jwt.leeway"),
+    ("COOKIE_SECURE", "auth.cookies.secure"),
+    ("COOKIE_SAME_SITE", "auth.cookies.same_site"),
+    ("COOKIE_DOMAIN", "auth.cookies.domain"),
+    ("COOKIE_HOST_PREFIX", "auth.cookies.host_prefix"),
+    ("CSRF_SECRET", "auth.cookies.csrf_secret"),
+    ("PASSWORD_MIN_LENGTH", "auth.password_policy.min_length"),
+    ("PASSWORD_MAX_LENGTH", "auth.password_policy.max_length"),
+    ("PASSWORD_MIN_SCORE", "auth.password_policy.min_score"),
+    (
+        "PASSWORD_BREACH_CHECK",
+        "auth.password_policy.check_breached",
+    ),
+    (
+        "PASSWORD_BREACH_CORPUS",
+        "auth.password_policy.breach_corpus",
+    ),
+    ("PASSWORD_HASH_MEMORY_KIB", "auth.password_hash.memory_kib"),
+    ("PASSWORD_HASH_ITERATIONS", "auth.password_hash.iterations"),
+    (
+        "PASSWORD_HASH_PARALLELISM",
+        "auth.password_hash.parallelism",
+    ),
+    ("PASSWORD_PEPPER", "auth.password_hash.pepper"),
+    ("PASSWORD_PEPPER_ID", "auth.password_hash.pepper_id"),
+    (
+        "PASSWORD_PREVIOUS_PEPPERS",
+        "auth.password_hash.previous_peppers",
+    ),
+    ("AUTH_LIMIT_STORE", "auth.limits.store"),
+    ("AUTH_LIMIT_WINDOW_SECS", "auth.limits.window_secs"),
+    ("AUTH_LIMIT_IP_MAX_ATTEMPTS", "auth.limits.ip_max_attempts"),
+    (
+        "AUTH_LIMIT_ACCOUNT_MAX_ATTEMPTS",
+        "auth.limits.account_max_attempts",
+    ),
+    (
+        "AUTH_LIMIT_ACCOUNT_MAX_EMAILS",
+        "auth.limits.account_max_emails",
+    ),
+    ("AUTH_LOCKOUT_THRESHOLD", "auth.limits.lockout_threshold"),
+    ("AUTH_LOCKOUT_SECS", "auth.limits.lockout_secs"),
+    ("AUTH_DELAY_BASE_MS", "auth.limits.delay_base_ms"),
+    ("AUTH_DELAY_MAX_MS", "auth.limits.delay_max_ms"),
+    (
+        "AUTH_EMAIL_CHANGE_CONFIRMATION_TTL_HOURS",
+        "auth.email_change.confirmation_ttl_hours",
+    ),
+    (
+        "AUTH_EMAIL_CHANGE_CANCEL_WINDOW_HOURS",
+        "auth.email_change.cancel_window_hours",
+    ),
+    ("OIDC_STATE_TTL_MINUTES", "auth.oidc.state_ttl_minutes"),
+    ("OIDC_HTTP_TIMEOUT_SECS", "auth.oidc.http_timeout_secs"),
+    ("MAIL_TRANSPORT", "mail.transport"),
+    ("MAIL_FROM", "mail.from"),
+    ("SMTP_SERVER", "mail.smtp_server"),
+    ("SMTP_PORT", "mail.smtp_port"),
+    ("SMTP_USERNAME", "mail.smtp_username"),
+    ("SMTP_PASSWORD", "mail.smtp_password"),
+    ("SMTP_POOL_SIZE", "mail.smtp_pool_size"),
+    ("MAIL_FILE_DIR", "mail.file_dir"),
+    ("MAIL_TEMPLATE_DIR", "mail.template_dir"),
+    (
+        "OUTBOX_POLL_INTERVAL_SECS",
+        "mail.outbox.poll_interval_secs",
+    ),
+    ("OUTBOX_BATCH_SIZE", "mail.outbox.batch_size"),
+    ("OUTBOX_MAX_ATTEMPTS", "mail.outbox.max_attempts"),
+    ("OUTBOX_BASE_BACKOFF_SECS", "mail.outbox.base_backoff_secs"),
+    ("OUTBOX_MAX_BACKOFF_SECS", "mail.outbox.max_backoff_secs"),
+    ("OUTBOX_LEASE_SECS", "mail.outbox.lease_secs"),
+    (
+        "OUTBOX_PURGE_INTERVAL_SECS",
+        "mail.outbox.purge_interval_secs",
+    ),
+    (
+        "OUTBOX_DEAD_RETENTION_HOURS",
+        "mail.outbox.dead_retention_hours",
+    ),
+    (
+        "OUTBOX_SENT_RETENTION_DAYS",
+        "mail.outbox.sent_retention_days",
+    ),
+    ("MEDIA_ASSETS_DIR", "media.assets_dir"),
+    ("MEDIA_ALLOWED_WIDTHS", "media.allowed_widths"),
+    ("CORS_ALLOWED_ORIGINS", "cors.allowed_origins"),
+    ("CORS_ALLOWED_METHODS", "cors.allowed_methods"),
+    ("CORS_ALLOWED_HEADERS", "cors.allowed_headers"),
+    ("CORS_MAX_AGE_SECS", "cors.max_age_secs"),
+    ("HSTS_MAX_AGE_SECS", "security_headers.hsts_max_age_secs"),
+    (
+        "HSTS_INCLUDE_SUBDOMAINS",
+        "security_headers.hsts_include_subdomains",
+    ),
+    ("HSTS_PRELOAD", "security_headers.hsts_preload"),
+    ("REFERRER_POLICY", "security_headers.referrer_policy"),
+    (
+        "CONTENT_SECURITY_POLICY",
+        "security_headers.content_security_policy",
+    ),
+    ("PERMISSIONS_POLICY", "security_headers.permissions_policy"),
+    ("RATE_LIMIT_ENABLED", "rate_limit.enabled"),
+    ("RATE_LIMIT_ALLOWLIST", "rate_limit.allowlist"),
+    ("NEWSLETTER_SECRET", "newsletter.signing_secret"),
+    (
+        "NEWSLETTER_SEND_RATE_PER_MINUTE",
+        "newsletter.send_rate_per_minute",
+    ),
+    (
+        "NEWSLETTER_CONFIRMATION_TTL_HOURS",
+        "newsletter.confirmation_ttl_hours",
+    ),
+    ("DIGEST_INTERVAL_SECS", "digest.interval_secs"),
+    ("DIGEST_BATCH_SIZE", "digest.batch_size"),
+    ("DIGEST_MAX_ITEMS", "digest.max_items"),
+    ("LOG_FORMAT", "telemetry.log_format"),
+    ("RUST_LOG", "telemetry.log_filter"),
+    ("OTEL_SERVICE_NAME", "telemetry.service_name"),
+    ("OTEL_EXPORTER_OTLP_ENDPOINT", "telemetry.otlp_endpoint"),
+    ("METRICS_TOKEN", "metrics.token"),
+    ("METRICS_ADMIN_PORT", "metrics.admin_port"),
+    ("HEALTH_CHECK_TIMEOUT", "health.check_timeout"),
+    ("HEALTH_CHECK_SMTP", "health.check_smtp"),
+    ("HEALTH_CACHE_TTL", "health.cache_ttl"),
+    ("SHUTDOWN_READINESS_DELAY", "shutdown.readiness_delay"),
+    ("SHUTDOWN_DRAIN_TIMEOUT", "shutdown.drain_timeout"),
+    ("API_DOCS_ENABLED", "docs.enabled"),
+];
+
+const OIDC_PROVIDER_FIELDS: [&str; 10] = [
+    "kind",
+    "issuer",
+    "client_id",
+    "client_secret",
+    "redirect_uri",
+    "scopes",
+    "authorization_endpoint",
+    "token_endpoint",
+    "userinfo_endpoint",
+    "jwks_uri",
+];
+
+/// Names a config path together with the environment variable that overrides it.
+pub fn describe(path: &str) -> String {
+    let env_var = ENV_VARS
+        .iter()
+        .find(|(_, target)| *target == path)
+        .map(|(key, _)| key.to_string())
+        .or_else(|| {
+            let (name, field) = path
+                .strip_prefix("auth.oidc.providers.")?
+                .rsplit_once('.')?;
+            Some(format!("{}_{}", oidc_prefix(name), field.to_uppercase()))
+        });
+
+    match env_var {
+        Some(env_var) => format!("{path} ({env_var})"),
+        None => path.to_string(),
+    }
+}
+
+pub fn merge_file(tree: &mut Value, config_file: Option<&str>, problems: &mut Vec<String>) {
+    let path = match config_file
+        .map(str::to_string)
+        .or_else(|| env::var("CONFIG_FILE").ok())
+    {
+        Some(path) => path,
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => DEFAULT_CONFIG_FILE.to_string(),
+        None => return,
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            problems.push(format!("{path}: {err}"));
+            return;
+        }
+    };
+
+    match toml::from_str::<Value>(&contents) {
+        Ok(file) => merge(tree, file),
+        Err(err) => problems.push(format!("{path}: {}", err.message())),
+    }
+}
+
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+pub fn apply_env(tree: &mut Value, problems: &mut Vec<String>) {
+    for (key, path) in ENV_VARS {
+        let Ok(raw) = env::var(key) else {
+            continue;
+        };
+
+        let segments: Vec<&str> = path.split('.').collect();
+        let current = get(tree, &segments).cloned().unwrap_or(Value::Null);
+
+        match coerce(&current, &raw) {
+            Ok(value) => set(tree, &segments, value),
+            Err(expected) => problems.push(format!("{key}: expected {expected} (got {raw:?})")),
+        }
+    }
+
+    apply_rate_limit_env(tree, problems);
+    apply_oidc_env(tree);
+}
+
+/// Env values are typed after the value they replace, so `PORT=abc` is reported here
+/// rather than as a confusing serde error.
+fn coerce(current: &Value, raw: &str) -> Result<Value, &'static str> {
+    match current {
+        Value::Bool(_) => raw
+            .trim()
+            .parse()
+            .map(Value::Bool)
+            .map_err(|_| "true or false"),
+        Value::Number(_) => raw
+            .trim()
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| "a number"),
+        Value::Array(items) => {
+            let item = items.first().cloned().unwrap_or(Value::Null);
+            raw.split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| coerce(&item, value))
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        }
+        Value::Null if raw.is_empty() => Ok(Value::Null),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// `RATE_LIMIT_<GROUP>` is `<per_minute>,<burst>[,<ip|user>]`, for the default rule
+/// and every group known from the defaults or the config file.
+fn apply_rate_limit_env(tree: &mut Value, problems: &mut Vec<String>) {
+    let groups: Vec<String> = get(tree, &["rate_limit", "groups"])
+        .and_then(Value::as_object)
+        .map(|groups| groups.keys().cloned().collect())
+        .unwrap_or_default();
+
+    for group in iter::once("default".to_string()).chain(groups) {
+        let key = format!("RATE_LIMIT_{}", group.to_uppercase());
+        let Ok(raw) = env::var(&key) else {
+            continue;
+        };
+
+        let parts: Vec<&str> = raw.split(',').map(str::trim).collect();
+        let (per_minute, burst, limit_key) = match parts.as_slice() {
+            [per_minute, burst] => (per_minute, burst, None),
+            [per_minute, burst, limit_key] => (per_minute, burst, Some(*limit_key)),
+            _ => (&"", &"", None),
+        };
+
+        let (Ok(per_minute), Ok(burst)) = (per_minute.parse::<u32>(), burst.parse::<u32>()) else {
+            problems.push(format!(
+                "{key}: expected <per_minute>,<burst>[,<ip|user>] (got {raw:?})"
+            ));
+            continue;
+        };
+
+        let rule: Vec<&str> = if group == "default" {
+            vec!["rate_limit", "default_rule"]
+        } else {
+            vec!["rate_limit", "groups", &group]
+        };
+
+        set(
+            tree,
+            &[&rule[..], &["per_minute"]].concat(),
+            Value::from(per_minute),
+        );
+        set(tree, &[&rule[..], &["burst"]].concat(), Value::from(burst));
+        if let Some(limit_key) = limit_key {
+            set(
+                tree,
+                &[&rule[..], &["key"]].concat(),
+                Value::String(limit_key.to_string()),
+            );
+        }
+    }
+}
+
+/// `OIDC_PROVIDERS` lists provider names; each one reads `OIDC_<NAME>_<FIELD>`.
+fn apply_oidc_env(tree: &mut Value) {
+    let mut names: Vec<String> = env::var("OIDC_PROVIDERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    if let Some(providers) = get(tree, &["auth", "oidc", "providers"]).and_then(Value::as_object) {
+        names.extend(providers.keys().cloned());
+    }
+
+    for name in names {
+        let provider = ["auth", "oidc", "providers", name.as_str()];
+        if get(tree, &provider).is_none() {
+            set(tree, &provider, Value::Object(Map::new()));
+        }
+
+        for field in OIDC_PROVIDER_FIELDS {
+            let key = format!("{}_{}", oidc_prefix(&name), field.to_uppercase());
+            if let Ok(value) = env::var(key) {
+                set(
+                    tree,
+                    &[&provider[..], &[field]].concat(),
+                    Value::String(value),
+                );
+            }
+        }
+    }
+}
+
+fn oidc_prefix(name: &str) -> String {
+    format!("OIDC_{}", name.to_uppercase().replace('-', "_"))
+}
+
+fn get<'a>(tree: &'a Value, segments: &[&str]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(tree, |value, segment| value.get(segment))
+}
+
+fn set(tree: &mut Value, segments: &[&str], value: Value) {
+    let Some((last, parents)) = segments.split_last() else {
+        return;
+    };
+
+    let mut current = tree;
+    for segment in parents {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .unwrap()
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    if !current.is_object() {
+        *current = Value::Object(Map::new());
+    }
+    current
+        .as_object_mut()
+        .unwrap()
+        .insert(last.to_string(), value);
+}
+
+pub fn redact(value: &mut Value, secret_keys: &[&str]) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if !secret_keys.contains(&key.as_str()) {
+                    redact(value, secret_keys);
+                    continue;
+                }
+
+                match value {
+                    Value::String(secret) if !secret.is_empty() => {
+                        *secret = "[redacted]".to_string();
+                    }
+                    Value::Array(secrets) => {
+                        for secret in secrets.iter_mut().filter(|secret| secret.is_string()) {
+                            *secret = Value::String("[redacted]".to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, secret_keys)),
+        _ => {}
+    }
+}
+
+/// TOML has no null, so unset optional values are left out of the dump.
+pub fn strip_nulls(value: &mut Value) {
+    if let Value::Object(map) = value {
+        map.retain(|_, value| !value.is_null());
+        map.values_mut().for_each(strip_nulls);
+    }
+}
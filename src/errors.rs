@@ -6,6 +6,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 
 use std::{collections::HashMap, fmt};
+use utoipa::ToSchema;
 use validator::ValidationErrors;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -19,11 +20,21 @@ pub enum Error {
     DatabaseError(sqlx::Error),
     InvalidHashFormat(argon2::password_hash::Error),
     Forbidden,
+    TooManyRequests(String),
     Validation(ValidationErrors),
     ReadString(String),
+    Image(image::ImageError),
+    EmailAddress(lettre::address::AddressError),
+    EmailBuild(lettre::error::Error),
+    EmailDelivery(lettre::transport::smtp::Error),
+    EmailFile(lettre::transport::file::Error),
+    EmailDirectory(std::io::Error),
+    EmailUnavailable(String),
+    Template(minijinja::Error),
+    Http(reqwest::Error),
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ValidationResponse {
     pub code: u16,
     pub message: String,
@@ -32,7 +43,9 @@ pub struct ValidationResponse {
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        match self {
+        let detail = format!("{:?}", self);
+
+        let response = match self {
             Self::NotFound => (StatusCode::NOT_FOUND, "Resource not found").into_response(),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
             Self::InternalServerError => {
@@ -54,6 +67,15 @@ impl IntoResponse for Error {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Invalid hash format").into_response()
             }
             Self::Forbidden => (StatusCode::FORBIDDEN, "Forbidden").into_response(),
+            Self::TooManyRequests(msg) => {
+                let response = ValidationResponse {
+                    message: msg,
+                    code: 429,
+                    errors: None,
+                };
+
+                (StatusCode::TOO_MANY_REQUESTS, Json(response)).into_response()
+            }
             Self::Validation(errors) => {
                 let mut errror_map = HashMap::new();
 
@@ -73,7 +95,41 @@ impl IntoResponse for Error {
                 (StatusCode::BAD_REQUEST, Json(response)).into_response()
             }
             Self::ReadString(err) => (StatusCode::FAILED_DEPENDENCY, err).into_response(),
+            Self::Image(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "Image processing error").into_response()
+            }
+            Self::EmailAddress(_) => {
+                let response = ValidationResponse {
+                    message: "Invalid email address".to_string(),
+                    code: 400,
+                    errors: None,
+                };
+
+                (StatusCode::BAD_REQUEST, Json(response)).into_response()
+            }
+            Self::EmailBuild(_) | Self::EmailFile(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Email error").into_response()
+            }
+            Self::Template(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Template error").into_response()
+            }
+            Self::EmailDelivery(_) => {
+                (StatusCode::BAD_GATEWAY, "Email delivery failed").into_response()
+            }
+            Self::EmailDirectory(_) | Self::EmailUnavailable(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "Email unavailable").into_response()
+            }
+            Self::Http(_) => {
+                (StatusCode::BAD_GATEWAY, "Identity provider request failed").into_response()
+            }
+        };
+
+        // The client only sees a generic message; the cause goes to the request's span.
+        if response.status().is_server_error() {
+            tracing::error!(error = %detail, "Request failed");
         }
+
+        response
     }
 }
 
@@ -95,6 +151,48 @@ impl From<ValidationErrors> for Error {
     }
 }
 
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+impl From<lettre::address::AddressError> for Error {
+    fn from(err: lettre::address::AddressError) -> Self {
+        Self::EmailAddress(err)
+    }
+}
+
+impl From<lettre::error::Error> for Error {
+    fn from(err: lettre::error::Error) -> Self {
+        Self::EmailBuild(err)
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for Error {
+    fn from(err: lettre::transport::smtp::Error) -> Self {
+        Self::EmailDelivery(err)
+    }
+}
+
+impl From<lettre::transport::file::Error> for Error {
+    fn from(err: lettre::transport::file::Error) -> Self {
+        Self::EmailFile(err)
+    }
+}
+
+impl From<minijinja::Error> for Error {
+    fn from(err: minijinja::Error) -> Self {
+        Self::Template(err)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
 impl From<std::string::String> for Error {
     fn from(err: std::string::String) -> Self {
         Self::ReadString(err)
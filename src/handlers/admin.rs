@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    middleware,
+    response::{Html, IntoResponse},
+    Extension, Json,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use validator::Validate;
+
+use crate::{
+    errors::ValidationResponse,
+    middleware::{role_check, JWTAuthMiddeware},
+    models::{
+        email::{EmailPreviewDto, EmailPreviewQueryDto, EmailTemplateInfo, TestEmailDto},
+        newsletter::{CreateIssueDto, NewsletterIssue, Subscriber, SubscribersQueryDto},
+        outbox::{OutboxEmail, OutboxQueryDto},
+        response::Response,
+        users::UserRole,
+    },
+    AppState, Result,
+};
+
+pub fn admin_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(list_outbox_emails))
+        .routes(routes!(get_outbox_email))
+        .routes(routes!(retry_outbox_email))
+        .routes(routes!(list_email_templates))
+        .routes(routes!(preview_email_template))
+        .routes(routes!(send_test_email))
+        .routes(routes!(list_subscribers))
+        .routes(routes!(list_newsletter_issues, send_newsletter_issue))
+        .layer(middleware::from_fn(|state, req, next| {
+            role_check(state, req, next, vec![UserRole::Admin])
+        }))
+}
+
+/// Queued and sent emails, newest first.
+#[utoipa::path(
+    get,
+    path = "/outbox",
+    tag = "admin",
+    params(OutboxQueryDto),
+    responses(
+        (status = 200, description = "Outbox emails", body = Vec<OutboxEmail>),
+        (status = 403, description = "Admins only"),
+    )
+)]
+async fn list_outbox_emails(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<OutboxQueryDto>,
+) -> Result<impl IntoResponse> {
+    let emails = app_state
+        .outbox_service
+        .list_emails(params.status, params.limit)
+        .await?;
+
+    Ok((StatusCode::OK, Json(emails)))
+}
+
+/// One outbox email.
+#[utoipa::path(
+    get,
+    path = "/outbox/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Outbox email id")),
+    responses(
+        (status = 200, description = "Outbox email", body = OutboxEmail),
+        (status = 403, description = "Admins only"),
+        (status = 404, description = "No such email"),
+    )
+)]
+async fn get_outbox_email(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(email_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let email = app_state.outbox_service.get_email(&email_id).await?;
+
+    Ok((StatusCode::OK, Json(email)))
+}
+
+/// Queue a failed email for another delivery attempt.
+#[utoipa::path(
+    post,
+    path = "/outbox/{id}/retry",
+    tag = "admin",
+    params(("id" = String, Path, description = "Outbox email id")),
+    responses(
+        (status = 200, description = "Email requeued", body = OutboxEmail),
+        (status = 403, description = "Admins only"),
+        (status = 404, description = "No such email"),
+    )
+)]
+async fn retry_outbox_email(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(email_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let email = app_state.outbox_service.retry_email(&email_id).await?;
+
+    Ok((StatusCode::OK, Json(email)))
+}
+
+/// Email templates and the locales each one is available in.
+#[utoipa::path(
+    get,
+    path = "/email-templates",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Templates", body = Vec<EmailTemplateInfo>),
+        (status = 403, description = "Admins only"),
+    )
+)]
+async fn list_email_templates(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    let templates = app_state.email_templates_service.list_templates();
+
+    Ok((StatusCode::OK, Json(templates)))
+}
+
+/// Render a template with sample data.
+#[utoipa::path(
+    get,
+    path = "/email-templates/{name}/preview",
+    tag = "admin",
+    params(
+        ("name" = String, Path, description = "Template name"),
+        EmailPreviewQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Rendered template", content(
+            (EmailPreviewDto = "application/json"),
+            (String = "text/html"),
+            (String = "text/plain"),
+        )),
+        (status = 403, description = "Admins only"),
+        (status = 404, description = "No such template"),
+    )
+)]
+async fn preview_email_template(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(template_name): Path<String>,
+    Query(params): Query<EmailPreviewQueryDto>,
+) -> Result<axum::response::Response> {
+    let preview = app_state
+        .email_templates_service
+        .preview(&template_name, params.locale.as_deref())?;
+
+    let response = match params.format.as_deref() {
+        Some("html") => Html(preview.html).into_response(),
+        Some("text") => preview.text.into_response(),
+        _ => Json(preview).into_response(),
+    };
+
+    Ok(response)
+}
+
+/// Send a template rendered with sample data to an address.
+#[utoipa::path(
+    post,
+    path = "/email-templates/{name}/test",
+    tag = "admin",
+    params(("name" = String, Path, description = "Template name")),
+    responses(
+        (status = 200, description = "Test email queued", body = Response),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+        (status = 403, description = "Admins only"),
+        (status = 404, description = "No such template"),
+    )
+)]
+async fn send_test_email(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(template_name): Path<String>,
+    Json(body): Json<TestEmailDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    app_state
+        .email_templates_service
+        .send_test(&template_name, &body.to, body.locale.as_deref())
+        .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: format!("Test email sent to {}", body.to),
+    }))
+}
+
+/// Newsletter subscribers.
+#[utoipa::path(
+    get,
+    path = "/newsletter/subscribers",
+    tag = "admin",
+    params(SubscribersQueryDto),
+    responses(
+        (status = 200, description = "Subscribers", body = Vec<Subscriber>),
+        (status = 403, description = "Admins only"),
+    )
+)]
+async fn list_subscribers(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<SubscribersQueryDto>,
+) -> Result<impl IntoResponse> {
+    let subscribers = app_state
+        .newsletter_service
+        .list_subscribers(params.status)
+        .await?;
+
+    Ok((StatusCode::OK, Json(subscribers)))
+}
+
+/// Newsletter issues sent so far.
+#[utoipa::path(
+    get,
+    path = "/newsletter/issues",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Issues", body = Vec<NewsletterIssue>),
+        (status = 403, description = "Admins only"),
+    )
+)]
+async fn list_newsletter_issues(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    let issues = app_state.newsletter_service.list_issues().await?;
+
+    Ok((StatusCode::OK, Json(issues)))
+}
+
+/// Send an issue to every confirmed subscriber.
+#[utoipa::path(
+    post,
+    path = "/newsletter/issues",
+    tag = "admin",
+    responses(
+        (status = 202, description = "Issue queued for delivery", body = NewsletterIssue),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+        (status = 403, description = "Admins only"),
+    )
+)]
+async fn send_newsletter_issue(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Extension(user): Extension<JWTAuthMiddeware>,
+    Json(body): Json<CreateIssueDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    let issue = app_state
+        .newsletter_service
+        .send_issue(
+            &body.subject,
+            &body.html_content,
+            &body.text_content,
+            user.user.id,
+        )
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(issue)))
+}
@@ -1,28 +1,33 @@
-use std::{env, sync::Arc};
+use std::sync::Arc;
 
 use axum::{
     body::Body,
-    extract::Query,
-    http::{
-        header::{self, ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE},
-        HeaderMap, HeaderName, Method, Request, StatusCode,
-    },
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderName, Method, Request, StatusCode},
     middleware::Next,
-    routing::{get, post},
-    Extension, Json, Router,
+    Extension, Json,
 };
-use tower_cookies::Cookie;
-use tower_http::cors::CorsLayer;
+use utoipa_axum::{router::OpenApiRouter, routes};
 use validator::Validate;
 
 use crate::{
-    mail::mails::send_verification_email,
+    errors::ValidationResponse,
+    handlers::{
+        docs::{DOCS_PATH, OPENAPI_PATH, REDOC_PATH},
+        health::{HEALTHZ_PATH, READYZ_PATH},
+        jwks::JWKS_PATH,
+        metrics::METRICS_PATH,
+        newsletter::UNSUBSCRIBE_PATH,
+        oidc::oidc_handler,
+    },
+    jwt::token::SessionToken,
+    middleware::client_ip::ClientIp,
     models::{
         query::VerifyEmailQueryDto,
         response::Response,
         users::{
-            ForgotPasswordRequestDto, LoginUserDto, RegisterUserDto, ResetPasswordRequestDto,
-            UserLoginResponseDto,
+            ForgotPasswordRequestDto, LoginUserDto, MagicLinkConsumeDto, MagicLinkRequestDto,
+            RegisterUserDto, ResendVerificationDto, ResetPasswordRequestDto, UserLoginResponseDto,
         },
     },
     AppState, Error, Result,
@@ -30,32 +35,60 @@ use crate::{
 
 use axum::response::IntoResponse;
 
-pub fn auth_handler() -> Router {
-    Router::new()
-        .route("/register", post(register))
-        .route("/login", post(login))
-        .route("/verify-email", get(verify_email))
-        .route("/forgot-password", post(forgot_password))
-        .route("/reset-password", post(reset_password))
+/// Paths reachable without `x-api-key`; `/metrics` checks its own token. Swagger UI's
+/// assets under `DOCS_PATH` are let through by prefix.
+const PUBLIC_PATHS: [&str; 7] = [
+    UNSUBSCRIBE_PATH,
+    JWKS_PATH,
+    METRICS_PATH,
+    HEALTHZ_PATH,
+    READYZ_PATH,
+    OPENAPI_PATH,
+    REDOC_PATH,
+];
+
+pub fn auth_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(register))
+        .routes(routes!(login))
+        .routes(routes!(request_magic_link))
+        .routes(routes!(consume_magic_link))
+        .routes(routes!(verify_email))
+        .routes(routes!(resend_verification))
+        .routes(routes!(forgot_password))
+        .routes(routes!(reset_password))
+        .routes(routes!(confirm_email_change))
+        .routes(routes!(cancel_email_change))
+        .nest("/oidc", oidc_handler())
 }
 
+/// Create an account and send the verification email.
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    responses(
+        (status = 201, description = "Account created", body = Response),
+        (status = 400, description = "Invalid input or email already in use", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
 pub async fn register(
     Extension(app_state): Extension<Arc<AppState>>,
     Json(new_user): Json<RegisterUserDto>,
 ) -> Result<impl IntoResponse> {
     new_user.validate()?;
 
-    let user = app_state
+    app_state
         .auth_service
-        .register(new_user.name, new_user.email, new_user.password)
+        .register(
+            new_user.name,
+            new_user.email,
+            new_user.password,
+            new_user.locale,
+        )
         .await?;
 
-    let token = user
-        .verification_token
-        .ok_or(Error::BadRequest("Invalid data".to_string()))?;
-
-    send_verification_email(&user.email, &user.name, &token).await?;
-
     Ok((
         StatusCode::CREATED,
         Json(Response {
@@ -66,39 +99,132 @@ pub async fn register(
     ))
 }
 
+/// Sign in with email and password.
+///
+/// Sets the session and CSRF cookies and also returns the session token for bearer use.
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Signed in", body = UserLoginResponseDto),
+        (status = 400, description = "Invalid input or credentials", body = ValidationResponse),
+        (status = 429, description = "Too many attempts or account locked", body = ValidationResponse),
+    )
+)]
 pub async fn login(
     Extension(app_state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(user): Json<LoginUserDto>,
 ) -> Result<impl IntoResponse> {
     user.validate()?;
 
     let token = app_state
         .auth_service
-        .login(&user.email, &user.password)
+        .login(&user.email, &user.password, ip)
         .await?;
 
-    let cookie_duration = time::Duration::minutes(app_state.config.jwt_maxage * 60);
-    let cookie = Cookie::build(("token", &token))
-        .path("/")
-        .max_age(cookie_duration)
-        .http_only(true)
-        .build();
+    Ok(login_response(&app_state, token))
+}
 
-    let response = Json(UserLoginResponseDto {
-        status: "success".to_string(),
-        token: token.clone(),
-    });
+/// Email a one-time sign-in link.
+#[utoipa::path(
+    post,
+    path = "/magic-link",
+    tag = "auth",
+    responses(
+        (status = 202, description = "Link sent if the account allows magic-link sign-in", body = Response),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
+pub async fn request_magic_link(
+    Extension(app_state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(body): Json<MagicLinkRequestDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
 
-    let mut headers = HeaderMap::new();
+    app_state
+        .auth_service
+        .request_magic_link(&body.email, ip)
+        .await?;
 
-    headers.append(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(Response {
+            status: "success",
+            message:
+                "If magic-link sign-in is enabled for this account, a sign-in link has been sent."
+                    .to_string(),
+        }),
+    ))
+}
 
-    let mut response = response.into_response();
+/// Exchange a magic-link token for a session.
+#[utoipa::path(
+    post,
+    path = "/magic-link/consume",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Signed in", body = UserLoginResponseDto),
+        (status = 400, description = "Invalid or expired token", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
+pub async fn consume_magic_link(
+    Extension(app_state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(body): Json<MagicLinkConsumeDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    let token = app_state
+        .auth_service
+        .consume_magic_link(&body.token, ip)
+        .await?;
+
+    Ok(login_response(&app_state, token))
+}
+
+pub fn login_response(app_state: &AppState, token: SessionToken) -> axum::response::Response {
+    let headers = session_cookies(app_state, &token);
+
+    let mut response = Json(UserLoginResponseDto {
+        status: "success".to_string(),
+        csrf_token: token.csrf_token(&app_state.config.auth.cookies),
+        token: token.token,
+    })
+    .into_response();
     response.headers_mut().extend(headers);
 
-    Ok(response)
+    response
 }
 
+fn session_cookies(app_state: &AppState, token: &SessionToken) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for cookie in [
+        token.cookie(&app_state.config.auth.cookies),
+        token.csrf_cookie(&app_state.config.auth.cookies),
+    ] {
+        headers.append(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    }
+
+    headers
+}
+
+/// Verify an email address and start a session.
+#[utoipa::path(
+    get,
+    path = "/verify-email",
+    tag = "auth",
+    params(VerifyEmailQueryDto),
+    responses(
+        (status = 200, description = "Email verified; session cookies set", body = Response),
+        (status = 400, description = "Invalid or expired token", body = ValidationResponse),
+    )
+)]
 pub async fn verify_email(
     Query(params): Query<VerifyEmailQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
@@ -109,32 +235,67 @@ pub async fn verify_email(
 
     let token = app_state.auth_service.verify_email(params.token).await?;
 
-    let cookie_duration = time::Duration::minutes(app_state.config.jwt_maxage * 60);
-    let cookie = Cookie::build(("token", token.clone()))
-        .path("/")
-        .max_age(cookie_duration)
-        .http_only(true)
-        .build();
-
-    let mut headers = HeaderMap::new();
-
-    headers.append(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    let headers = session_cookies(&app_state, &token);
 
     let response = Json(Response {
         status: "success",
         message: "Email verified successfully!".to_string(),
     });
 
-    Ok(response)
+    Ok((headers, response))
 }
 
+/// Send a new verification email.
+#[utoipa::path(
+    post,
+    path = "/resend-verification",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Sent if the account exists and is unverified", body = Response),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+    )
+)]
+pub async fn resend_verification(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(body): Json<ResendVerificationDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    app_state
+        .auth_service
+        .resend_verification(&body.email)
+        .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message:
+            "If the account exists and is not verified, a new verification email has been sent."
+                .to_string(),
+    }))
+}
+
+/// Email a password reset link.
+#[utoipa::path(
+    post,
+    path = "/forgot-password",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Reset link sent", body = Response),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
 pub async fn forgot_password(
     Extension(app_state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(email): Json<ForgotPasswordRequestDto>,
 ) -> Result<impl IntoResponse> {
     email.validate()?;
 
-    app_state.auth_service.forgot_password(email.email).await?;
+    app_state
+        .auth_service
+        .forgot_password(email.email, ip)
+        .await?;
 
     let response = Response {
         message: "Password reset link has been sent to your email.".to_string(),
@@ -144,8 +305,20 @@ pub async fn forgot_password(
     Ok(Json(response))
 }
 
+/// Set a new password using a reset token.
+#[utoipa::path(
+    post,
+    path = "/reset-password",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Password reset", body = Response),
+        (status = 400, description = "Invalid input or token", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
 pub async fn reset_password(
     Extension(app_state): Extension<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(body): Json<ResetPasswordRequestDto>,
 ) -> Result<impl IntoResponse> {
     body.validate()
@@ -153,7 +326,7 @@ pub async fn reset_password(
 
     app_state
         .auth_service
-        .reset_password(body.token, body.new_password)
+        .reset_password(body.token, body.new_password, ip)
         .await?;
 
     let response = Response {
@@ -164,31 +337,79 @@ pub async fn reset_password(
     Ok(Json(response))
 }
 
-pub fn configure_cors() -> CorsLayer {
-    let x_api_key = HeaderName::from_static("x-api-key");
-
-    CorsLayer::new()
-        .allow_origin(["https://nextlevelcode-blog.vercel.app"
-            .parse()
-            .expect("Invalid origin format")])
-        .allow_methods(vec![
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-        ])
-        .allow_headers(vec![AUTHORIZATION, CONTENT_TYPE, ACCEPT, x_api_key])
-        .allow_credentials(true)
-        .expose_headers(vec![CONTENT_DISPOSITION])
-        .max_age(std::time::Duration::from_secs(86400))
+/// Confirm a pending email change from the link sent to the new address.
+#[utoipa::path(
+    get,
+    path = "/confirm-email-change",
+    tag = "auth",
+    params(VerifyEmailQueryDto),
+    responses(
+        (status = 200, description = "Email changed", body = Response),
+        (status = 400, description = "Invalid or expired token", body = ValidationResponse),
+    )
+)]
+pub async fn confirm_email_change(
+    Query(params): Query<VerifyEmailQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    app_state
+        .auth_service
+        .confirm_email_change(&params.token)
+        .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Email changed successfully!".to_string(),
+    }))
+}
+
+/// Cancel an email change from the link sent to the old address; a change that was already
+/// confirmed is reverted to the old address.
+#[utoipa::path(
+    get,
+    path = "/cancel-email-change",
+    tag = "auth",
+    params(VerifyEmailQueryDto),
+    responses(
+        (status = 200, description = "Email change cancelled or reverted", body = Response),
+        (status = 400, description = "Invalid or expired token, or the old address is taken", body = ValidationResponse),
+    )
+)]
+pub async fn cancel_email_change(
+    Query(params): Query<VerifyEmailQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    app_state
+        .auth_service
+        .cancel_email_change(&params.token)
+        .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Email change cancelled.".to_string(),
+    }))
 }
 
 pub async fn require_api_key(
+    State(app_state): State<AppState>,
     req: Request<Body>,
     next: Next,
 ) -> std::result::Result<axum::response::Response, StatusCode> {
-    if req.method() == Method::OPTIONS {
+    let path = req.uri().path();
+    if req.method() == Method::OPTIONS
+        || PUBLIC_PATHS.contains(&path)
+        || path
+            .strip_prefix(DOCS_PATH)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    {
         return Ok(next.run(req).await);
     }
 
@@ -197,9 +418,7 @@ pub async fn require_api_key(
 
     match headers.get(&api_key_header) {
         Some(api_key_value) => {
-            let stored_key = env::var("API_KEY").unwrap_or_default();
-
-            if api_key_value.to_str().unwrap_or("") == stored_key {
+            if api_key_value.to_str().unwrap_or("") == app_state.api_key {
                 Ok(next.run(req).await)
             } else {
                 Err(StatusCode::UNAUTHORIZED)
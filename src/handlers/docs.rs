@@ -0,0 +1,106 @@
+use axum::{
+    http::{header, HeaderValue},
+    response::{Html, IntoResponse},
+    routing::get,
+    Router,
+};
+use tower_http::set_header::SetResponseHeaderLayer;
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiDocument,
+    },
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+pub const DOCS_PATH: &str = "/api/docs";
+pub const REDOC_PATH: &str = "/api/redoc";
+
+/// Both UIs style themselves inline and Redoc loads from its CDN and renders in a worker,
+/// none of which the default CSP allows.
+const DOCS_CSP: &str = "default-src 'none'; script-src 'self' https://cdn.redoc.ly; \
+    style-src 'self' 'unsafe-inline' https://fonts.googleapis.com; \
+    font-src https://fonts.gstatic.com; img-src 'self' data: https://cdn.redoc.ly; \
+    connect-src 'self'; worker-src blob:";
+
+const REDOC_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>API reference</title>
+  </head>
+  <body>
+    <redoc spec-url="/api/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.5.0/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "NextLevelCode Blog API",
+        description = "Every `/api` route needs the `x-api-key` header unless marked otherwise."
+    ),
+    modifiers(&ApiDocExtras),
+    security(("api_key" = [])),
+    tags(
+        (name = "auth", description = "Registration, sign-in and account recovery"),
+        (name = "oidc", description = "Sign-in through external identity providers"),
+        (name = "users", description = "The signed-in user's account"),
+        (name = "posts", description = "News posts and comments"),
+        (name = "videos", description = "Videos and their categories"),
+        (name = "media", description = "Image metadata and resizing"),
+        (name = "newsletter", description = "Newsletter subscriptions"),
+        (name = "admin", description = "Outbox, email templates and newsletter issues; admins only"),
+        (name = "system", description = "Health checks and signing keys"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the security schemes and drops the empty license utoipa copies from Cargo.toml.
+struct ApiDocExtras;
+
+impl Modify for ApiDocExtras {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-api-key"))),
+        );
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "The token from sign-in. Browsers can rely on the session cookie \
+                         instead, but then unsafe methods also need the `x-csrf-token` header.",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Serves the document with Swagger UI at `/api/docs` and Redoc at `/api/redoc`.
+pub fn docs_router(openapi: OpenApiDocument) -> Router {
+    Router::new()
+        .merge(SwaggerUi::new(DOCS_PATH).url(OPENAPI_PATH, openapi))
+        .route(REDOC_PATH, get(redoc))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(DOCS_CSP),
+        ))
+}
+
+async fn redoc() -> impl IntoResponse {
+    Html(REDOC_PAGE)
+}
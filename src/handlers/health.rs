@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+
+use crate::{errors::ValidationResponse, models::health::Readiness, AppState};
+
+pub const HEALTHZ_PATH: &str = "/healthz";
+pub const READYZ_PATH: &str = "/readyz";
+
+/// Liveness only says the process is serving requests; dependencies are `/readyz`'s job.
+#[utoipa::path(
+    get,
+    path = HEALTHZ_PATH,
+    tag = "system",
+    security(()),
+    responses((status = 200, description = "The process is up"))
+)]
+pub async fn healthz() -> impl IntoResponse {
+    StatusCode::OK
+}
+
+/// Whether the database, migrations, SMTP and storage are usable. Check results are reused for
+/// `HEALTH_CACHE_TTL`; shutting down is reported immediately.
+#[utoipa::path(
+    get,
+    path = READYZ_PATH,
+    tag = "system",
+    security(()),
+    responses(
+        (status = 200, description = "Ready for traffic", body = Readiness),
+        (status = 503, description = "A check failed or the server is shutting down", body = Readiness),
+        (status = 429, description = "Too many requests from this client", body = ValidationResponse),
+    )
+)]
+pub async fn readyz(Extension(app_state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    let readiness = app_state.health_service.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(readiness))
+}
@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use axum::{http::header, response::IntoResponse, Extension, Json};
+
+use crate::AppState;
+
+pub const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+/// Public keys for verifying session tokens.
+#[utoipa::path(
+    get,
+    path = JWKS_PATH,
+    tag = "system",
+    security(()),
+    responses((status = 200, description = "JSON Web Key Set", body = Object))
+)]
+pub async fn jwks(Extension(app_state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(app_state.keyring.jwks()),
+    )
+}
@@ -0,0 +1,143 @@
+use std::{io::Cursor, sync::Arc, time::Instant};
+
+use axum::{
+    extract::{Path as PathParam, Query},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use image::{ImageFormat, ImageReader};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    errors::ValidationResponse,
+    models::media::{ArticleImages, ImageMetadataResponse, ImageParams},
+    AppState, Result,
+};
+
+pub fn media_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_image_metadata))
+        .routes(routes!(get_article_images))
+}
+
+/// Dimensions, placeholders and responsive variants of an image under the assets directory.
+#[utoipa::path(
+    get,
+    path = "/images/{*path}",
+    tag = "media",
+    params(("path" = String, Path, description = "Image path relative to the assets directory; may contain `/`")),
+    responses(
+        (status = 200, description = "Image metadata", body = ImageMetadataResponse),
+        (status = 400, description = "Invalid image path", body = ValidationResponse),
+        (status = 404, description = "No such image"),
+    )
+)]
+async fn get_image_metadata(
+    Extension(app_state): Extension<Arc<AppState>>,
+    PathParam(path): PathParam<String>,
+) -> Result<impl IntoResponse> {
+    let metadata = app_state.media_service.image_metadata(&path).await?;
+
+    Ok((StatusCode::OK, Json(metadata)))
+}
+
+/// Metadata for every image in an article's directory.
+#[utoipa::path(
+    get,
+    path = "/articles/{slug}",
+    tag = "media",
+    params(("slug" = String, Path, description = "Article directory name")),
+    responses(
+        (status = 200, description = "Article images", body = ArticleImages),
+        (status = 400, description = "Invalid slug", body = ValidationResponse),
+        (status = 404, description = "No such article"),
+    )
+)]
+async fn get_article_images(
+    Extension(app_state): Extension<Arc<AppState>>,
+    PathParam(slug): PathParam<String>,
+) -> Result<impl IntoResponse> {
+    let article = app_state.media_service.article_images(&slug).await?;
+
+    Ok((StatusCode::OK, Json(article)))
+}
+
+/// Resize an asset and re-encode it as WebP, in the shape Next.js' image loader expects.
+#[utoipa::path(
+    get,
+    path = "/_next/image",
+    tag = "media",
+    params(ImageParams),
+    responses(
+        (status = 200, description = "Optimized image", content(("image/webp"))),
+        (status = 400, description = "Invalid URL encoding", body = String, content_type = "text/plain"),
+        (status = 403, description = "Path outside the assets directory", body = String, content_type = "text/plain"),
+        (status = 404, description = "No such image", body = String, content_type = "text/plain"),
+        (status = 422, description = "Not a decodable image", body = String, content_type = "text/plain"),
+    )
+)]
+pub async fn handle_image_optimization(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<ImageParams>,
+) -> std::result::Result<impl IntoResponse, (StatusCode, String)> {
+    let decoded_url = urlencoding::decode(&params.url)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid URL encoding".to_string()))?;
+
+    let file_path = app_state
+        .media_service
+        .asset_path(&decoded_url)
+        .ok_or((StatusCode::FORBIDDEN, "Invalid file path".to_string()))?;
+
+    let image_bytes = tokio::fs::read(app_state.media_service.assets_dir().join(&file_path))
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("File not found: {}", e)))?;
+
+    let start = Instant::now();
+    let mut img = ImageReader::new(Cursor::new(image_bytes))
+        .with_guessed_format()
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Image decode error: {}", e),
+            )
+        })?
+        .decode()
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Image processing error: {}", e),
+            )
+        })?;
+
+    if let Some(width) = params
+        .w
+        .map(|width| app_state.media_service.image_width(width))
+    {
+        img = img.resize(
+            width,
+            (width as f32 * (img.height() as f32 / img.width() as f32)) as u32,
+            image::imageops::FilterType::Lanczos3,
+        );
+    }
+
+    let mut output_buf = Cursor::new(Vec::new());
+    img.write_to(&mut output_buf, ImageFormat::WebP)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Image conversion error: {}", e),
+            )
+        })?;
+    metrics::histogram!("image_processing_duration_seconds", "operation" => "optimize")
+        .record(start.elapsed().as_secs_f64());
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", "image/webp".parse().unwrap());
+    headers.insert(
+        "cache-control",
+        "public, max-age=31536000, immutable".parse().unwrap(),
+    );
+
+    Ok((StatusCode::OK, headers, output_buf.into_inner()))
+}
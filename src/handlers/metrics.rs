@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::{
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use sqlx::PgPool;
+
+use crate::{supervisor::Shutdown, AppState, Error, Result};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The router served on `metrics.admin_port`.
+pub fn metrics_router(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(METRICS_PATH, get(metrics))
+        .layer(Extension(app_state))
+}
+
+/// Serves `app` on a copy of the bound `listener`, so a restart reuses the same port.
+pub async fn serve_metrics(
+    listener: Arc<std::net::TcpListener>,
+    app: Router,
+    mut shutdown: Shutdown,
+) {
+    let listener = match listener
+        .try_clone()
+        .and_then(tokio::net::TcpListener::from_std)
+    {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!(error = ?err, "Failed to reuse the metrics listener");
+            return;
+        }
+    };
+
+    if let Err(err) = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await
+    {
+        tracing::error!(error = ?err, "Metrics listener stopped");
+    }
+}
+
+pub async fn metrics(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse> {
+    let handle = app_state.metrics.as_ref().ok_or(Error::NotFound)?;
+
+    if let Some(token) = &app_state.config.metrics.token {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        if bearer != Some(token.as_str()) {
+            return Err(Error::Unauthorized);
+        }
+    }
+
+    record_pool_usage(
+        &app_state.db_pool,
+        app_state.config.database.max_connections,
+    );
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    ))
+}
+
+fn record_pool_usage(pool: &PgPool, max_connections: u32) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(idle);
+    metrics::gauge!("db_pool_connections", "state" => "in_use").set(size.saturating_sub(idle));
+    metrics::gauge!("db_pool_max_connections").set(max_connections);
+}
@@ -1,4 +1,12 @@
+pub mod admin;
 pub mod auth;
+pub mod docs;
+pub mod health;
+pub mod jwks;
+pub mod media;
+pub mod metrics;
 pub mod news_post;
+pub mod newsletter;
+pub mod oidc;
 pub mod user;
 pub mod videos;
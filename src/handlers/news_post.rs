@@ -1,42 +1,50 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::Path,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
-    Extension, Json, Router,
-};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use utoipa_axum::{router::OpenApiRouter, router::UtoipaMethodRouterExt, routes};
 
 use crate::{
-    models::news_post::{CreateNewsPostDto, PostCommentDto, UpdateNewsPost, UpdatePostCommentDto},
+    errors::ValidationResponse,
+    middleware::rate_limit::RateLimitLayer,
+    models::news_post::{
+        CreateNewsPostDto, NewsPost, PostCommentDto, PostCommentWithComments, UpdateNewsPost,
+        UpdatePostCommentDto,
+    },
     AppState, Result,
 };
 
-pub fn news_posts_handler() -> Router {
-    Router::new()
-        .route("/get-posts", get(get_posts))
-        .route(
-            "/get-all-posts-with-comments",
-            get(get_all_posts_with_comments),
-        )
-        .route(
-            "/get-posts-with-comments/{id}",
-            get(get_posts_with_comments),
-        )
-        .route("/create-post/{id}", post(create_post))
-        .route("/update-post/{id}", put(update_post))
-        .route("/delete-post/{id}", delete(delete_post))
-        .route("/create-comment/{id}", post(create_comment))
-        .route("/update-comment/{id}", put(update_comment))
-        .route("/delete-comment/{id}", delete(delete_comment))
+pub fn news_posts_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_posts))
+        .routes(routes!(get_all_posts_with_comments))
+        .routes(routes!(get_posts_with_comments))
+        .routes(routes!(create_post))
+        .routes(routes!(update_post))
+        .routes(routes!(delete_post))
+        .routes(routes!(create_comment).layer(RateLimitLayer::new("comments")))
+        .routes(routes!(update_comment).layer(RateLimitLayer::new("comments")))
+        .routes(routes!(delete_comment))
 }
 
+/// All posts, newest first.
+#[utoipa::path(
+    get,
+    path = "/get-posts",
+    tag = "posts",
+    responses((status = 200, description = "Posts", body = Vec<NewsPost>))
+)]
 async fn get_posts(Extension(app_state): Extension<Arc<AppState>>) -> Result<impl IntoResponse> {
     let posts = app_state.news_post_service.get_news_posts().await?;
     Ok((StatusCode::OK, Json(posts)))
 }
 
+/// All posts with their comments.
+#[utoipa::path(
+    get,
+    path = "/get-all-posts-with-comments",
+    tag = "posts",
+    responses((status = 200, description = "Posts with comments", body = Vec<PostCommentWithComments>))
+)]
 async fn get_all_posts_with_comments(
     Extension(app_state): Extension<Arc<AppState>>,
 ) -> Result<impl IntoResponse> {
@@ -48,6 +56,14 @@ async fn get_all_posts_with_comments(
     Ok((StatusCode::OK, Json(posts)))
 }
 
+/// One post with its comments.
+#[utoipa::path(
+    get,
+    path = "/get-posts-with-comments/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Post id")),
+    responses((status = 200, description = "Post with comments", body = PostCommentWithComments))
+)]
 async fn get_posts_with_comments(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(post_id): Path<String>,
@@ -60,6 +76,14 @@ async fn get_posts_with_comments(
     Ok((StatusCode::OK, Json(posts)))
 }
 
+/// Create a post.
+#[utoipa::path(
+    post,
+    path = "/create-post/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Id of the post's author, not of the post")),
+    responses((status = 201, description = "Post created"))
+)]
 async fn create_post(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(author_id): Path<String>,
@@ -72,6 +96,14 @@ async fn create_post(
     Ok(StatusCode::CREATED)
 }
 
+/// Update a post; omitted fields are left unchanged.
+#[utoipa::path(
+    put,
+    path = "/update-post/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Post id")),
+    responses((status = 201, description = "Post updated"))
+)]
 async fn update_post(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(news_post_id): Path<String>,
@@ -83,12 +115,21 @@ async fn update_post(
             &news_post_id,
             update_news_post.url.as_deref(),
             update_news_post.description.as_deref(),
+            update_news_post.cover_image.as_deref(),
         )
         .await?;
 
     Ok(StatusCode::CREATED)
 }
 
+/// Delete a post.
+#[utoipa::path(
+    delete,
+    path = "/delete-post/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Post id")),
+    responses((status = 204, description = "Post deleted"))
+)]
 async fn delete_post(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(post_id): Path<String>,
@@ -101,6 +142,17 @@ async fn delete_post(
     Ok((StatusCode::NO_CONTENT, "successes"))
 }
 
+/// Comment on a post; the post id goes in the body.
+#[utoipa::path(
+    post,
+    path = "/create-comment/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Id of the comment's author, not of the post")),
+    responses(
+        (status = 200, description = "Comment created"),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
 async fn create_comment(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(author_id): Path<String>,
@@ -119,6 +171,17 @@ async fn create_comment(
     Ok(StatusCode::OK)
 }
 
+/// Edit a comment.
+#[utoipa::path(
+    put,
+    path = "/update-comment/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Comment id")),
+    responses(
+        (status = 200, description = "Comment updated"),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
 async fn update_comment(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(comment_id): Path<String>,
@@ -132,6 +195,14 @@ async fn update_comment(
     Ok(StatusCode::OK)
 }
 
+/// Delete a comment.
+#[utoipa::path(
+    delete,
+    path = "/delete-comment/{id}",
+    tag = "posts",
+    params(("id" = String, Path, description = "Comment id")),
+    responses((status = 200, description = "Comment deleted"))
+)]
 async fn delete_comment(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(comment_id): Path<String>,
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use validator::Validate;
+
+use crate::{
+    errors::ValidationResponse,
+    models::{
+        digest::DigestFrequencyUpdateDto,
+        newsletter::{SubscribeDto, Subscriber},
+        query::VerifyEmailQueryDto,
+        response::Response,
+    },
+    AppState, Error, Result,
+};
+
+pub const UNSUBSCRIBE_PATH: &str = "/api/newsletter/unsubscribe";
+
+pub fn newsletter_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(subscribe))
+        .routes(routes!(confirm_subscription))
+        .routes(routes!(unsubscribe))
+        .routes(routes!(update_preferences))
+}
+
+/// Subscribe an address; it has to confirm from the email it receives.
+#[utoipa::path(
+    post,
+    path = "/subscribe",
+    tag = "newsletter",
+    responses(
+        (status = 202, description = "Confirmation email sent", body = Response),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+        (status = 429, description = "Rate limited", body = ValidationResponse),
+    )
+)]
+async fn subscribe(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(body): Json<SubscribeDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    app_state
+        .newsletter_service
+        .subscribe(&body.email, body.locale.as_deref())
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(Response {
+            status: "success",
+            message: "Please check your email to confirm your subscription.".to_string(),
+        }),
+    ))
+}
+
+/// Confirm a subscription from the emailed link.
+#[utoipa::path(
+    get,
+    path = "/confirm",
+    tag = "newsletter",
+    params(VerifyEmailQueryDto),
+    responses(
+        (status = 200, description = "Subscription confirmed", body = Response),
+        (status = 400, description = "Invalid or expired token", body = ValidationResponse),
+    )
+)]
+async fn confirm_subscription(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<VerifyEmailQueryDto>,
+) -> Result<impl IntoResponse> {
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    app_state.newsletter_service.confirm(&params.token).await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Subscription confirmed!".to_string(),
+    }))
+}
+
+/// Unsubscribe using the token from any newsletter email. Mail clients call this for
+/// one-click unsubscribe, so it needs no API key.
+#[utoipa::path(
+    post,
+    path = "/unsubscribe",
+    tag = "newsletter",
+    params(VerifyEmailQueryDto),
+    security(()),
+    responses(
+        (status = 200, description = "Unsubscribed", body = Response),
+        (status = 400, description = "Invalid token", body = ValidationResponse),
+    )
+)]
+async fn unsubscribe(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<VerifyEmailQueryDto>,
+) -> Result<impl IntoResponse> {
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    app_state
+        .newsletter_service
+        .unsubscribe(&params.token)
+        .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "You have been unsubscribed.".to_string(),
+    }))
+}
+
+/// Change how often a subscriber receives the digest.
+#[utoipa::path(
+    put,
+    path = "/preferences",
+    tag = "newsletter",
+    params(VerifyEmailQueryDto),
+    responses(
+        (status = 200, description = "Preferences updated", body = Subscriber),
+        (status = 400, description = "Invalid token", body = ValidationResponse),
+    )
+)]
+async fn update_preferences(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(params): Query<VerifyEmailQueryDto>,
+    Json(body): Json<DigestFrequencyUpdateDto>,
+) -> Result<impl IntoResponse> {
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    let subscriber = app_state
+        .newsletter_service
+        .update_digest_frequency(&params.token, body.frequency)
+        .await?;
+
+    Ok((StatusCode::OK, Json(subscriber)))
+}
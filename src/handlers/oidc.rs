@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use axum::{extract::Path, http::header, response::IntoResponse, Extension, Json};
+use axum_extra::extract::CookieJar;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use validator::Validate;
+
+use crate::{
+    errors::ValidationResponse,
+    handlers::auth::login_response,
+    jwt::token::{build_cookie, cookie_name},
+    models::{
+        oidc::{OidcAuthorizationResponseDto, OidcCallbackDto, OidcProvidersResponseDto},
+        users::UserLoginResponseDto,
+    },
+    AppState, Result,
+};
+
+/// Ties a sign-in to the browser that started it; the callback must carry the same state.
+pub const OIDC_STATE_COOKIE: &str = "oidc_state";
+
+pub fn oidc_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(list_providers))
+        .routes(routes!(authorize))
+        .routes(routes!(callback))
+}
+
+/// List the configured identity providers.
+#[utoipa::path(
+    get,
+    path = "/providers",
+    tag = "oidc",
+    responses((status = 200, description = "Provider names", body = OidcProvidersResponseDto))
+)]
+pub async fn list_providers(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(OidcProvidersResponseDto {
+        status: "success",
+        providers: app_state.oidc_service.providers(),
+    }))
+}
+
+/// Start a sign-in with the provider and return the URL to redirect the browser to.
+#[utoipa::path(
+    get,
+    path = "/{provider}/authorize",
+    tag = "oidc",
+    params(("provider" = String, Path, description = "Provider name from `/providers`")),
+    responses(
+        (status = 200, description = "Authorization URL; sets the sign-in state cookie", body = OidcAuthorizationResponseDto),
+        (status = 404, description = "Unknown provider"),
+    )
+)]
+pub async fn authorize(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(provider): Path<String>,
+) -> Result<impl IntoResponse> {
+    let authorization = app_state.oidc_service.authorization_url(&provider).await?;
+    let cookie = build_cookie(
+        &app_state.config.auth.cookies,
+        OIDC_STATE_COOKIE,
+        authorization.state,
+        true,
+        time::Duration::minutes(app_state.oidc_service.state_ttl_minutes()),
+    );
+
+    Ok((
+        [(header::SET_COOKIE, cookie.to_string())],
+        Json(OidcAuthorizationResponseDto {
+            status: "success",
+            authorization_url: authorization.url,
+        }),
+    ))
+}
+
+/// Finish a provider sign-in with the code and state from the redirect.
+#[utoipa::path(
+    post,
+    path = "/{provider}/callback",
+    tag = "oidc",
+    params(("provider" = String, Path, description = "Provider name from `/providers`")),
+    responses(
+        (status = 200, description = "Signed in", body = UserLoginResponseDto),
+        (status = 400, description = "Invalid code or state, or the state cookie is missing", body = ValidationResponse),
+        (status = 404, description = "Unknown provider"),
+    )
+)]
+pub async fn callback(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(provider): Path<String>,
+    jar: CookieJar,
+    Json(body): Json<OidcCallbackDto>,
+) -> Result<impl IntoResponse> {
+    body.validate()?;
+
+    let cookies = &app_state.config.auth.cookies;
+    let browser_state = jar
+        .get(&cookie_name(cookies, OIDC_STATE_COOKIE))
+        .map(|cookie| cookie.value().to_string());
+
+    let token = app_state
+        .oidc_service
+        .sign_in(&provider, &body.code, &body.state, browser_state.as_deref())
+        .await?;
+
+    let mut response = login_response(&app_state, token);
+    let expired = build_cookie(
+        cookies,
+        OIDC_STATE_COOKIE,
+        String::new(),
+        true,
+        time::Duration::ZERO,
+    );
+    response
+        .headers_mut()
+        .append(header::SET_COOKIE, expired.to_string().parse().unwrap());
+
+    Ok(response)
+}
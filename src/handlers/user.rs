@@ -1,47 +1,55 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::Path,
-    http::StatusCode,
-    middleware,
-    response::IntoResponse,
-    routing::{delete, get, put},
-    Extension, Json, Router,
-};
+use axum::{extract::Path, http::StatusCode, middleware, response::IntoResponse, Extension, Json};
+use utoipa_axum::{router::OpenApiRouter, router::UtoipaMethodRouterExt, routes};
 use validator::Validate;
 
 use crate::{
+    errors::ValidationResponse,
     middleware::{role_check, JWTAuthMiddeware},
     models::{
+        digest::DigestFrequencyUpdateDto,
         response::Response,
         users::{
-            FilterUserDto, NameUpdateDto, UserData, UserPasswordUpdateDto, UserResponseDto,
-            UserRole,
+            EmailUpdateDto, FilterUserDto, LocaleUpdateDto, MagicLinkUpdateDto, NameUpdateDto,
+            UserData, UserPasswordUpdateDto, UserResponseDto, UserRole,
         },
     },
     AppState, Result,
 };
 
-pub fn users_handler() -> Router {
-    Router::new()
-        .route(
-            "/me",
-            get(get_me).layer(middleware::from_fn(|state, req, next| {
+pub fn users_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(
+            routes!(get_me).layer(middleware::from_fn(|state, req, next| {
                 role_check(state, req, next, vec![UserRole::Admin, UserRole::User])
             })),
         )
-        .route(
-            "/users",
-            get(get_users).layer(middleware::from_fn(|state, req, next| {
+        .routes(
+            routes!(get_users).layer(middleware::from_fn(|state, req, next| {
                 role_check(state, req, next, vec![UserRole::Admin])
             })),
         )
-        .route("/delete/{id}", delete(delete_user))
-        .route("/update-username", put(update_user_name))
-        .route("/role", put(update_user_role))
-        .route("/update-password", put(update_user_password))
+        .routes(routes!(delete_user))
+        .routes(routes!(update_user_name))
+        .routes(routes!(update_user_role))
+        .routes(routes!(update_user_password))
+        .routes(routes!(update_user_locale))
+        .routes(routes!(update_user_digest))
+        .routes(routes!(update_user_email))
+        .routes(routes!(update_user_magic_link))
 }
 
+/// The signed-in user.
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "Current user", body = UserResponseDto),
+        (status = 403, description = "Role not allowed"),
+    )
+)]
 async fn get_me(
     Extension(_app_state): Extension<Arc<AppState>>,
     Extension(user): Extension<JWTAuthMiddeware>,
@@ -58,6 +66,14 @@ async fn get_me(
     Ok(Json(response_data))
 }
 
+/// Delete a user account.
+#[utoipa::path(
+    delete,
+    path = "/delete/{id}",
+    tag = "users",
+    params(("id" = String, Path, description = "User id")),
+    responses((status = 204, description = "User deleted"))
+)]
 async fn delete_user(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(user_id): Path<String>,
@@ -67,10 +83,30 @@ async fn delete_user(
     Ok((StatusCode::NO_CONTENT, "Deleted"))
 }
 
+/// List users. Not implemented yet; always returns an empty 200.
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    responses(
+        (status = 200, description = "Empty response"),
+        (status = 403, description = "Admins only"),
+    )
+)]
 async fn get_users() {
     // Get all users
 }
 
+/// Change the signed-in user's display name.
+#[utoipa::path(
+    put,
+    path = "/update-username",
+    tag = "users",
+    responses(
+        (status = 200, description = "Name updated"),
+        (status = 400, description = "Invalid input or wrong password", body = ValidationResponse),
+    )
+)]
 pub async fn update_user_name(
     Extension(app_state): Extension<Arc<AppState>>,
     Extension(user): Extension<JWTAuthMiddeware>,
@@ -86,10 +122,27 @@ pub async fn update_user_name(
     Ok(StatusCode::OK)
 }
 
+/// Change a user's role. Not implemented yet; always returns an empty 200.
+#[utoipa::path(
+    put,
+    path = "/role",
+    tag = "users",
+    responses((status = 200, description = "Empty response"))
+)]
 async fn update_user_role() {
     // Update the user's role
 }
 
+/// Change the signed-in user's password.
+#[utoipa::path(
+    put,
+    path = "/update-password",
+    tag = "users",
+    responses(
+        (status = 200, description = "Password updated", body = Response),
+        (status = 400, description = "Invalid input or wrong old password", body = ValidationResponse),
+    )
+)]
 pub async fn update_user_password(
     Extension(app_state): Extension<Arc<AppState>>,
     Extension(user): Extension<JWTAuthMiddeware>,
@@ -109,3 +162,99 @@ pub async fn update_user_password(
 
     Ok(Json(response))
 }
+
+/// Change the locale used for the signed-in user's emails.
+#[utoipa::path(
+    put,
+    path = "/locale",
+    tag = "users",
+    responses(
+        (status = 200, description = "Locale updated"),
+        (status = 400, description = "Invalid input", body = ValidationResponse),
+    )
+)]
+pub async fn update_user_locale(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Extension(user): Extension<JWTAuthMiddeware>,
+    Json(locale_update): Json<LocaleUpdateDto>,
+) -> Result<impl IntoResponse> {
+    locale_update.validate()?;
+
+    app_state
+        .users_service
+        .update_locale(&user.user, &locale_update.locale)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Change how often the signed-in user receives the digest.
+#[utoipa::path(
+    put,
+    path = "/digest",
+    tag = "users",
+    responses((status = 200, description = "Digest frequency updated"))
+)]
+pub async fn update_user_digest(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Extension(user): Extension<JWTAuthMiddeware>,
+    Json(digest_update): Json<DigestFrequencyUpdateDto>,
+) -> Result<impl IntoResponse> {
+    app_state
+        .users_service
+        .update_digest_frequency(&user.user, digest_update.frequency)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Turn magic-link sign-in on or off for the signed-in user.
+#[utoipa::path(
+    put,
+    path = "/magic-link",
+    tag = "users",
+    responses((status = 200, description = "Setting updated"))
+)]
+pub async fn update_user_magic_link(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Extension(user): Extension<JWTAuthMiddeware>,
+    Json(magic_link_update): Json<MagicLinkUpdateDto>,
+) -> Result<impl IntoResponse> {
+    app_state
+        .users_service
+        .update_magic_link(&user.user, magic_link_update.enabled)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Start an email change; the new address has to confirm it.
+#[utoipa::path(
+    put,
+    path = "/email",
+    tag = "users",
+    responses(
+        (status = 202, description = "Confirmation sent to the new address", body = Response),
+        (status = 400, description = "Invalid input, wrong password or email in use", body = ValidationResponse),
+    )
+)]
+pub async fn update_user_email(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Extension(user): Extension<JWTAuthMiddeware>,
+    Json(email_update): Json<EmailUpdateDto>,
+) -> Result<impl IntoResponse> {
+    email_update.validate()?;
+
+    app_state
+        .auth_service
+        .request_email_change(&user.user, &email_update.password, &email_update.new_email)
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(Response {
+            status: "success",
+            message: "Please check your new email to confirm the change.".to_string(),
+        }),
+    ))
+}
@@ -1,34 +1,33 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::Path,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
-    Extension, Json, Router,
-};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    models::query::{CategoryDto, CategoryName, UpdateVideoDto, VideoDto},
+    models::query::{CategoryDto, CategoryName, ResponseVideo, UpdateVideoDto, Video, VideoDto},
     AppState, Result,
 };
 
-pub fn videos_handler() -> Router {
-    Router::new()
-        .route("/videos", get(get_videos))
-        .route("/create-video", post(create_video))
-        .route("/update-video/{id}", put(update_video))
-        .route("/delete-video/{id}", delete(delete_video))
-        .route("/add-category-video/{id}", post(add_category_to_video))
-        .route("/get-video/{id}", get(get_video_by_youtube_id))
-        .route(
-            "/remove-category-video/{id}",
-            delete(remove_category_from_video),
-        )
-        .route("/create-category", post(create_category))
-        .route("/delete-category", post(delete_category))
+pub fn videos_handler() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_videos))
+        .routes(routes!(create_video))
+        .routes(routes!(update_video))
+        .routes(routes!(delete_video))
+        .routes(routes!(add_category_to_video))
+        .routes(routes!(get_video_by_youtube_id))
+        .routes(routes!(remove_category_from_video))
+        .routes(routes!(create_category))
+        .routes(routes!(delete_category))
 }
 
+/// Add a video.
+#[utoipa::path(
+    post,
+    path = "/create-video",
+    tag = "videos",
+    responses((status = 201, description = "Video created"))
+)]
 async fn create_video(
     Extension(app_state): Extension<Arc<AppState>>,
     Json(create_video): Json<VideoDto>,
@@ -46,6 +45,14 @@ async fn create_video(
     Ok(StatusCode::CREATED)
 }
 
+/// Look up a video by its YouTube id.
+#[utoipa::path(
+    get,
+    path = "/get-video/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "YouTube video id")),
+    responses((status = 200, description = "Video", body = ResponseVideo))
+)]
 async fn get_video_by_youtube_id(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(youtube_id): Path<String>,
@@ -57,12 +64,27 @@ async fn get_video_by_youtube_id(
     Ok((StatusCode::OK, Json(video)))
 }
 
+/// All videos with their categories.
+#[utoipa::path(
+    get,
+    path = "/videos",
+    tag = "videos",
+    responses((status = 200, description = "Videos", body = Vec<Video>))
+)]
 async fn get_videos(Extension(app_state): Extension<Arc<AppState>>) -> Result<impl IntoResponse> {
     let videos = app_state.videos_service.videos().await?;
 
     Ok((StatusCode::OK, Json(videos)))
 }
 
+/// Remove a category from a video.
+#[utoipa::path(
+    delete,
+    path = "/remove-category-video/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "Video id")),
+    responses((status = 200, description = "Category removed"))
+)]
 async fn remove_category_from_video(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(video_id): Path<String>,
@@ -76,6 +98,14 @@ async fn remove_category_from_video(
     Ok(())
 }
 
+/// Add a category to a video.
+#[utoipa::path(
+    post,
+    path = "/add-category-video/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "Video id")),
+    responses((status = 200, description = "Category added"))
+)]
 async fn add_category_to_video(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(video_id): Path<String>,
@@ -89,6 +119,14 @@ async fn add_category_to_video(
     Ok(StatusCode::OK)
 }
 
+/// Update a video; omitted fields are left unchanged.
+#[utoipa::path(
+    put,
+    path = "/update-video/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "Video id")),
+    responses((status = 200, description = "The submitted changes", body = UpdateVideoDto))
+)]
 async fn update_video(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(video_id): Path<String>,
@@ -108,6 +146,14 @@ async fn update_video(
     Ok((StatusCode::OK, Json(update_video)))
 }
 
+/// Delete a video.
+#[utoipa::path(
+    delete,
+    path = "/delete-video/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "Video id")),
+    responses((status = 204, description = "Video deleted"))
+)]
 async fn delete_video(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(video_id): Path<String>,
@@ -116,6 +162,13 @@ async fn delete_video(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Create a video category.
+#[utoipa::path(
+    post,
+    path = "/create-category",
+    tag = "videos",
+    responses((status = 201, description = "Category created"))
+)]
 async fn create_category(
     Extension(app_state): Extension<Arc<AppState>>,
     Json(category): Json<CategoryName>,
@@ -128,6 +181,17 @@ async fn create_category(
     Ok(StatusCode::CREATED)
 }
 
+/// Delete a video category.
+#[utoipa::path(
+    delete,
+    path = "/delete-category/{id}",
+    tag = "videos",
+    params(("id" = String, Path, description = "Category id")),
+    responses(
+        (status = 204, description = "Category deleted"),
+        (status = 404, description = "No such category")
+    )
+)]
 async fn delete_category(
     Extension(app_state): Extension<Arc<AppState>>,
     Path(category_id): Path<String>,
@@ -137,5 +201,5 @@ async fn delete_category(
         .delete_category(&category_id)
         .await?;
 
-    Ok(StatusCode::CREATED)
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,458 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{
+    rsa::PublicKeyComponents,
+    signature::{Ed25519KeyPair, KeyPair, RsaKeyPair},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{config::JwtConfig, Error, Result};
+
+const SECRET_KID: &str = "secret";
+
+/// `JWT_KEYRING` manifest. Key files are PEM private keys relative to the manifest; the
+/// public halves are published in the JWKS.
+///
+/// ```json
+/// {
+///   "active": "2025-05",
+///   "keys": [
+///     { "kid": "2025-05", "algorithm": "EdDSA", "file": "2025-05.pem" },
+///     { "kid": "2025-01", "algorithm": "RS256", "file": "2025-01.pem", "retiredAt": "2025-05-01T00:00:00Z" }
+///   ]
+/// }
+/// ```
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    active: String,
+    keys: Vec<ManifestKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestKey {
+    kid: String,
+    algorithm: Algorithm,
+    file: String,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+struct VerifyingKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: Option<Jwk>,
+    valid_until: Option<DateTime<Utc>>,
+}
+
+/// Signs tokens with the active key and verifies them against every key that is still valid.
+/// Retired keys keep verifying for the grace period, so rotating keys does not end sessions.
+pub struct Keyring {
+    kid: String,
+    algorithm: Algorithm,
+    signing_key: EncodingKey,
+    keys: HashMap<String, VerifyingKey>,
+    issuer: String,
+    audience: String,
+    leeway_secs: u64,
+}
+
+impl Keyring {
+    /// Without `JWT_KEYRING`, tokens are signed with the HS256 `JWT_SECRET_KEY` and the
+    /// JWKS stays empty.
+    pub fn load(config: &JwtConfig) -> Result<Self> {
+        let Some(manifest_path) = config.keyring.as_deref().map(Path::new) else {
+            let keys = HashMap::from([(
+                SECRET_KID.to_string(),
+                VerifyingKey {
+                    algorithm: Algorithm::HS256,
+                    key: DecodingKey::from_secret(config.secret.as_bytes()),
+                    jwk: None,
+                    valid_until: None,
+                },
+            )]);
+
+            return Ok(Self {
+                kid: SECRET_KID.to_string(),
+                algorithm: Algorithm::HS256,
+                signing_key: EncodingKey::from_secret(config.secret.as_bytes()),
+                keys,
+                issuer: config.issuer.clone(),
+                audience: config.audience.clone(),
+                leeway_secs: config.leeway.as_secs(),
+            });
+        };
+
+        let manifest = fs::read_to_string(manifest_path)
+            .map_err(|err| format!("Failed to read {}: {}", manifest_path.display(), err))?;
+        let manifest: Manifest = serde_json::from_str(&manifest)
+            .map_err(|err| format!("Invalid keyring {}: {}", manifest_path.display(), err))?;
+        let key_dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+        let mut signing_key = None;
+        let mut keys = HashMap::new();
+
+        for entry in manifest.keys {
+            let pem = fs::read(key_dir.join(&entry.file))
+                .map_err(|err| format!("Failed to read JWT key {}: {}", entry.file, err))?;
+            let (encoding_key, jwk) = load_key(&entry, &pem)
+                .map_err(|err| format!("Invalid JWT key {}: {}", entry.kid, err))?;
+
+            if entry.kid == manifest.active {
+                if entry.retired_at.is_some() {
+                    return Err(format!("Active JWT key {} is marked as retired", entry.kid).into());
+                }
+                signing_key = Some((entry.algorithm, encoding_key));
+            }
+
+            let verifying_key = VerifyingKey {
+                algorithm: entry.algorithm,
+                key: DecodingKey::from_jwk(&jwk)
+                    .map_err(|err| format!("Invalid JWT key {}: {}", entry.kid, err))?,
+                jwk: Some(jwk),
+                valid_until: entry.retired_at.map(|retired_at| {
+                    retired_at + Duration::seconds(config.retired_key_grace_secs)
+                }),
+            };
+
+            if keys.insert(entry.kid.clone(), verifying_key).is_some() {
+                return Err(format!("Duplicate JWT key id {}", entry.kid).into());
+            }
+        }
+
+        let (algorithm, signing_key) = signing_key.ok_or(format!(
+            "Active JWT key {} is not in the keyring",
+            manifest.active
+        ))?;
+
+        Ok(Self {
+            kid: manifest.active,
+            algorithm,
+            signing_key,
+            keys,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            leeway_secs: config.leeway.as_secs(),
+        })
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.kid.clone());
+
+        encode(&header, claims, &self.signing_key).map_err(|_| Error::InternalServerError)
+    }
+
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T> {
+        let header = decode_header(token).map_err(|_| Error::Unauthorized)?;
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.keys.get(kid))
+            .filter(|key| key.is_valid(Utc::now()))
+            .ok_or(Error::Unauthorized)?;
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.leeway = self.leeway_secs;
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+
+        decode::<T>(token, &key.key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| Error::Unauthorized)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        let mut keys: Vec<_> = self
+            .keys
+            .values()
+            .filter(|key| key.is_valid(now))
+            .filter_map(|key| key.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+
+        JwkSet { keys }
+    }
+}
+
+impl VerifyingKey {
+    fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.valid_until.is_none_or(|valid_until| now < valid_until)
+    }
+}
+
+fn load_key(entry: &ManifestKey, pem: &[u8]) -> std::result::Result<(EncodingKey, Jwk), String> {
+    let parsed = pem::parse(pem).map_err(|err| err.to_string())?;
+    let der = parsed.contents();
+
+    let (encoding_key, key_algorithm, algorithm) = match entry.algorithm {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {
+            let key_pair = match parsed.tag() {
+                "RSA PRIVATE KEY" => RsaKeyPair::from_der(der),
+                _ => RsaKeyPair::from_pkcs8(der),
+            }
+            .map_err(|err| err.to_string())?;
+            let public = PublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+            let key_algorithm = match entry.algorithm {
+                Algorithm::RS384 => KeyAlgorithm::RS384,
+                Algorithm::RS512 => KeyAlgorithm::RS512,
+                _ => KeyAlgorithm::RS256,
+            };
+
+            (
+                EncodingKey::from_rsa_pem(pem).map_err(|err| err.to_string())?,
+                key_algorithm,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(public.n),
+                    e: URL_SAFE_NO_PAD.encode(public.e),
+                }),
+            )
+        }
+        Algorithm::EdDSA => {
+            let key_pair =
+                Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).map_err(|err| err.to_string())?;
+
+            (
+                EncodingKey::from_ed_der(der),
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                }),
+            )
+        }
+        algorithm => return Err(format!("unsupported algorithm {:?}", algorithm)),
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(entry.kid.clone()),
+            ..Default::default()
+        },
+        algorithm,
+    };
+
+    Ok((encoding_key, jwk))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ring::rand::SystemRandom;
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Only ever used to sign test tokens.
+    const RSA_KEY: &str = include_str!("test-rsa-key.pem");
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        iss: String,
+        aud: String,
+        exp: i64,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "reader".to_string(),
+            iss: "https://api.example.com".to_string(),
+            aud: "https://api.example.com".to_string(),
+            exp: (Utc::now() + Duration::minutes(5)).timestamp(),
+        }
+    }
+
+    fn config(keyring: Option<PathBuf>, retired_key_grace_secs: i64) -> JwtConfig {
+        JwtConfig {
+            secret: "test-secret".to_string(),
+            keyring: keyring.map(|path| path.to_string_lossy().to_string()),
+            issuer: "https://api.example.com".to_string(),
+            audience: "https://api.example.com".to_string(),
+            retired_key_grace_secs,
+            ..JwtConfig::default()
+        }
+    }
+
+    fn ed25519_pem() -> String {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref().to_vec()))
+    }
+
+    /// Writes a manifest with `keys` as `(kid, algorithm, pem, retired_at)` into a fresh
+    /// directory and returns its path.
+    fn manifest(active: &str, keys: &[(&str, &str, &str, Option<DateTime<Utc>>)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("keyring-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let entries: Vec<_> = keys
+            .iter()
+            .map(|(kid, algorithm, pem, retired_at)| {
+                fs::write(dir.join(format!("{kid}.pem")), pem).unwrap();
+                serde_json::json!({
+                    "kid": kid,
+                    "algorithm": algorithm,
+                    "file": format!("{kid}.pem"),
+                    "retiredAt": retired_at,
+                })
+            })
+            .collect();
+
+        let path = dir.join("keyring.json");
+        fs::write(
+            &path,
+            serde_json::json!({ "active": active, "keys": entries }).to_string(),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn every_supported_algorithm_signs_and_verifies() {
+        let ed25519 = ed25519_pem();
+
+        let hs256 = Keyring::load(&config(None, 0)).unwrap();
+        let token = hs256.sign(&claims()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::HS256);
+        assert_eq!(hs256.verify::<TestClaims>(&token).unwrap(), claims());
+        assert!(hs256.jwks().keys.is_empty());
+
+        for (algorithm, expected, pem) in [
+            ("RS256", Algorithm::RS256, RSA_KEY),
+            ("RS384", Algorithm::RS384, RSA_KEY),
+            ("RS512", Algorithm::RS512, RSA_KEY),
+            ("EdDSA", Algorithm::EdDSA, ed25519.as_str()),
+        ] {
+            let path = manifest("current", &[("current", algorithm, pem, None)]);
+            let keyring = Keyring::load(&config(Some(path), 0)).unwrap();
+
+            let token = keyring.sign(&claims()).unwrap();
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.alg, expected);
+            assert_eq!(header.kid.as_deref(), Some("current"));
+            assert_eq!(keyring.verify::<TestClaims>(&token).unwrap(), claims());
+
+            let jwks = keyring.jwks();
+            assert_eq!(jwks.keys.len(), 1);
+            assert_eq!(jwks.keys[0].common.key_id.as_deref(), Some("current"));
+        }
+    }
+
+    #[test]
+    fn retired_keys_verify_only_within_the_grace_period() {
+        let old_pem = ed25519_pem();
+        let new_pem = ed25519_pem();
+        let before_rotation = Keyring::load(&config(
+            Some(manifest("old", &[("old", "EdDSA", &old_pem, None)])),
+            0,
+        ))
+        .unwrap();
+        let token = before_rotation.sign(&claims()).unwrap();
+
+        let rotated = |grace_secs: i64| {
+            let path = manifest(
+                "new",
+                &[
+                    ("new", "EdDSA", &new_pem, None),
+                    (
+                        "old",
+                        "EdDSA",
+                        &old_pem,
+                        Some(Utc::now() - Duration::hours(1)),
+                    ),
+                ],
+            );
+            Keyring::load(&config(Some(path), grace_secs)).unwrap()
+        };
+
+        let within_grace = rotated(2 * 60 * 60);
+        assert_eq!(within_grace.verify::<TestClaims>(&token).unwrap(), claims());
+        assert_eq!(within_grace.jwks().keys.len(), 2);
+
+        let after_grace = rotated(30 * 60);
+        assert!(matches!(
+            after_grace.verify::<TestClaims>(&token),
+            Err(Error::Unauthorized)
+        ));
+        assert_eq!(after_grace.jwks().keys.len(), 1);
+        assert_eq!(
+            after_grace.jwks().keys[0].common.key_id.as_deref(),
+            Some("new")
+        );
+    }
+
+    #[test]
+    fn unknown_key_ids_are_rejected() {
+        let keyring = Keyring::load(&config(
+            Some(manifest(
+                "current",
+                &[("current", "EdDSA", &ed25519_pem(), None)],
+            )),
+            0,
+        ))
+        .unwrap();
+        let stranger = Keyring::load(&config(
+            Some(manifest(
+                "stranger",
+                &[("stranger", "EdDSA", &ed25519_pem(), None)],
+            )),
+            0,
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            keyring.verify::<TestClaims>(&stranger.sign(&claims()).unwrap()),
+            Err(Error::Unauthorized)
+        ));
+
+        // Same kid, different key: the signature check still has to fail.
+        let impostor = Keyring::load(&config(
+            Some(manifest(
+                "current",
+                &[("current", "EdDSA", &ed25519_pem(), None)],
+            )),
+            0,
+        ))
+        .unwrap();
+        assert!(matches!(
+            keyring.verify::<TestClaims>(&impostor.sign(&claims()).unwrap()),
+            Err(Error::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn an_active_key_that_is_retired_or_missing_is_rejected() {
+        let pem = ed25519_pem();
+
+        let retired = manifest("current", &[("current", "EdDSA", &pem, Some(Utc::now()))]);
+        assert!(Keyring::load(&config(Some(retired), 0)).is_err());
+
+        let missing = manifest("absent", &[("current", "EdDSA", &pem, None)]);
+        assert!(Keyring::load(&config(Some(missing), 0)).is_err());
+    }
+}
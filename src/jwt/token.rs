@@ -0,0 +1,260 @@
+use std::sync::Arc;
+
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    config::{CookieConfig, CookieSameSite, JwtConfig},
+    middleware::csrf::csrf_token,
+    models::users::{User, UserRole},
+    Error, Result,
+};
+
+use super::keyring::Keyring;
+
+pub const SESSION_COOKIE: &str = "token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// With `COOKIE_HOST_PREFIX`, cookies are named `__Host-<name>` so browsers pin them to the
+/// exact API host over HTTPS.
+pub fn cookie_name(config: &CookieConfig, name: &str) -> String {
+    if config.host_prefix {
+        format!("__Host-{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub role: UserRole,
+    /// Session id, unique per issued token.
+    pub sid: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+pub struct SessionToken {
+    pub token: String,
+    pub claims: Claims,
+}
+
+impl SessionToken {
+    /// The session cookie lives exactly as long as the JWT inside it.
+    pub fn cookie(&self, config: &CookieConfig) -> Cookie<'static> {
+        self.build_cookie(config, SESSION_COOKIE, self.token.clone(), true)
+    }
+
+    /// Readable by the frontend, which echoes it back in `X-CSRF-Token`.
+    pub fn csrf_cookie(&self, config: &CookieConfig) -> Cookie<'static> {
+        self.build_cookie(config, CSRF_COOKIE, self.csrf_token(config), false)
+    }
+
+    pub fn csrf_token(&self, config: &CookieConfig) -> String {
+        csrf_token(&config.csrf_secret, self.claims.sid)
+    }
+
+    fn build_cookie(
+        &self,
+        config: &CookieConfig,
+        name: &str,
+        value: String,
+        http_only: bool,
+    ) -> Cookie<'static> {
+        let mut cookie = build_cookie(
+            config,
+            name,
+            value,
+            http_only,
+            time::Duration::seconds(self.claims.exp - self.claims.iat),
+        );
+
+        if let Ok(expires) = OffsetDateTime::from_unix_timestamp(self.claims.exp) {
+            cookie.set_expires(expires);
+        }
+
+        cookie
+    }
+}
+
+/// A cookie with the configured security attributes.
+pub fn build_cookie(
+    config: &CookieConfig,
+    name: &str,
+    value: String,
+    http_only: bool,
+    max_age: time::Duration,
+) -> Cookie<'static> {
+    let same_site = match config.same_site {
+        CookieSameSite::Strict => SameSite::Strict,
+        CookieSameSite::Lax => SameSite::Lax,
+        CookieSameSite::None => SameSite::None,
+    };
+
+    let mut cookie = Cookie::build((cookie_name(config, name), value))
+        .path("/")
+        .max_age(max_age)
+        .http_only(http_only)
+        .secure(config.secure)
+        .same_site(same_site)
+        .build();
+
+    if let Some(domain) = &config.domain {
+        cookie.set_domain(domain.clone());
+    }
+
+    cookie
+}
+
+#[derive(Clone)]
+pub struct SessionTokens {
+    keyring: Arc<Keyring>,
+    ttl: chrono::Duration,
+}
+
+impl SessionTokens {
+    pub fn new(keyring: Arc<Keyring>, config: &JwtConfig) -> Self {
+        Self {
+            keyring,
+            ttl: chrono::Duration::from_std(config.session_ttl)
+                .unwrap_or(chrono::Duration::hours(1)),
+        }
+    }
+
+    pub fn issue(&self, user: &User) -> Result<SessionToken> {
+        self.issue_at(user, Utc::now())
+    }
+
+    fn issue_at(&self, user: &User, now: DateTime<Utc>) -> Result<SessionToken> {
+        let claims = Claims {
+            sub: user.id,
+            role: user.role,
+            sid: Uuid::now_v7(),
+            iss: self.keyring.issuer().to_string(),
+            aud: self.keyring.audience().to_string(),
+            iat: now.timestamp(),
+            exp: (now + self.ttl).timestamp(),
+        };
+
+        Ok(SessionToken {
+            token: self.keyring.sign(&claims)?,
+            claims,
+        })
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims> {
+        self.keyring
+            .verify::<Claims>(token)
+            .map_err(|_| Error::Unauthorized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn config(session_ttl: Duration) -> JwtConfig {
+        JwtConfig {
+            secret: "test-secret".to_string(),
+            keyring: None,
+            issuer: "https://api.example.com".to_string(),
+            audience: "https://api.example.com".to_string(),
+            retired_key_grace_secs: 0,
+            session_ttl,
+            leeway: Duration::from_secs(30),
+        }
+    }
+
+    fn cookie_config() -> CookieConfig {
+        CookieConfig {
+            secure: true,
+            same_site: CookieSameSite::Lax,
+            domain: None,
+            host_prefix: true,
+            csrf_secret: "test-csrf-secret".to_string(),
+        }
+    }
+
+    fn tokens(session_ttl: Duration) -> SessionTokens {
+        let config = config(session_ttl);
+        let keyring = Keyring::load(&config).unwrap();
+
+        SessionTokens::new(Arc::new(keyring), &config)
+    }
+
+    fn user() -> User {
+        User {
+            id: Uuid::now_v7(),
+            name: "reader".to_string(),
+            email: "reader@example.com".to_string(),
+            password: String::new(),
+            role: UserRole::Admin,
+            verified: true,
+            locale: "en".to_string(),
+            magic_link_enabled: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn cookie_and_jwt_expire_together() {
+        for secs in [90, 15 * 60, 60 * 60, 12 * 60 * 60, 7 * 24 * 60 * 60] {
+            let tokens = tokens(Duration::from_secs(secs));
+            let issued = tokens.issue(&user()).unwrap();
+            let claims = tokens.verify(&issued.token).unwrap();
+
+            assert_eq!(claims.exp - claims.iat, secs as i64);
+
+            for cookie in [
+                issued.cookie(&cookie_config()),
+                issued.csrf_cookie(&cookie_config()),
+            ] {
+                assert_eq!(cookie.max_age(), Some(time::Duration::seconds(secs as i64)));
+                assert_eq!(
+                    cookie
+                        .expires_datetime()
+                        .map(|expires| expires.unix_timestamp()),
+                    Some(claims.exp)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn claims_carry_role_and_unique_session_id() {
+        let tokens = tokens(Duration::from_secs(60 * 60));
+        let user = user();
+
+        let first = tokens.verify(&tokens.issue(&user).unwrap().token).unwrap();
+        let second = tokens.verify(&tokens.issue(&user).unwrap().token).unwrap();
+
+        assert_eq!(first.sub, user.id);
+        assert_eq!(first.role, UserRole::Admin);
+        assert_ne!(first.sid, second.sid);
+    }
+
+    #[test]
+    fn expired_tokens_are_accepted_only_within_leeway() {
+        let tokens = tokens(Duration::from_secs(60));
+        let user = user();
+
+        let within = tokens
+            .issue_at(&user, Utc::now() - chrono::Duration::seconds(80))
+            .unwrap();
+        let beyond = tokens
+            .issue_at(&user, Utc::now() - chrono::Duration::seconds(120))
+            .unwrap();
+
+        assert!(tokens.verify(&within.token).is_ok());
+        assert!(tokens.verify(&beyond.token).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::{authentication::Credentials, PoolConfig},
+    AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    config::{MailConfig, MailTransport},
+    Error, Result,
+};
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    fn from(&self) -> &Mailbox;
+    async fn send(&self, message: Message) -> Result<()>;
+
+    /// Used by readiness checks; transports without a remote end are always reachable.
+    async fn check_connection(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub fn build_mailer(config: &MailConfig) -> Result<Arc<dyn Mailer>> {
+    let from: Mailbox = config.from.parse()?;
+
+    let mailer: Arc<dyn Mailer> = match config.transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::new(config, from)?),
+        MailTransport::File => Arc::new(FileMailer::new(&config.file_dir, from)?),
+        MailTransport::Memory => Arc::new(MemoryMailer::new(from)),
+    };
+
+    Ok(mailer)
+}
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig, from: Mailbox) -> Result<Self> {
+        let creds = Credentials::new(config.smtp_username.clone(), config.smtp_password.clone());
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)?
+            .credentials(creds)
+            .port(config.smtp_port)
+            .pool_config(PoolConfig::new().max_size(config.smtp_pool_size))
+            .build();
+
+        Ok(Self { from, transport })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+
+    async fn check_connection(&self) -> Result<()> {
+        if !self.transport.test_connection().await? {
+            return Err(Error::EmailUnavailable(
+                "SMTP server did not answer NOOP".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct FileMailer {
+    from: Mailbox,
+    transport: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileMailer {
+    pub fn new(dir: &str, from: Mailbox) -> Result<Self> {
+        std::fs::create_dir_all(dir).map_err(Error::EmailDirectory)?;
+
+        Ok(Self {
+            from,
+            transport: AsyncFileTransport::new(dir),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+pub struct MemoryMailer {
+    from: Mailbox,
+    messages: Mutex<Vec<Message>>,
+}
+
+impl MemoryMailer {
+    pub fn new(from: Mailbox) -> Self {
+        Self {
+            from,
+            messages: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn sent(&self) -> Vec<Message> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
@@ -1,56 +1,318 @@
-use std::env;
+use minijinja::{context, Value};
 
-use crate::Result;
+use crate::{
+    models::digest::{DigestFrequency, DigestPost, DigestRecipient, DigestVideo},
+    Result,
+};
 
-use super::sendmail::send_email;
+use super::{
+    sendmail::{render_email, OutgoingEmail},
+    templates::TemplateEngine,
+};
 
-pub async fn send_verification_email(to_email: &str, username: &str, token: &str) -> Result<()> {
-    let subject = "Email Verification";
-    let template_path = "src/mail/templates/Verification-email.html";
-    let base_url = &format!(
-        "{}/confirm-auth/verify-email",
-        env::var("FRONT_URL").expect("FRONT_URL must be set")
-    );
+pub fn verification_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    username: &str,
+    token: &str,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let base_url = &format!("{}/confirm-auth/verify-email", templates.front_url());
     let verification_link = create_verification_link(base_url, token);
-    let login_url = env::var("FRONT_URL").expect("FRONT_URL must be set");
-    let placeholders = vec![
-        ("{{username}}".to_string(), username.to_string()),
-        ("{{verification_link}}".to_string(), verification_link),
-        ("{{login_url}}".to_string(), login_url),
-    ];
+    let login_url = templates.front_url();
 
-    send_email(to_email, subject, template_path, &placeholders).await
+    render_email(
+        templates,
+        to_email,
+        "Verification-email",
+        locale,
+        context! { username, verification_link, login_url },
+    )
 }
 
 fn create_verification_link(base_url: &str, token: &str) -> String {
     format!("{}?token={}", base_url, token)
 }
 
-pub async fn send_welcome_email(to_email: &str, username: &str) -> Result<()> {
-    let subject = "Welcome to Application";
-    let template_path = "src/mail/templates/Welcome-email.html";
-    let x_url = "x.com/next_level_code".to_string();
-    let github_url = "https://github.com/m4rc3l04ugu2t0".to_string();
-    let placeholders = vec![
-        ("{{username}}".to_string(), username.to_string()),
-        ("{{x_url}}".to_string(), x_url.to_string()),
-        ("{{github_url}}".to_string(), github_url.to_string()),
-    ];
+pub fn welcome_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    username: &str,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let x_url = "x.com/next_level_code";
+    let github_url = "https://github.com/m4rc3l04ugu2t0";
 
-    send_email(to_email, subject, template_path, &placeholders).await
+    render_email(
+        templates,
+        to_email,
+        "Welcome-email",
+        locale,
+        context! { username, x_url, github_url },
+    )
 }
 
-pub async fn send_forgot_password_email(
+pub fn forgot_password_email(
+    templates: &TemplateEngine,
     to_email: &str,
     reset_link: &str,
     username: &str,
-) -> Result<()> {
-    let subject = "Rest your Password";
-    let template_path = "src/mail/templates/RestPassword-email.html";
-    let placeholders = vec![
-        ("{{username}}".to_string(), username.to_string()),
-        ("{{reset_link}}".to_string(), reset_link.to_string()),
-    ];
-
-    send_email(to_email, subject, template_path, &placeholders).await
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    render_email(
+        templates,
+        to_email,
+        "RestPassword-email",
+        locale,
+        context! { username, reset_link },
+    )
+}
+
+pub fn magic_link_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    username: &str,
+    token: &str,
+    expires_in_minutes: i64,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let magic_link = format!(
+        "{}/confirm-auth/magic-link?token={}",
+        templates.front_url(),
+        token
+    );
+
+    render_email(
+        templates,
+        to_email,
+        "MagicLink-email",
+        locale,
+        context! { username, magic_link, expires_in_minutes },
+    )
+}
+
+pub fn account_locked_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    username: &str,
+    locked_minutes: i64,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let reset_link = format!("{}/forgot-password", templates.front_url());
+
+    render_email(
+        templates,
+        to_email,
+        "AccountLocked-email",
+        locale,
+        context! { username, locked_minutes, reset_link },
+    )
+}
+
+pub fn email_change_confirmation_email(
+    templates: &TemplateEngine,
+    username: &str,
+    new_email: &str,
+    token: &str,
+    expires_in_hours: i64,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let confirm_link = format!(
+        "{}/confirm-auth/confirm-email-change?token={}",
+        templates.front_url(),
+        token
+    );
+
+    render_email(
+        templates,
+        new_email,
+        "EmailChange-confirm",
+        locale,
+        context! { username, new_email, confirm_link, expires_in_hours },
+    )
+}
+
+pub fn email_change_notice_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    username: &str,
+    new_email: &str,
+    token: &str,
+    expires_in_hours: i64,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let cancel_link = format!(
+        "{}/confirm-auth/cancel-email-change?token={}",
+        templates.front_url(),
+        token
+    );
+
+    render_email(
+        templates,
+        to_email,
+        "EmailChange-notice",
+        locale,
+        context! { username, new_email, cancel_link, expires_in_hours },
+    )
+}
+
+pub fn newsletter_confirmation_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    token: &str,
+    expires_in_hours: i64,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    let confirm_link = format!(
+        "{}/newsletter/confirm?token={}",
+        templates.front_url(),
+        token
+    );
+
+    render_email(
+        templates,
+        to_email,
+        "Newsletter-confirm",
+        locale,
+        context! { confirm_link, expires_in_hours },
+    )
+}
+
+pub fn newsletter_issue_email(
+    templates: &TemplateEngine,
+    to_email: &str,
+    subject: &str,
+    html_content: &str,
+    text_content: &str,
+    unsubscribe_link: &str,
+    locale: &str,
+) -> Result<OutgoingEmail> {
+    render_email(
+        templates,
+        to_email,
+        "Newsletter-issue",
+        locale,
+        context! { subject, html_content, text_content, unsubscribe_link },
+    )
+}
+
+pub fn digest_email(
+    templates: &TemplateEngine,
+    recipient: &DigestRecipient,
+    posts: &[DigestPost],
+    videos: &[DigestVideo],
+    preferences_link: &str,
+    unsubscribe_link: &str,
+) -> Result<OutgoingEmail> {
+    let frequency = match recipient.frequency {
+        DigestFrequency::Daily => "daily",
+        _ => "weekly",
+    };
+    let posts: Vec<Value> = posts
+        .iter()
+        .map(|post| {
+            context! {
+                url => post.url,
+                description => post.description,
+                author_name => post.author_name,
+            }
+        })
+        .collect();
+    let videos: Vec<Value> = videos
+        .iter()
+        .map(|video| {
+            context! {
+                title => video.title,
+                url => format!("https://www.youtube.com/watch?v={}", video.youtube_id),
+                duration => video.duration,
+            }
+        })
+        .collect();
+
+    render_email(
+        templates,
+        &recipient.email,
+        "Digest-email",
+        &recipient.locale,
+        context! {
+            name => recipient.name,
+            frequency,
+            posts,
+            videos,
+            preferences_link,
+            unsubscribe_link,
+        },
+    )
+}
+
+pub fn sample_context(template_name: &str, front_url: &str) -> Option<Value> {
+    let username = "Ada Lovelace";
+
+    let context = match template_name {
+        "Verification-email" => context! {
+            username,
+            verification_link => format!("{}/confirm-auth/verify-email?token=sample-token", front_url),
+            login_url => front_url,
+        },
+        "Welcome-email" => context! {
+            username,
+            x_url => "x.com/next_level_code",
+            github_url => "https://github.com/m4rc3l04ugu2t0",
+        },
+        "RestPassword-email" => context! {
+            username,
+            reset_link => format!("{}/confirm-auth/reset-password?token=sample-token", front_url),
+        },
+        "Newsletter-confirm" => context! {
+            confirm_link => format!("{}/newsletter/confirm?token=sample-token", front_url),
+            expires_in_hours => 48,
+        },
+        "Newsletter-issue" => context! {
+            subject => "What's new this month",
+            html_content => "<p>Fresh posts and videos are up on the blog.</p>",
+            text_content => "Fresh posts and videos are up on the blog.",
+            unsubscribe_link => format!("{}/newsletter/unsubscribe?token=sample-token", front_url),
+        },
+        "MagicLink-email" => context! {
+            username,
+            magic_link => format!("{}/confirm-auth/magic-link?token=sample-token", front_url),
+            expires_in_minutes => 15,
+        },
+        "AccountLocked-email" => context! {
+            username,
+            locked_minutes => 15,
+            reset_link => format!("{}/forgot-password", front_url),
+        },
+        "EmailChange-confirm" => context! {
+            username,
+            new_email => "ada@example.com",
+            confirm_link => format!("{}/confirm-auth/confirm-email-change?token=sample-token", front_url),
+            expires_in_hours => 24,
+        },
+        "EmailChange-notice" => context! {
+            username,
+            new_email => "ada@example.com",
+            cancel_link => format!("{}/confirm-auth/cancel-email-change?token=sample-token", front_url),
+            expires_in_hours => 24,
+        },
+        "Digest-email" => context! {
+            name => username,
+            frequency => "weekly",
+            posts => vec![context! {
+                url => "https://nextlevelcode-blog.vercel.app/posts/sample",
+                description => "Setting up a Bitcoin wallet",
+                author_name => username,
+            }],
+            videos => vec![context! {
+                title => "Arch Linux install guide",
+                url => "https://www.youtube.com/watch?v=sample",
+                duration => "12:34",
+            }],
+            preferences_link => format!("{}/newsletter/preferences?token=sample-token", front_url),
+            unsubscribe_link => format!("{}/newsletter/unsubscribe?token=sample-token", front_url),
+        },
+        _ => return None,
+    };
+
+    Some(context)
 }
@@ -1,2 +1,4 @@
+pub mod mailer;
 pub mod mails;
 pub mod sendmail;
+pub mod templates;
@@ -1,56 +1,120 @@
-use crate::Result;
+use std::collections::BTreeMap;
+
+use crate::{Error, Result};
 use lettre::{
-    message::{header, SinglePart},
-    transport::smtp::authentication::Credentials,
-    Message, SmtpTransport, Transport,
+    message::{
+        header::{HeaderName, HeaderValue},
+        Mailbox, MultiPart, SinglePart,
+    },
+    Message,
 };
-use std::{env::var, fs::read_to_string};
+use minijinja::Value;
+
+use super::{mailer::Mailer, templates::TemplateEngine};
 
-pub async fn send_email(
+#[derive(Debug, Clone)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: Option<String>,
+    pub headers: BTreeMap<String, String>,
+}
+
+pub fn render_email(
+    templates: &TemplateEngine,
     to: &str,
-    subject: &str,
-    template_path: &str,
-    placeholders: &[(String, String)],
-) -> Result<()> {
-    let smtp_username = var("SMTP_USERNAME").expect("SMTP_USERNAME must be set");
-    let smtp_password = var("SMTP_PASSWORD").expect("SMTP_PASSWORD must be set");
-    let smtp_server = var("SMTP_SERVER").expect("SMTP_SERVER must be set");
-    let smtp_port: u16 = var("SMTP_PORT")
-        .expect("SMPT_PORT must be set")
-        .parse()
-        .unwrap();
-
-    let mut html_template = read_to_string(template_path).unwrap();
-
-    for (k, v) in placeholders {
-        html_template = html_template.replace(k, v);
+    template_name: &str,
+    locale: &str,
+    context: Value,
+) -> Result<OutgoingEmail> {
+    let rendered = templates.render(template_name, locale, context)?;
+
+    Ok(OutgoingEmail {
+        to: to.to_string(),
+        subject: rendered.subject,
+        html_body: rendered.html,
+        text_body: Some(rendered.text),
+        headers: BTreeMap::new(),
+    })
+}
+
+pub fn build_message(from: &Mailbox, email: &OutgoingEmail) -> Result<Message> {
+    let builder = Message::builder()
+        .from(from.clone())
+        .to(email.to.parse()?)
+        .subject(&email.subject);
+
+    let mut message = match &email.text_body {
+        Some(text_body) => builder.multipart(MultiPart::alternative_plain_html(
+            text_body.clone(),
+            email.html_body.clone(),
+        ))?,
+        None => builder.singlepart(SinglePart::html(email.html_body.clone()))?,
+    };
+
+    for (name, value) in &email.headers {
+        let name = HeaderName::new_from_ascii(name.clone())
+            .map_err(|_| Error::BadRequest(format!("Invalid email header: {}", name)))?;
+        message
+            .headers_mut()
+            .insert_raw(HeaderValue::new(name, value.clone()));
     }
 
-    let email = Message::builder()
-        .from(smtp_username.parse().unwrap())
-        .to(to.parse().unwrap())
-        .subject(subject)
-        .header(header::ContentType::TEXT_HTML)
-        .singlepart(
-            SinglePart::builder()
-                .header(header::ContentType::TEXT_HTML)
-                .body(html_template),
-        )
-        .unwrap();
-
-    let creds = Credentials::new(smtp_username.clone(), smtp_password.clone());
-    let mailer = SmtpTransport::starttls_relay(&smtp_server)
-        .unwrap()
-        .credentials(creds)
-        .port(smtp_port)
-        .build();
-
-    let result = mailer.send(&email);
-
-    match result {
-        Ok(_) => println!("Email sent successfully!"),
-        Err(e) => println!("Failed to send email: {:?}", e),
+    Ok(message)
+}
+
+pub async fn send_email(mailer: &dyn Mailer, email: &OutgoingEmail) -> Result<()> {
+    let message = build_message(mailer.from(), email)?;
+
+    mailer.send(message).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_headers_reach_the_message() {
+        let from: Mailbox = "Blog <noreply@example.com>".parse().unwrap();
+        let email = OutgoingEmail {
+            to: "reader@example.com".to_string(),
+            subject: "Weekly digest".to_string(),
+            html_body: "<p>Hi</p>".to_string(),
+            text_body: Some("Hi".to_string()),
+            headers: BTreeMap::from([
+                (
+                    "List-Unsubscribe".to_string(),
+                    "<https://api.example.com/unsubscribe?token=abc>".to_string(),
+                ),
+                (
+                    "List-Unsubscribe-Post".to_string(),
+                    "List-Unsubscribe=One-Click".to_string(),
+                ),
+            ]),
+        };
+
+        let message = build_message(&from, &email).unwrap();
+
+        assert_eq!(
+            message.headers().get_raw("List-Unsubscribe"),
+            Some("<https://api.example.com/unsubscribe?token=abc>")
+        );
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n"));
     }
 
-    Ok(())
+    #[test]
+    fn invalid_header_names_are_rejected() {
+        let from: Mailbox = "noreply@example.com".parse().unwrap();
+        let email = OutgoingEmail {
+            to: "reader@example.com".to_string(),
+            subject: "Hi".to_string(),
+            html_body: "<p>Hi</p>".to_string(),
+            text_body: None,
+            headers: BTreeMap::from([("Bad Header".to_string(), "x".to_string())]),
+        };
+
+        assert!(build_message(&from, &email).is_err());
+    }
 }
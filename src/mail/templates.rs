@@ -0,0 +1,148 @@
+use minijinja::{context, path_loader, Environment, Template, Value};
+
+use crate::Result;
+
+pub const DEFAULT_LOCALE: &str = "en";
+pub const LOCALES: [&str; 2] = ["en", "pt-BR"];
+pub const TEMPLATE_NAMES: [&str; 10] = [
+    "Verification-email",
+    "Welcome-email",
+    "RestPassword-email",
+    "Newsletter-confirm",
+    "Newsletter-issue",
+    "Digest-email",
+    "EmailChange-confirm",
+    "EmailChange-notice",
+    "MagicLink-email",
+    "AccountLocked-email",
+];
+
+macro_rules! embedded_templates {
+    ($($path:literal),* $(,)?) => {
+        &[$(($path, include_str!(concat!("templates/", $path)))),*]
+    };
+}
+
+const EMBEDDED_TEMPLATES: &[(&str, &str)] = embedded_templates![
+    "layout.html",
+    "layout.txt",
+    "en/Verification-email.html",
+    "en/Verification-email.txt",
+    "en/Welcome-email.html",
+    "en/Welcome-email.txt",
+    "en/RestPassword-email.html",
+    "en/RestPassword-email.txt",
+    "en/Newsletter-confirm.html",
+    "en/Newsletter-confirm.txt",
+    "en/Newsletter-issue.html",
+    "en/Newsletter-issue.txt",
+    "en/Digest-email.html",
+    "en/Digest-email.txt",
+    "en/EmailChange-confirm.html",
+    "en/EmailChange-confirm.txt",
+    "en/EmailChange-notice.html",
+    "en/EmailChange-notice.txt",
+    "en/MagicLink-email.html",
+    "en/MagicLink-email.txt",
+    "en/AccountLocked-email.html",
+    "en/AccountLocked-email.txt",
+    "pt-BR/Verification-email.html",
+    "pt-BR/Verification-email.txt",
+    "pt-BR/Welcome-email.html",
+    "pt-BR/Welcome-email.txt",
+    "pt-BR/RestPassword-email.html",
+    "pt-BR/RestPassword-email.txt",
+    "pt-BR/Newsletter-confirm.html",
+    "pt-BR/Newsletter-confirm.txt",
+    "pt-BR/Newsletter-issue.html",
+    "pt-BR/Newsletter-issue.txt",
+    "pt-BR/Digest-email.html",
+    "pt-BR/Digest-email.txt",
+    "pt-BR/EmailChange-confirm.html",
+    "pt-BR/EmailChange-confirm.txt",
+    "pt-BR/EmailChange-notice.html",
+    "pt-BR/EmailChange-notice.txt",
+    "pt-BR/MagicLink-email.html",
+    "pt-BR/MagicLink-email.txt",
+    "pt-BR/AccountLocked-email.html",
+    "pt-BR/AccountLocked-email.txt",
+];
+
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+pub struct TemplateEngine {
+    env: Environment<'static>,
+    front_url: String,
+}
+
+impl TemplateEngine {
+    pub fn new(template_dir: Option<&str>, front_url: &str) -> Result<Self> {
+        let mut env = Environment::new();
+
+        match template_dir {
+            Some(dir) => env.set_loader(path_loader(dir)),
+            None => {
+                for (name, source) in EMBEDDED_TEMPLATES {
+                    env.add_template(name, source)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            env,
+            front_url: front_url.to_string(),
+        })
+    }
+
+    /// Base URL for the frontend links in emails.
+    pub fn front_url(&self) -> &str {
+        &self.front_url
+    }
+
+    pub fn render(&self, name: &str, locale: &str, context: Value) -> Result<RenderedEmail> {
+        let locale = normalize_locale(locale);
+        let context = context! { locale, ..context };
+
+        let html = self.template(name, locale, "html")?.render(&context)?;
+
+        let mut text = self
+            .template(name, locale, "txt")?
+            .render_captured(&context)?;
+        let subject = text
+            .with_state_mut(|state| state.render_block("subject"))?
+            .trim()
+            .to_string();
+        let text = text.output().trim().to_string();
+
+        Ok(RenderedEmail {
+            subject,
+            html,
+            text,
+        })
+    }
+
+    fn template(&self, name: &str, locale: &str, extension: &str) -> Result<Template<'_, '_>> {
+        let template = self
+            .env
+            .get_template(&format!("{locale}/{name}.{extension}"))
+            .or_else(|_| {
+                self.env
+                    .get_template(&format!("{DEFAULT_LOCALE}/{name}.{extension}"))
+            })?;
+
+        Ok(template)
+    }
+}
+
+pub fn normalize_locale(locale: &str) -> &'static str {
+    if locale.to_lowercase().starts_with("pt") {
+        "pt-BR"
+    } else {
+        DEFAULT_LOCALE
+    }
+}
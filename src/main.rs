@@ -1,97 +1,393 @@
-use axum::middleware::from_fn_with_state;
-use config::Config;
+use axum::middleware::{from_fn, from_fn_with_state};
+use config::{AuthLimitStore, Config, MailTransport};
 use dotenv::dotenv;
-use handlers::auth::{configure_cors, require_api_key};
-use repositories::PostgresRepo;
+use handlers::{
+    auth::require_api_key,
+    metrics::{metrics_router, serve_metrics},
+};
+use jwt::{keyring::Keyring, token::SessionTokens};
+use mail::{
+    mailer::{build_mailer, Mailer},
+    templates::TemplateEngine,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use middleware::{
+    cors::cors_layer,
+    metrics::http_metrics,
+    rate_limit::RateLimiter,
+    request_id::{propagate_request_id_layer, set_request_id_layer, trace_layer},
+    security_headers::{security_headers, SecurityHeaders},
+};
+use password::{hasher::PasswordHasher, policy::PasswordPolicy};
+use repositories::{
+    auth_attempt_repo::{AuthAttemptRepository, MemoryAttemptStore},
+    PostgresRepo, MIGRATOR,
+};
 use routes::create_routes;
 use services::{
-    auth::AuthService, posts::NewsPostsService, user::UserService, video::VideosService,
+    auth::AuthService, auth_limiter::AuthLimiter, digest::DigestService,
+    email_templates::EmailTemplatesService, health::HealthService, media::MediaService,
+    newsletter::NewsletterService, oidc::OidcService, outbox::OutboxService,
+    posts::NewsPostsService, user::UserService, video::VideosService,
 };
 use sqlx::{postgres::PgPoolOptions, PgPool};
+use supervisor::{ShutdownTrigger, Supervisor};
+use telemetry::{run_metrics_upkeep, Telemetry};
+use tracing::{error, info, warn};
 
-use std::{env, sync::Arc};
+use std::{env, net::SocketAddr, path::PathBuf, sync::Arc};
 
 pub use self::errors::{Error, Result};
 
 mod config;
 mod errors;
 mod handlers;
+mod jwt;
 mod mail;
 mod middleware;
 mod models;
+mod oidc;
+mod password;
 mod repositories;
 mod routes;
 mod services;
+mod supervisor;
+mod telemetry;
 
 #[derive(Clone)]
 pub struct AppState {
     pub api_key: String,
     pub db_pool: PgPool,
     pub config: Config,
+    pub mailer: Arc<dyn Mailer>,
+    pub templates: Arc<TemplateEngine>,
     pub auth_service: AuthService,
+    pub email_templates_service: EmailTemplatesService,
+    pub health_service: HealthService,
+    pub media_service: MediaService,
+    pub newsletter_service: NewsletterService,
+    pub oidc_service: OidcService,
+    pub outbox_service: OutboxService,
     pub news_post_service: NewsPostsService,
     pub videos_service: VideosService,
     pub users_service: UserService,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub keyring: Arc<Keyring>,
+    pub metrics: Option<PrometheusHandle>,
 }
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let config = Config::init();
 
-    let api_key = env::var("API_KEY").unwrap_or_else(|_| {
-        panic!("🔒 API_KEY environment variable must be set and non-empty!");
-    });
+    let args: Vec<String> = env::args().collect();
+    let config_file = args
+        .windows(2)
+        .find(|pair| pair[0] == "--config")
+        .map(|pair| pair[1].as_str());
 
-    if api_key.is_empty() {
-        panic!("🔒 API_KEY cannot be empty!");
+    let config = match Config::load(config_file) {
+        Ok(config) => config,
+        Err(report) => {
+            eprint!("{}", report);
+            std::process::exit(1);
+        }
+    };
+
+    if args.iter().any(|arg| arg == "--print-config") {
+        print!("{}", config.redacted());
+        return;
     }
 
+    let telemetry = match Telemetry::init(&config.telemetry, &config.metrics) {
+        Ok(telemetry) => telemetry,
+        Err(err) => {
+            eprintln!("Failed to set up logging: {:?}", err);
+            std::process::exit(1);
+        }
+    };
+
     let pool = match PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&config.database_url)
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
         .await
     {
         Ok(pool) => {
-            println!("✅ Connection to the database is successful!");
+            info!("Connected to the database");
             pool
         }
         Err(err) => {
-            println!("🔥 Failed to connect to the database: {:?}", err);
+            error!(error = ?err, "Failed to connect to the database");
             std::process::exit(1);
         }
     };
 
-    sqlx::migrate!()
-        .run(&pool)
-        .await
-        .expect("Failed to run migrates!");
+    MIGRATOR.run(&pool).await.expect("Failed to run migrates!");
+
+    let mailer = match build_mailer(&config.mail) {
+        Ok(mailer) => mailer,
+        Err(err) => {
+            error!(error = ?err, "Failed to configure the mailer");
+            std::process::exit(1);
+        }
+    };
+
+    let templates = match TemplateEngine::new(
+        config.mail.template_dir.as_deref(),
+        &config.server.front_url,
+    ) {
+        Ok(templates) => Arc::new(templates),
+        Err(err) => {
+            error!(error = ?err, "Failed to load email templates");
+            std::process::exit(1);
+        }
+    };
 
     let db_blog = PostgresRepo::new(pool.clone());
+    let mut supervisor = Supervisor::new();
+    let media_service = MediaService::new(
+        db_blog.clone(),
+        config.server.api_url.clone(),
+        config.media.clone(),
+    );
+
+    let outbox_service =
+        OutboxService::new(db_blog.clone(), mailer.clone(), config.mail.outbox.clone());
+    let dispatcher = outbox_service.clone();
+    supervisor.spawn("outbox-dispatcher", move |shutdown| {
+        dispatcher.clone().run_dispatcher(shutdown)
+    });
+    let outbox_purger = outbox_service.clone();
+    supervisor.spawn("outbox-purger", move |shutdown| {
+        outbox_purger.clone().run_purger(shutdown)
+    });
+
+    let newsletter_service = NewsletterService::new(
+        db_blog.clone(),
+        templates.clone(),
+        config.newsletter.clone(),
+        config.server.api_url.clone(),
+    );
+
+    let digest_service = DigestService::new(
+        db_blog.clone(),
+        templates.clone(),
+        newsletter_service.clone(),
+        config.digest.clone(),
+    );
+    supervisor.spawn("digest-scheduler", move |shutdown| {
+        digest_service.clone().run_scheduler(shutdown)
+    });
+
+    let cache_warmer = media_service.clone();
+    supervisor.spawn("media-cache-warmer", move |shutdown| {
+        cache_warmer.clone().run_cache_warmer(shutdown)
+    });
+
+    let attempt_store: Arc<dyn AuthAttemptRepository> = match config.auth.limits.store {
+        AuthLimitStore::Postgres => Arc::new(db_blog.clone()),
+        AuthLimitStore::Memory => Arc::new(MemoryAttemptStore::default()),
+    };
+    let auth_limiter = AuthLimiter::new(attempt_store, config.auth.limits.clone());
+    let attempt_pruner = auth_limiter.clone();
+    supervisor.spawn("auth-attempt-pruner", move |shutdown| {
+        attempt_pruner.clone().run_pruner(shutdown)
+    });
+
+    let password_policy = match PasswordPolicy::new(config.auth.password_policy.clone()) {
+        Ok(password_policy) => Arc::new(password_policy),
+        Err(err) => {
+            error!(error = ?err, "Failed to load the password policy");
+            std::process::exit(1);
+        }
+    };
+
+    let password_hasher = match PasswordHasher::new(config.auth.password_hash.clone()) {
+        Ok(password_hasher) => Arc::new(password_hasher),
+        Err(err) => {
+            error!(error = ?err, "Invalid password hashing parameters");
+            std::process::exit(1);
+        }
+    };
+
+    let keyring = match Keyring::load(&config.auth.jwt) {
+        Ok(keyring) => Arc::new(keyring),
+        Err(err) => {
+            error!(error = ?err, "Failed to load the JWT keyring");
+            std::process::exit(1);
+        }
+    };
+
+    let session_tokens = SessionTokens::new(keyring.clone(), &config.auth.jwt);
+
+    let auth_service = AuthService::new(
+        db_blog.clone(),
+        session_tokens.clone(),
+        templates.clone(),
+        auth_limiter,
+        password_policy.clone(),
+        password_hasher.clone(),
+        config.auth.email_change.clone(),
+        config.server.api_url.clone(),
+    );
+
+    let oidc_service = match OidcService::new(
+        db_blog.clone(),
+        auth_service.clone(),
+        &config.auth.oidc,
+        password_hasher.clone(),
+    ) {
+        Ok(oidc_service) => oidc_service,
+        Err(err) => {
+            error!(error = ?err, "Failed to configure OIDC providers");
+            std::process::exit(1);
+        }
+    };
+
+    let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.clone()));
+    let bucket_pruner = rate_limiter.clone();
+    supervisor.spawn("rate-limit-pruner", move |shutdown| {
+        bucket_pruner.clone().run_pruner(shutdown)
+    });
+
+    let mut storage_dirs = vec![PathBuf::from(&config.media.assets_dir)];
+    if config.mail.transport == MailTransport::File {
+        storage_dirs.push(PathBuf::from(&config.mail.file_dir));
+    }
+    let health_service = HealthService::new(
+        db_blog.clone(),
+        mailer.clone(),
+        storage_dirs,
+        config.health.clone(),
+    );
 
     let app_state = AppState {
-        api_key,
+        api_key: config.server.api_key.clone(),
         db_pool: pool,
         config: config.clone(),
-        auth_service: AuthService::new(
+        mailer: mailer.clone(),
+        templates: templates.clone(),
+        email_templates_service: EmailTemplatesService::new(templates, mailer.clone()),
+        auth_service,
+        health_service: health_service.clone(),
+        newsletter_service,
+        oidc_service,
+        outbox_service,
+        news_post_service: NewsPostsService::new(db_blog.clone(), media_service.clone()),
+        media_service,
+        users_service: UserService::new(
             db_blog.clone(),
-            config.jwt_secret.clone(),
-            config.jwt_maxage,
+            session_tokens,
+            password_policy,
+            password_hasher,
         ),
-        news_post_service: NewsPostsService::new(db_blog.clone()),
-        users_service: UserService::new(db_blog.clone(), config.jwt_secret.clone()),
         videos_service: VideosService::new(db_blog),
+        rate_limiter,
+        keyring,
+        metrics: telemetry.metrics(),
     };
 
+    if let Some(handle) = telemetry.metrics() {
+        supervisor.spawn("metrics-upkeep", move |shutdown| {
+            run_metrics_upkeep(handle.clone(), shutdown)
+        });
+    }
+
+    if config.metrics.admin_port != 0 {
+        let admin_listener =
+            match std::net::TcpListener::bind(format!("[::]:{}", config.metrics.admin_port))
+                .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            {
+                Ok(listener) => Arc::new(listener),
+                Err(err) => {
+                    error!(error = ?err, "Failed to bind the metrics port");
+                    std::process::exit(1);
+                }
+            };
+        info!(port = config.metrics.admin_port, "Serving metrics");
+
+        let metrics_app = metrics_router(Arc::new(app_state.clone()));
+        supervisor.spawn("metrics-listener", move |shutdown| {
+            serve_metrics(admin_listener.clone(), metrics_app.clone(), shutdown)
+        });
+    }
+
     let app = create_routes(Arc::new(app_state.clone()))
-        .layer(configure_cors())
-        .layer(from_fn_with_state(app_state, require_api_key));
-
-    let listener = tokio::net::TcpListener::bind(format!(
-        "[::]:{}",
-        env::var("PORT").unwrap_or_else(|_| "8080".to_string())
-    ))
-    .await
-    .unwrap();
-    axum::serve(listener, app).await.unwrap();
+        .layer(cors_layer(&config.cors))
+        .layer(from_fn_with_state(app_state, require_api_key))
+        .layer(from_fn_with_state(
+            Arc::new(SecurityHeaders::new(&config.security_headers)),
+            security_headers,
+        ))
+        .layer(from_fn(http_metrics))
+        .layer(trace_layer())
+        .layer(propagate_request_id_layer())
+        .layer(set_request_id_layer());
+
+    let listener = tokio::net::TcpListener::bind(format!("[::]:{}", config.server.port))
+        .await
+        .unwrap();
+    info!(port = config.server.port, "Listening");
+
+    let stop_server = ShutdownTrigger::new();
+    let mut server_stopping = stop_server.subscribe();
+    let readiness_delay = config.shutdown.readiness_delay;
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down");
+        health_service.begin_shutdown();
+        tokio::time::sleep(readiness_delay).await;
+        stop_server.trigger();
+    });
+
+    let mut graceful = server_stopping.clone();
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move { graceful.wait().await });
+
+    let drain_deadline = async {
+        server_stopping.wait().await;
+        tokio::time::sleep(config.shutdown.drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server => {
+            if let Err(err) = result {
+                error!(error = ?err, "Server error");
+            }
+        }
+        _ = drain_deadline => {
+            warn!("In-flight requests did not finish within the drain timeout");
+        }
+    }
+
+    info!("Stopping background tasks");
+    supervisor.shutdown(config.shutdown.drain_timeout).await;
+    telemetry.shutdown();
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use axum::http::{
+    header::{CONTENT_DISPOSITION, RETRY_AFTER},
+    HeaderValue,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+
+use super::{
+    rate_limit::{RATELIMIT_LIMIT, RATELIMIT_POLICY, RATELIMIT_REMAINING, RATELIMIT_RESET},
+    request_id::REQUEST_ID_HEADER,
+};
+
+/// Origins may contain `*`, which matches a single DNS label or port, e.g.
+/// `https://nextlevelcode-blog-*.vercel.app` or `http://localhost:*`.
+pub fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origins = config.allowed_origins.clone();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin.to_str().is_ok_and(|origin| {
+                origins
+                    .iter()
+                    .any(|pattern| origin_matches(pattern, origin))
+            })
+        }))
+        .allow_methods(config.allowed_methods.clone())
+        .allow_headers(config.allowed_headers.clone())
+        .allow_credentials(true)
+        .expose_headers(vec![
+            CONTENT_DISPOSITION,
+            RETRY_AFTER,
+            RATELIMIT_LIMIT,
+            RATELIMIT_REMAINING,
+            RATELIMIT_RESET,
+            RATELIMIT_POLICY,
+            REQUEST_ID_HEADER,
+        ])
+        .max_age(Duration::from_secs(config.max_age_secs))
+}
+
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern.eq_ignore_ascii_case(origin);
+    };
+
+    if origin.len() < prefix.len() || !origin[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return false;
+    }
+    let origin = &origin[prefix.len()..];
+
+    // The wildcard takes at least one character and never crosses a label, port or path.
+    let segment_end = origin.find(['.', ':', '/']).unwrap_or(origin.len());
+    (1..=segment_end).any(|end| origin_matches(rest, &origin[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_patterns_match_only_that_origin() {
+        let pattern = "https://example.com";
+
+        assert!(origin_matches(pattern, "https://example.com"));
+        assert!(origin_matches(pattern, "HTTPS://Example.COM"));
+
+        for origin in [
+            "http://example.com",
+            "https://example.com:8443",
+            "https://app.example.com",
+            "https://evil-example.com",
+            "https://example.com.evil.com",
+            "https://example.co",
+        ] {
+            assert!(!origin_matches(pattern, origin), "{origin}");
+        }
+    }
+
+    #[test]
+    fn wildcards_match_a_single_subdomain_label() {
+        let pattern = "https://*.example.com";
+
+        assert!(origin_matches(pattern, "https://app.example.com"));
+        assert!(origin_matches(pattern, "https://preview-42.example.com"));
+
+        for origin in [
+            "https://example.com",
+            "https://.example.com",
+            "https://a.b.example.com",
+            "https://evil-example.com",
+            "https://app.example.com.evil.com",
+            "https://evil.com/.example.com",
+            "http://app.example.com",
+        ] {
+            assert!(!origin_matches(pattern, origin), "{origin}");
+        }
+    }
+
+    #[test]
+    fn wildcards_inside_a_label_or_port_stay_there() {
+        let preview = "https://nextlevelcode-blog-*.vercel.app";
+        assert!(origin_matches(
+            preview,
+            "https://nextlevelcode-blog-git-main.vercel.app"
+        ));
+        assert!(!origin_matches(
+            preview,
+            "https://nextlevelcode-blog-x.evil.com/.vercel.app"
+        ));
+        assert!(!origin_matches(
+            preview,
+            "https://nextlevelcode-blog.vercel.app"
+        ));
+
+        let localhost = "http://localhost:*";
+        assert!(origin_matches(localhost, "http://localhost:3000"));
+        assert!(!origin_matches(localhost, "http://localhost"));
+        assert!(!origin_matches(localhost, "http://localhost:3000.evil.com"));
+    }
+}
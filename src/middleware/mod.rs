@@ -1,14 +1,30 @@
+pub mod client_ip;
+pub mod cors;
+pub mod csrf;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
+pub mod security_headers;
+
 use std::sync::Arc;
 
-use axum::{extract::Request, http::header, middleware::Next, response::IntoResponse, Extension};
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::IntoResponse,
+    Extension,
+};
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
 use crate::{
+    config::CookieConfig,
+    jwt::token::{cookie_name, SESSION_COOKIE},
     models::users::{User, UserRole},
     AppState, Error, Result,
 };
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JWTAuthMiddeware {
     pub user: User,
@@ -20,34 +36,29 @@ pub async fn auth(mut req: Request, next: Next) -> Result<impl IntoResponse> {
         .get::<Arc<AppState>>()
         .ok_or(Error::BadRequest("msmsmsmss1".to_string()))?;
 
-    let cookies = CookieJar::from_headers(req.headers());
-
-    let cookie = cookies
-        .get("token")
-        .map(|c| c.value().to_string())
-        .or_else(|| {
-            req.headers()
-                .get(header::AUTHORIZATION)
-                .and_then(|auth_header| auth_header.to_str().ok())
-                .and_then(|auth_value| {
-                    auth_value
-                        .strip_prefix("Bearer ")
-                        .map(|stripped| stripped.to_string())
-                })
-        });
-
-    let token = cookie.ok_or(Error::Unauthorized)?;
+    let token =
+        request_token(req.headers(), &app_state.config.auth.cookies).ok_or(Error::Unauthorized)?;
 
-    let token_details = app_state
+    let claims = app_state
         .users_service
-        .decode_token(token)
+        .decode_token(token.value)
         .map_err(|_| Error::Unauthorized)?;
 
-    let user_id = Uuid::parse_str(&token_details.to_string()).map_err(|_| Error::Unauthorized)?;
+    tracing::Span::current().record("user_id", tracing::field::display(claims.sub));
+
+    // Browsers attach cookies to cross-site requests on their own; bearer tokens are never
+    // sent implicitly, so only cookie sessions need the CSRF check.
+    if token.source == TokenSource::Cookie && !req.method().is_safe() {
+        csrf::verify_csrf(
+            &app_state.config.auth.cookies.csrf_secret,
+            claims.sid,
+            req.headers(),
+        )?;
+    }
 
     let user = app_state
         .users_service
-        .get_user(Some(user_id), None, None, None)
+        .get_user(Some(claims.sub), None, None)
         .await?;
 
     req.extensions_mut().insert(JWTAuthMiddeware { user });
@@ -55,6 +66,37 @@ pub async fn auth(mut req: Request, next: Next) -> Result<impl IntoResponse> {
     Ok(next.run(req).await)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSource {
+    Bearer,
+    Cookie,
+}
+
+pub struct RequestToken {
+    pub value: String,
+    pub source: TokenSource,
+}
+
+pub fn request_token(headers: &HeaderMap, cookies: &CookieConfig) -> Option<RequestToken> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|auth_header| auth_header.to_str().ok())
+        .and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+        .map(|token| RequestToken {
+            value: token.to_string(),
+            source: TokenSource::Bearer,
+        });
+
+    bearer.or_else(|| {
+        CookieJar::from_headers(headers)
+            .get(&cookie_name(cookies, SESSION_COOKIE))
+            .map(|cookie| RequestToken {
+                value: cookie.value().to_string(),
+                source: TokenSource::Cookie,
+            })
+    })
+}
+
 pub async fn role_check(
     Extension(_app_state): Extension<Arc<AppState>>,
     req: Request,
@@ -0,0 +1,402 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Request,
+    http::{header::RETRY_AFTER, HeaderMap, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use crate::{
+    config::{RateLimitConfig, RateLimitKey, RateLimitRule},
+    services::user_tokens::hash_token,
+    supervisor::Shutdown,
+    AppState, Error,
+};
+
+use super::{client_ip::client_ip, request_token};
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+pub const RATELIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+pub struct RateLimitStatus {
+    allowed: bool,
+    limit: u32,
+    per_minute: u32,
+    remaining: u32,
+    reset_secs: u64,
+}
+
+impl RateLimitStatus {
+    /// Nested groups each charge their own bucket, so the outer `default` group acts as an
+    /// overall budget; the headers describe whichever bucket is closest to running out.
+    fn apply(&self, headers: &mut HeaderMap) {
+        // An inner group already rejected the request and its headers match `Retry-After`.
+        if headers.contains_key(RETRY_AFTER) {
+            return;
+        }
+
+        let reported_remaining = headers
+            .get(RATELIMIT_REMAINING)
+            .and_then(|value| value.to_str().ok()?.parse::<u32>().ok());
+        let reported_reset = headers
+            .get(RATELIMIT_RESET)
+            .and_then(|value| value.to_str().ok()?.parse::<u64>().ok());
+
+        if let (Some(remaining), Some(reset_secs)) = (reported_remaining, reported_reset) {
+            if (remaining, std::cmp::Reverse(reset_secs))
+                <= (self.remaining, std::cmp::Reverse(self.reset_secs))
+            {
+                return;
+            }
+        }
+
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATELIMIT_RESET, HeaderValue::from(self.reset_secs));
+        if let Ok(policy) =
+            HeaderValue::from_str(&format!("{};w=60;burst={}", self.per_minute, self.limit))
+        {
+            headers.insert(RATELIMIT_POLICY, policy);
+        }
+    }
+}
+
+/// In-process token buckets, one per route group and client key.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(&'static str, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn run_pruner(self: Arc<Self>, mut shutdown: Shutdown) {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+            self.prune();
+        }
+    }
+
+    fn rule(&self, group: &str) -> &RateLimitRule {
+        self.config
+            .groups
+            .get(group)
+            .unwrap_or(&self.config.default_rule)
+    }
+
+    /// Only client IPs can be allowlisted; every client sends the same `x-api-key`.
+    fn is_allowlisted(&self, ip: Option<IpAddr>) -> bool {
+        ip.is_some_and(|ip| {
+            self.config
+                .allowlist
+                .iter()
+                .any(|entry| entry.parse::<IpAddr>() == Ok(ip))
+        })
+    }
+
+    fn acquire(&self, group: &'static str, key: String) -> RateLimitStatus {
+        let rule = self.rule(group);
+        let capacity = f64::from(rule.burst.max(1));
+        let per_second = f64::from(rule.per_minute.max(1)) / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry((group, key)).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        let reset_secs = if allowed {
+            (capacity - bucket.tokens) / per_second
+        } else {
+            (1.0 - bucket.tokens) / per_second
+        };
+
+        RateLimitStatus {
+            allowed,
+            limit: rule.burst.max(1),
+            per_minute: rule.per_minute,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: reset_secs.ceil() as u64,
+        }
+    }
+
+    fn prune(&self) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        buckets.retain(|(group, _), bucket| {
+            let rule = self.rule(group);
+            let refill = now.duration_since(bucket.updated_at).as_secs_f64()
+                * f64::from(rule.per_minute.max(1))
+                / 60.0;
+            bucket.tokens + refill < f64::from(rule.burst.max(1))
+        });
+    }
+
+    fn client_key(&self, group: &str, req: &Request, app_state: &AppState) -> String {
+        let ip = client_ip(req.headers(), req.extensions());
+
+        let key = match self.rule(group).key {
+            RateLimitKey::Ip => None,
+            RateLimitKey::ApiKey => req
+                .headers()
+                .get("x-api-key")
+                .and_then(|value| value.to_str().ok())
+                .filter(|api_key| !api_key.is_empty() && *api_key == app_state.api_key)
+                .map(|api_key| format!("key:{}", &hash_token(api_key)[..16])),
+            RateLimitKey::User => request_token(req.headers(), &app_state.config.auth.cookies)
+                .and_then(|token| app_state.users_service.decode_token(token.value).ok())
+                .map(|claims| format!("user:{}", claims.sub)),
+        };
+
+        key.or_else(|| ip.map(|ip| format!("ip:{}", ip)))
+            .unwrap_or_else(|| "anonymous".to_string())
+    }
+}
+
+/// Token-bucket limit for a route group, e.g. `RateLimitLayer::new("comments")`.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    group: &'static str,
+}
+
+impl RateLimitLayer {
+    pub fn new(group: &'static str) -> Self {
+        Self { group }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            group: self.group,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    group: &'static str,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let group = self.group;
+
+        Box::pin(async move {
+            let Some(app_state) = req.extensions().get::<Arc<AppState>>().cloned() else {
+                return inner.call(req).await;
+            };
+
+            let limiter = &app_state.rate_limiter;
+            if !limiter.config.enabled
+                || limiter.is_allowlisted(client_ip(req.headers(), req.extensions()))
+            {
+                return inner.call(req).await;
+            }
+
+            let status = limiter.acquire(group, limiter.client_key(group, &req, &app_state));
+            if !status.allowed {
+                let mut response =
+                    Error::TooManyRequests("Too many requests. Please slow down.".to_string())
+                        .into_response();
+                status.apply(response.headers_mut());
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(status.reset_secs.max(1)));
+
+                return Ok(response);
+            }
+
+            let mut response = inner.call(req).await?;
+            status.apply(response.headers_mut());
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        let mut config = RateLimitConfig {
+            allowlist: vec!["203.0.113.7".to_string()],
+            ..Default::default()
+        };
+        config.groups.insert(
+            "test".to_string(),
+            RateLimitRule {
+                per_minute: 60,
+                burst: 3,
+                key: RateLimitKey::Ip,
+            },
+        );
+
+        RateLimiter::new(config)
+    }
+
+    fn rewind(limiter: &RateLimiter, key: &str, by: Duration) {
+        let mut buckets = limiter.buckets.lock().unwrap();
+        let bucket = buckets.get_mut(&("test", key.to_string())).unwrap();
+        bucket.updated_at -= by;
+    }
+
+    #[test]
+    fn a_full_bucket_allows_a_burst_then_rejects() {
+        let limiter = limiter();
+
+        let statuses: Vec<_> = (0..4)
+            .map(|_| limiter.acquire("test", "ip:a".to_string()))
+            .collect();
+
+        assert!(statuses[..3].iter().all(|status| status.allowed));
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|status| status.remaining)
+                .collect::<Vec<_>>(),
+            [2, 1, 0, 0]
+        );
+        assert!(!statuses[3].allowed);
+        assert_eq!(statuses[3].limit, 3);
+        assert_eq!(statuses[3].reset_secs, 1);
+    }
+
+    #[test]
+    fn buckets_refill_at_the_per_minute_rate() {
+        let limiter = limiter();
+        for _ in 0..3 {
+            limiter.acquire("test", "ip:a".to_string());
+        }
+
+        rewind(&limiter, "ip:a", Duration::from_secs(2));
+        assert!(limiter.acquire("test", "ip:a".to_string()).allowed);
+        assert!(limiter.acquire("test", "ip:a".to_string()).allowed);
+        assert!(!limiter.acquire("test", "ip:a".to_string()).allowed);
+
+        // Refilling stops at the burst size.
+        rewind(&limiter, "ip:a", Duration::from_secs(600));
+        let status = limiter.acquire("test", "ip:a".to_string());
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 2);
+    }
+
+    #[test]
+    fn clients_and_groups_have_separate_buckets() {
+        let limiter = limiter();
+        for _ in 0..3 {
+            limiter.acquire("test", "ip:a".to_string());
+        }
+
+        assert!(!limiter.acquire("test", "ip:a".to_string()).allowed);
+        assert!(limiter.acquire("test", "ip:b".to_string()).allowed);
+
+        let other_group = limiter.acquire("unknown", "ip:a".to_string());
+        assert!(other_group.allowed);
+        assert_eq!(other_group.limit, limiter.config.default_rule.burst);
+    }
+
+    #[test]
+    fn full_buckets_are_pruned() {
+        let limiter = limiter();
+        limiter.acquire("test", "ip:a".to_string());
+        limiter.acquire("test", "ip:b".to_string());
+
+        rewind(&limiter, "ip:a", Duration::from_secs(60));
+        limiter.prune();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(!buckets.contains_key(&("test", "ip:a".to_string())));
+        assert!(buckets.contains_key(&("test", "ip:b".to_string())));
+    }
+
+    #[test]
+    fn headers_describe_the_bucket_closest_to_running_out() {
+        let status = |remaining, reset_secs| RateLimitStatus {
+            allowed: true,
+            limit: 10,
+            per_minute: 60,
+            remaining,
+            reset_secs,
+        };
+        let mut headers = HeaderMap::new();
+
+        status(5, 5).apply(&mut headers);
+        status(8, 2).apply(&mut headers);
+        assert_eq!(headers[RATELIMIT_REMAINING], "5");
+
+        status(2, 8).apply(&mut headers);
+        assert_eq!(headers[RATELIMIT_REMAINING], "2");
+        assert_eq!(headers[RATELIMIT_RESET], "8");
+
+        headers.insert(RETRY_AFTER, HeaderValue::from(8));
+        status(0, 60).apply(&mut headers);
+        assert_eq!(headers[RATELIMIT_REMAINING], "2");
+    }
+
+    #[test]
+    fn only_client_ips_are_allowlisted() {
+        let limiter = limiter();
+
+        assert!(limiter.is_allowlisted(Some("203.0.113.7".parse().unwrap())));
+        assert!(!limiter.is_allowlisted(Some("203.0.113.8".parse().unwrap())));
+        assert!(!limiter.is_allowlisted(None));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::SecurityHeadersConfig;
+
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    content_security_policy: HeaderValue,
+}
+
+impl SecurityHeaders {
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        let mut headers = vec![
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+            (header::REFERRER_POLICY, config.referrer_policy.clone()),
+            (PERMISSIONS_POLICY, config.permissions_policy.clone()),
+        ];
+
+        if config.hsts_max_age_secs > 0 {
+            let mut hsts = format!("max-age={}", config.hsts_max_age_secs);
+            if config.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            if config.hsts_preload {
+                hsts.push_str("; preload");
+            }
+            headers.push((
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_str(&hsts).expect("HSTS value is ASCII"),
+            ));
+        }
+
+        Self {
+            headers,
+            content_security_policy: config.content_security_policy.clone(),
+        }
+    }
+}
+
+/// Adds the configured headers without overriding ones a handler set itself. The CSP only
+/// goes on HTML responses; JSON and images have nothing for it to restrict.
+pub async fn security_headers(
+    State(security_headers): State<Arc<SecurityHeaders>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    for (name, value) in &security_headers.headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+
+    let is_html = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+
+    if is_html && !headers.contains_key(header::CONTENT_SECURITY_POLICY) {
+        headers.insert(
+            header::CONTENT_SECURITY_POLICY,
+            security_headers.content_security_policy.clone(),
+        );
+    }
+
+    response
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "digest_frequency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    Off,
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "digest_recipient", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DigestRecipientKind {
+    User,
+    Subscriber,
+}
+
+#[derive(Debug, sqlx::FromRow, Clone)]
+pub struct DigestRecipient {
+    pub kind: DigestRecipientKind,
+    pub id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub locale: String,
+    pub frequency: DigestFrequency,
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct DigestPost {
+    pub id: Uuid,
+    pub url: String,
+    pub description: String,
+    #[serde(rename = "authorName")]
+    pub author_name: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct DigestVideo {
+    pub id: Uuid,
+    pub title: String,
+    pub youtube_id: String,
+    pub duration: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DigestFrequencyUpdateDto {
+    pub frequency: DigestFrequency,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailTemplateInfo {
+    pub name: String,
+    pub locales: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EmailPreviewQueryDto {
+    pub locale: Option<String>,
+    /// `html` or `text` returns just that part; anything else returns the JSON preview.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailPreviewDto {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TestEmailDto {
+    #[validate(
+        length(min = 1, message = "Email is required"),
+        email(message = "Email is invalid")
+    )]
+    pub to: String,
+    pub locale: Option<String>,
+}
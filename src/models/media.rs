@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct ImageMetadata {
+    pub path: String,
+    pub width: i32,
+    pub height: i32,
+    #[serde(rename = "dominantColor")]
+    pub dominant_color: String,
+    pub blurhash: String,
+    pub lqip: String,
+    #[serde(skip)]
+    pub file_size: i64,
+    #[serde(skip)]
+    pub file_modified_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ImageVariant {
+    pub width: u32,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ImageMetadataResponse {
+    pub src: String,
+    pub width: i32,
+    pub height: i32,
+    #[serde(rename = "dominantColor")]
+    pub dominant_color: String,
+    pub blurhash: String,
+    pub lqip: String,
+    pub variants: Vec<ImageVariant>,
+    pub srcset: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ArticleImages {
+    pub slug: String,
+    pub cover: Option<ImageMetadataResponse>,
+    pub images: Vec<ImageMetadataResponse>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImageParams {
+    /// URL-encoded path of the image under the assets directory.
+    pub url: String,
+    /// Target width; rounded up to the next configured image width.
+    pub w: Option<u32>,
+}
@@ -1,4 +1,11 @@
+pub mod digest;
+pub mod email;
+pub mod health;
+pub mod media;
 pub mod news_post;
+pub mod newsletter;
+pub mod oidc;
+pub mod outbox;
 pub mod query;
 pub mod response;
 pub mod users;
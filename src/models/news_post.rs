@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+use super::media::ImageMetadataResponse;
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
 pub struct NewsPost {
     pub id: Uuid,
     pub url: String,
@@ -13,9 +16,13 @@ pub struct NewsPost {
     pub description: String,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
+    #[sqlx(skip)]
+    pub cover: Option<ImageMetadataResponse>,
 }
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct PostCommentWithComments {
     pub id: Uuid,
     pub url: String,
@@ -26,10 +33,14 @@ pub struct PostCommentWithComments {
     pub author_name: String,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
+    #[sqlx(skip)]
+    pub cover: Option<ImageMetadataResponse>,
     pub comments: Vec<CommentWithAuthor>,
 }
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, ToSchema)]
 pub struct CommentWithAuthor {
     pub id: Uuid,
     pub content: String,
@@ -53,7 +64,7 @@ pub struct PostComment {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
 pub struct PostCommentDto {
     pub id: String,
     pub content: String,
@@ -61,23 +72,27 @@ pub struct PostCommentDto {
     pub author_name: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
 pub struct UpdatePostCommentDto {
     pub content: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
 pub struct CreateNewsPostDto {
     pub url: String,
     pub description: String,
     #[serde(rename = "authorName")]
     pub author_name: String,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateNewsPost {
     pub url: Option<String>,
     pub description: Option<String>,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
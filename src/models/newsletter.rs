@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::digest::DigestFrequency;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "subscriber_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriberStatus {
+    Pending,
+    Confirmed,
+    Unsubscribed,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
+pub struct Subscriber {
+    pub id: Uuid,
+    pub email: String,
+    #[serde(rename = "userId")]
+    pub user_id: Option<Uuid>,
+    pub locale: String,
+    pub status: SubscriberStatus,
+    #[serde(rename = "digestFrequency")]
+    pub digest_frequency: DigestFrequency,
+    #[serde(rename = "confirmedAt")]
+    pub confirmed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "unsubscribedAt")]
+    pub unsubscribed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
+pub struct NewsletterIssue {
+    pub id: Uuid,
+    pub subject: String,
+    #[serde(rename = "htmlContent")]
+    pub html_content: String,
+    #[serde(rename = "textContent")]
+    pub text_content: String,
+    pub recipients: i32,
+    #[serde(rename = "sentBy")]
+    pub sent_by: Option<Uuid>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubscribeDto {
+    #[validate(
+        length(
+            min = 3,
+            max = 255,
+            message = "Email must be between 3 and 255 characters"
+        ),
+        email(message = "Invalid email address")
+    )]
+    pub email: String,
+    #[validate(length(max = 10, message = "Locale must be at most 10 characters"))]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SubscribersQueryDto {
+    pub status: Option<SubscriberStatus>,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateIssueDto {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Subject must be between 1 and 200 characters"
+    ))]
+    pub subject: String,
+    #[validate(length(min = 1, message = "HTML content is required"))]
+    #[serde(rename = "htmlContent")]
+    pub html_content: String,
+    #[validate(length(min = 1, message = "Text content is required"))]
+    #[serde(rename = "textContent")]
+    pub text_content: String,
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OidcState {
+    pub state_hash: String,
+    pub provider: String,
+    pub nonce: String,
+    pub pkce_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OidcCallbackDto {
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+    #[validate(length(min = 1, message = "State is required"))]
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcProvidersResponseDto {
+    pub status: &'static str,
+    pub providers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcAuthorizationResponseDto {
+    pub status: &'static str,
+    #[serde(rename = "authorizationUrl")]
+    pub authorization_url: String,
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "email_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmailStatus {
+    Pending,
+    Sending,
+    Sent,
+    Dead,
+}
+
+impl EmailStatus {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Sending => "sending",
+            Self::Sent => "sent",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+/// What admins see of an outbox email. The rendered bodies stay in the database: until the
+/// email is sent they carry live confirmation and sign-in links.
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone, ToSchema)]
+pub struct OutboxEmail {
+    pub id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub status: EmailStatus,
+    pub attempts: i32,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Utc>,
+    #[serde(rename = "sentAt")]
+    pub sent_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A claimed email with everything the dispatcher needs to send it.
+#[derive(Debug, sqlx::FromRow, Clone)]
+pub struct ClaimedEmail {
+    pub id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: Option<String>,
+    pub headers: Json<BTreeMap<String, String>>,
+    pub attempts: i32,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OutboxQueryDto {
+    pub status: Option<EmailStatus>,
+    pub limit: Option<i64>,
+}
@@ -1,14 +1,16 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct VerifyEmailQueryDto {
     #[validate(length(min = 1, message = "Token is required."))]
     pub token: String,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct Video {
     pub id: Uuid,
     pub title: String,
@@ -27,14 +29,14 @@ pub struct CreateVideo {
     pub views: i32,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct ResponseVideo {
     pub title: String,
     pub duration: String,
     pub views: Option<i32>,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct VideoDto {
     pub title: String,
     pub youtube_id: String,
@@ -42,7 +44,7 @@ pub struct VideoDto {
     pub views: Option<i32>,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct UpdateVideoDto {
     pub title: Option<String>,
     pub youtube_id: Option<String>,
@@ -50,12 +52,12 @@ pub struct UpdateVideoDto {
     pub views: Option<i32>,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct CategoryDto {
     pub category_id: String,
 }
 
-#[derive(Debug, sqlx::FromRow, Deserialize, Serialize)]
+#[derive(Debug, sqlx::FromRow, Deserialize, Serialize, ToSchema)]
 pub struct CategoryName {
     pub name: String,
 }
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -27,15 +28,16 @@ pub struct User {
     pub password: String,
     pub role: UserRole,
     pub verified: bool,
-    pub verification_token: Option<String>,
-    pub token_expires_at: Option<DateTime<Utc>>,
+    pub locale: String,
+    #[serde(rename = "magicLinkEnabled")]
+    pub magic_link_enabled: bool,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RegisterUserDto {
     #[validate(length(
         min = 3,
@@ -52,7 +54,7 @@ pub struct RegisterUserDto {
         email(message = "Invalid email address")
     )]
     pub email: String,
-    #[validate(length(min = 6, message = "Password must be at least 6 characters"))]
+    #[validate(length(min = 1, message = "Password is required"))]
     pub password: String,
 
     #[validate(
@@ -61,9 +63,12 @@ pub struct RegisterUserDto {
     )]
     #[serde(rename = "passwordConfirm")]
     pub password_confirm: String,
+
+    #[validate(length(max = 10, message = "Locale must be at most 10 characters"))]
+    pub locale: Option<String>,
 }
 
-#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LoginUserDto {
     #[validate(
         length(min = 1, message = "Email is required"),
@@ -74,13 +79,16 @@ pub struct LoginUserDto {
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FilterUserDto {
     pub id: String,
     pub name: String,
     pub email: String,
     pub role: String,
     pub verified: bool,
+    pub locale: String,
+    #[serde(rename = "magicLinkEnabled")]
+    pub magic_link_enabled: bool,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
@@ -95,6 +103,8 @@ impl FilterUserDto {
             email: user.email.to_owned(),
             verified: user.verified,
             role: user.role.to_str().to_string(),
+            locale: user.locale.to_owned(),
+            magic_link_enabled: user.magic_link_enabled,
             created_at: user.created_at,
             updated_at: user.updated_at,
         }
@@ -105,24 +115,26 @@ impl FilterUserDto {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserData {
     pub user: FilterUserDto,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserResponseDto {
     pub status: String,
     pub data: UserData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserLoginResponseDto {
     pub status: String,
     pub token: String,
+    #[serde(rename = "csrfToken")]
+    pub csrf_token: String,
 }
 
-#[derive(Deserialize, Serialize, Validate, Debug, Clone)]
+#[derive(Deserialize, Serialize, Validate, Debug, Clone, ToSchema)]
 pub struct ForgotPasswordRequestDto {
     #[validate(
         length(min = 1, message = "Email is required"),
@@ -131,20 +143,17 @@ pub struct ForgotPasswordRequestDto {
     pub email: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+#[derive(Debug, Serialize, Deserialize, Validate, Clone, ToSchema)]
 pub struct ResetPasswordRequestDto {
     #[validate(length(min = 1, message = "Token is required."))]
     pub token: String,
 
-    #[validate(length(min = 6, message = "new password must be at least 6 characters"))]
+    #[validate(length(min = 1, message = "new password is required"))]
     #[serde(rename = "newPassword")]
     pub new_password: String,
 
     #[validate(
-        length(
-            min = 6,
-            message = "new password confirm must be at least 6 characters"
-        ),
+        length(min = 1, message = "new password confirm is required"),
         must_match(other = "new_password", message = "new passwords do not match")
     )]
     #[serde(rename = "confirmPassword")]
@@ -157,17 +166,14 @@ pub struct DeleteUser {
     pub password: String,
 }
 
-#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserPasswordUpdateDto {
-    #[validate(length(min = 6, message = "new password must be at least 6 characters"))]
+    #[validate(length(min = 1, message = "new password is required"))]
     #[serde(rename = "newPassword")]
     pub new_password: String,
 
     #[validate(
-        length(
-            min = 6,
-            message = "new password confirm must be at least 6 characters"
-        ),
+        length(min = 1, message = "new password confirm is required"),
         must_match(other = "new_password", message = "new passwords do not match")
     )]
     #[serde(rename = "newPasswordConfirm")]
@@ -177,10 +183,96 @@ pub struct UserPasswordUpdateDto {
     #[serde(rename = "oldPassword")]
     pub old_password: String,
 }
-#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NameUpdateDto {
     #[validate(length(min = 1, message = "Name is required"))]
     pub name: String,
     #[validate(length(min = 1, message = "Password is required"))]
     pub password: String,
 }
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LocaleUpdateDto {
+    #[validate(length(min = 1, message = "Locale is required"))]
+    pub locale: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmailUpdateDto {
+    #[validate(
+        length(
+            min = 3,
+            max = 50,
+            message = "Email must be between 3 and 50 characters"
+        ),
+        email(message = "Invalid email address")
+    )]
+    #[serde(rename = "newEmail")]
+    pub new_email: String,
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailChangeRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub new_email: String,
+    pub cancel_token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub cancel_until: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "token_purpose", rename_all = "snake_case")]
+pub enum TokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+    ChangeEmail,
+    MagicLink,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "auth_action", rename_all = "snake_case")]
+pub enum AuthAction {
+    Login,
+    MagicLink,
+    ForgotPassword,
+    ResetPassword,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserToken {
+    pub user_id: Uuid,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResendVerificationDto {
+    #[validate(
+        length(min = 1, message = "Email is required"),
+        email(message = "Email is invalid")
+    )]
+    pub email: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MagicLinkRequestDto {
+    #[validate(
+        length(min = 1, message = "Email is required"),
+        email(message = "Email is invalid")
+    )]
+    pub email: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MagicLinkConsumeDto {
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MagicLinkUpdateDto {
+    pub enabled: bool,
+}
@@ -0,0 +1,705 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, PublicKeyUse},
+    Algorithm, DecodingKey, Validation,
+};
+use reqwest::{header::ACCEPT, Client};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::{
+    config::{OidcProviderConfig, OidcProviderKind},
+    Error, Result,
+};
+
+/// Unknown key ids trigger a JWKS refetch at most this often, so tokens with made-up key
+/// ids can't be used to hammer the provider.
+const JWKS_MIN_REFETCH: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: Option<String>,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdentityClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    pub locale: Option<String>,
+    nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    id: u64,
+    login: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+struct JwksCache {
+    keys: JwkSet,
+    fetched_at: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub struct OidcClient {
+    config: OidcProviderConfig,
+    http: Client,
+    metadata: Arc<OnceCell<ProviderMetadata>>,
+    jwks: Arc<RwLock<JwksCache>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcProviderConfig, http: Client) -> Self {
+        Self {
+            config,
+            http,
+            metadata: Arc::new(OnceCell::new()),
+            jwks: Arc::new(RwLock::new(JwksCache {
+                keys: JwkSet { keys: Vec::new() },
+                fetched_at: None,
+            })),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> Result<String> {
+        let metadata = self.metadata().await?;
+        let separator = if metadata.authorization_endpoint.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+
+        let params = [
+            ("response_type", "code"),
+            ("client_id", self.config.client_id.as_str()),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("scope", self.config.scopes.as_str()),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", &code_challenge(code_verifier)),
+            ("code_challenge_method", "S256"),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+        Ok(format!(
+            "{}{}{}",
+            metadata.authorization_endpoint, separator, params
+        ))
+    }
+
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<IdentityClaims> {
+        let metadata = self.metadata().await?;
+
+        let tokens = self
+            .http
+            .post(&metadata.token_endpoint)
+            .header(ACCEPT, "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_uri),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        if self.config.kind == OidcProviderKind::Github {
+            return self.github_identity(metadata, &tokens.access_token).await;
+        }
+
+        let id_token = tokens.id_token.ok_or(Error::BadRequest(
+            "The identity provider did not return an ID token".to_string(),
+        ))?;
+        let mut claims = self.verify_id_token(&id_token, nonce).await?;
+
+        if claims.email.is_none() {
+            if let Some(userinfo_endpoint) = &metadata.userinfo_endpoint {
+                let userinfo = self
+                    .http
+                    .get(userinfo_endpoint)
+                    .header(ACCEPT, "application/json")
+                    .bearer_auth(&tokens.access_token)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<IdentityClaims>()
+                    .await?;
+
+                if userinfo.sub == claims.sub {
+                    claims.email = userinfo.email;
+                    claims.email_verified = userinfo.email_verified;
+                    claims.name = claims.name.or(userinfo.name);
+                }
+            }
+        }
+
+        Ok(claims)
+    }
+
+    /// GitHub's `/user` has no verified-email flag, so the address comes from `/user/emails`.
+    async fn github_identity(
+        &self,
+        metadata: &ProviderMetadata,
+        access_token: &str,
+    ) -> Result<IdentityClaims> {
+        let user_endpoint = metadata.userinfo_endpoint.as_deref().ok_or_else(|| {
+            tracing::error!(provider = %self.config.name, "GitHub provider has no user endpoint");
+            Error::InternalServerError
+        })?;
+
+        let user = self
+            .http
+            .get(user_endpoint)
+            .header(ACCEPT, "application/vnd.github+json")
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<GithubUser>()
+            .await?;
+
+        let emails = self
+            .http
+            .get(format!("{}/emails", user_endpoint.trim_end_matches('/')))
+            .header(ACCEPT, "application/vnd.github+json")
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<GithubEmail>>()
+            .await?;
+        let email = emails
+            .into_iter()
+            .find(|email| email.primary && email.verified)
+            .map(|email| email.email);
+
+        Ok(IdentityClaims {
+            sub: user.id.to_string(),
+            email_verified: email.is_some(),
+            email,
+            name: user.name,
+            preferred_username: Some(user.login),
+            locale: None,
+            nonce: None,
+        })
+    }
+
+    async fn verify_id_token(&self, id_token: &str, nonce: &str) -> Result<IdentityClaims> {
+        let invalid = || Error::BadRequest("Invalid identity token".to_string());
+        let metadata = self.metadata().await?;
+
+        let header = decode_header(id_token).map_err(|_| invalid())?;
+
+        let jwk = self
+            .signing_key(header.kid.as_deref())
+            .await?
+            .ok_or_else(invalid)?;
+        if !key_allows(&jwk, header.alg) {
+            return Err(invalid());
+        }
+        let key = DecodingKey::from_jwk(&jwk).map_err(|_| invalid())?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdentityClaims>(id_token, &key, &validation)
+            .map_err(|_| invalid())?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid());
+        }
+
+        Ok(claims)
+    }
+
+    async fn signing_key(&self, kid: Option<&str>) -> Result<Option<Jwk>> {
+        if let Some(jwk) = find_key(&self.jwks.read().await.keys, kid) {
+            return Ok(Some(jwk));
+        }
+
+        let mut cache = self.jwks.write().await;
+        // Another request may have refetched the keys while this one waited for the lock.
+        if let Some(jwk) = find_key(&cache.keys, kid) {
+            return Ok(Some(jwk));
+        }
+        if cache
+            .fetched_at
+            .is_some_and(|fetched_at| fetched_at.elapsed() < JWKS_MIN_REFETCH)
+        {
+            return Ok(None);
+        }
+
+        // Unknown key ids usually mean the provider rotated its keys.
+        cache.fetched_at = Some(Instant::now());
+        let metadata = self.metadata().await?;
+        cache.keys = self
+            .http
+            .get(&metadata.jwks_uri)
+            .header(ACCEPT, "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()
+            .await?;
+
+        Ok(find_key(&cache.keys, kid))
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let config = &self.config;
+                let discovered = match (
+                    &config.authorization_endpoint,
+                    &config.token_endpoint,
+                    &config.jwks_uri,
+                ) {
+                    (Some(authorization_endpoint), Some(token_endpoint), jwks_uri)
+                        if jwks_uri.is_some() || config.kind == OidcProviderKind::Github =>
+                    {
+                        ProviderMetadata {
+                            issuer: config.issuer.clone(),
+                            authorization_endpoint: authorization_endpoint.clone(),
+                            token_endpoint: token_endpoint.clone(),
+                            userinfo_endpoint: None,
+                            jwks_uri: jwks_uri.clone().unwrap_or_default(),
+                        }
+                    }
+                    _ => {
+                        self.http
+                            .get(format!(
+                                "{}/.well-known/openid-configuration",
+                                config.issuer
+                            ))
+                            .header(ACCEPT, "application/json")
+                            .send()
+                            .await?
+                            .error_for_status()?
+                            .json::<ProviderMetadata>()
+                            .await?
+                    }
+                };
+
+                if discovered.issuer.trim_end_matches('/') != config.issuer {
+                    tracing::error!(
+                        provider = %config.name,
+                        issuer = %discovered.issuer,
+                        expected = %config.issuer,
+                        "OIDC provider reported an unexpected issuer"
+                    );
+                    return Err(Error::InternalServerError);
+                }
+
+                Ok(ProviderMetadata {
+                    issuer: discovered.issuer,
+                    authorization_endpoint: config
+                        .authorization_endpoint
+                        .clone()
+                        .unwrap_or(discovered.authorization_endpoint),
+                    token_endpoint: config
+                        .token_endpoint
+                        .clone()
+                        .unwrap_or(discovered.token_endpoint),
+                    userinfo_endpoint: config
+                        .userinfo_endpoint
+                        .clone()
+                        .or(discovered.userinfo_endpoint),
+                    jwks_uri: config.jwks_uri.clone().unwrap_or(discovered.jwks_uri),
+                })
+            })
+            .await
+    }
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn find_key(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+    }
+}
+
+/// The token's `alg` must fit the key's type and curve, and the key's own `alg` if it has one;
+/// symmetric keys are never accepted.
+fn key_allows(jwk: &Jwk, alg: Algorithm) -> bool {
+    let fits_key_type = match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => matches!(
+            alg,
+            Algorithm::RS256
+                | Algorithm::RS384
+                | Algorithm::RS512
+                | Algorithm::PS256
+                | Algorithm::PS384
+                | Algorithm::PS512
+        ),
+        AlgorithmParameters::EllipticCurve(params) => matches!(
+            (&params.curve, alg),
+            (EllipticCurve::P256, Algorithm::ES256) | (EllipticCurve::P384, Algorithm::ES384)
+        ),
+        AlgorithmParameters::OctetKeyPair(_) => alg == Algorithm::EdDSA,
+        AlgorithmParameters::OctetKey(_) => false,
+    };
+    let fits_key_alg = jwk
+        .common
+        .key_algorithm
+        .is_none_or(|key_alg| key_alg.to_string().parse::<Algorithm>().ok() == Some(alg));
+    let signing_key = !matches!(jwk.common.public_key_use, Some(PublicKeyUse::Encryption));
+
+    fits_key_type && fits_key_alg && signing_key
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use axum::{extract::State, routing::get, routing::post, Form, Json, Router};
+    use jsonwebtoken::{
+        encode,
+        jwk::{
+            CommonParameters, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+        },
+        EncodingKey, Header,
+    };
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const CLIENT_ID: &str = "blog";
+    const NONCE: &str = "nonce-123";
+
+    #[derive(Default)]
+    struct Stub {
+        jwks: Mutex<Vec<Jwk>>,
+        jwks_fetches: AtomicUsize,
+        id_token: Mutex<Option<String>>,
+        token_form: Mutex<HashMap<String, String>>,
+    }
+
+    struct SigningKey {
+        kid: String,
+        key: EncodingKey,
+        jwk: Jwk,
+    }
+
+    impl SigningKey {
+        fn new(kid: &str) -> Self {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+
+            Self {
+                kid: kid.to_string(),
+                key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+                jwk: Jwk {
+                    common: CommonParameters {
+                        public_key_use: Some(PublicKeyUse::Signature),
+                        key_algorithm: Some(KeyAlgorithm::EdDSA),
+                        key_id: Some(kid.to_string()),
+                        ..Default::default()
+                    },
+                    algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                    }),
+                },
+            }
+        }
+
+        fn id_token(&self, issuer: &str, nonce: &str) -> String {
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some(self.kid.clone());
+            let claims = json!({
+                "iss": issuer,
+                "aud": CLIENT_ID,
+                "sub": "provider-user-1",
+                "exp": chrono::Utc::now().timestamp() + 300,
+                "nonce": nonce,
+                "email": "reader@example.com",
+                "email_verified": true,
+            });
+
+            encode(&header, &claims, &self.key).unwrap()
+        }
+    }
+
+    async fn token(
+        State(stub): State<Arc<Stub>>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Json<Value> {
+        *stub.token_form.lock().unwrap() = form;
+
+        Json(json!({
+            "access_token": "access-token",
+            "id_token": stub.id_token.lock().unwrap().clone(),
+        }))
+    }
+
+    async fn jwks(State(stub): State<Arc<Stub>>) -> Json<JwkSet> {
+        stub.jwks_fetches.fetch_add(1, Ordering::SeqCst);
+
+        Json(JwkSet {
+            keys: stub.jwks.lock().unwrap().clone(),
+        })
+    }
+
+    async fn github_user() -> Json<Value> {
+        Json(json!({ "id": 42, "login": "octocat", "name": null, "email": null }))
+    }
+
+    async fn github_emails() -> Json<Value> {
+        Json(json!([
+            { "email": "old@example.com", "primary": false, "verified": true },
+            { "email": "octocat@example.com", "primary": true, "verified": true },
+        ]))
+    }
+
+    /// A provider on a local port with the endpoints configured, so discovery is skipped.
+    async fn provider(kind: OidcProviderKind) -> (OidcClient, Arc<Stub>, String) {
+        let stub = Arc::new(Stub::default());
+        let router = Router::new()
+            .route("/token", post(token))
+            .route("/jwks", get(jwks))
+            .route("/user", get(github_user))
+            .route("/user/emails", get(github_emails))
+            .with_state(stub.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let config = OidcProviderConfig {
+            name: "stub".to_string(),
+            kind,
+            issuer: base.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "client-secret".to_string(),
+            redirect_uri: "https://blog.example.com/confirm-auth/oidc/stub".to_string(),
+            scopes: "openid email".to_string(),
+            authorization_endpoint: Some(format!("{base}/authorize")),
+            token_endpoint: Some(format!("{base}/token")),
+            userinfo_endpoint: Some(format!("{base}/user")),
+            jwks_uri: Some(format!("{base}/jwks")),
+        };
+
+        (OidcClient::new(config, Client::new()), stub, base)
+    }
+
+    #[test]
+    fn code_challenge_is_the_unpadded_base64url_sha256_of_the_verifier() {
+        // SHA-256("abc") is ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.
+        assert_eq!(
+            code_challenge("abc"),
+            "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0"
+        );
+    }
+
+    #[tokio::test]
+    async fn authorization_url_carries_state_nonce_and_challenge() {
+        let (client, _, base) = provider(OidcProviderKind::Oidc).await;
+
+        let url = client
+            .authorization_url("state-abc", NONCE, "verifier")
+            .await
+            .unwrap();
+
+        assert!(url.starts_with(&format!("{base}/authorize?response_type=code&")));
+        assert!(url.contains("&state=state-abc&"));
+        assert!(url.contains(&format!("&nonce={NONCE}&")));
+        assert!(url.contains(&format!(
+            "&code_challenge={}&code_challenge_method=S256",
+            code_challenge("verifier")
+        )));
+        assert!(!url.contains("verifier"));
+    }
+
+    #[tokio::test]
+    async fn exchange_sends_the_verifier_and_checks_the_nonce() {
+        let (client, stub, issuer) = provider(OidcProviderKind::Oidc).await;
+        let key = SigningKey::new("k1");
+        stub.jwks.lock().unwrap().push(key.jwk.clone());
+        *stub.id_token.lock().unwrap() = Some(key.id_token(&issuer, NONCE));
+
+        let claims = client
+            .exchange_code("code", "verifier", NONCE)
+            .await
+            .unwrap();
+
+        assert_eq!(claims.sub, "provider-user-1");
+        assert_eq!(claims.email.as_deref(), Some("reader@example.com"));
+        assert!(claims.email_verified);
+        let form = stub.token_form.lock().unwrap().clone();
+        assert_eq!(form["code"], "code");
+        assert_eq!(form["code_verifier"], "verifier");
+
+        assert!(client
+            .exchange_code("code", "verifier", "another-nonce")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn id_tokens_from_another_issuer_are_rejected() {
+        let (client, stub, _) = provider(OidcProviderKind::Oidc).await;
+        let key = SigningKey::new("k1");
+        stub.jwks.lock().unwrap().push(key.jwk.clone());
+        *stub.id_token.lock().unwrap() = Some(key.id_token("https://evil.example.com", NONCE));
+
+        assert!(client
+            .exchange_code("code", "verifier", NONCE)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_key_ids_refetch_the_jwks_at_most_once_per_interval() {
+        let (client, stub, issuer) = provider(OidcProviderKind::Oidc).await;
+        let old_key = SigningKey::new("old");
+        let new_key = SigningKey::new("new");
+        stub.jwks.lock().unwrap().push(old_key.jwk.clone());
+
+        client
+            .verify_id_token(&old_key.id_token(&issuer, NONCE), NONCE)
+            .await
+            .unwrap();
+        assert_eq!(stub.jwks_fetches.load(Ordering::SeqCst), 1);
+
+        // The provider rotates, but the keys were fetched moments ago.
+        stub.jwks.lock().unwrap().push(new_key.jwk.clone());
+        let rotated = new_key.id_token(&issuer, NONCE);
+        for _ in 0..3 {
+            assert!(client.verify_id_token(&rotated, NONCE).await.is_err());
+        }
+        assert_eq!(stub.jwks_fetches.load(Ordering::SeqCst), 1);
+
+        client.jwks.write().await.fetched_at = Some(Instant::now() - JWKS_MIN_REFETCH);
+        client.verify_id_token(&rotated, NONCE).await.unwrap();
+        client.verify_id_token(&rotated, NONCE).await.unwrap();
+        assert_eq!(stub.jwks_fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn tokens_must_use_the_algorithm_of_their_key() {
+        let (client, stub, issuer) = provider(OidcProviderKind::Oidc).await;
+        let key = SigningKey::new("k1");
+        let mut jwk = key.jwk.clone();
+        jwk.common.key_algorithm = Some(KeyAlgorithm::ES256);
+        stub.jwks.lock().unwrap().push(jwk);
+
+        assert!(client
+            .verify_id_token(&key.id_token(&issuer, NONCE), NONCE)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn keys_only_allow_algorithms_of_their_type() {
+        let ed25519 = SigningKey::new("ed").jwk;
+        assert!(key_allows(&ed25519, Algorithm::EdDSA));
+        assert!(!key_allows(&ed25519, Algorithm::RS256));
+        assert!(!key_allows(&ed25519, Algorithm::HS256));
+
+        let mut untagged = ed25519.clone();
+        untagged.common.key_algorithm = None;
+        assert!(key_allows(&untagged, Algorithm::EdDSA));
+
+        let mut encryption = ed25519.clone();
+        encryption.common.public_key_use = Some(PublicKeyUse::Encryption);
+        assert!(!key_allows(&encryption, Algorithm::EdDSA));
+
+        let secret: Jwk = serde_json::from_value(json!({ "kty": "oct", "k": "c2VjcmV0" })).unwrap();
+        assert!(!key_allows(&secret, Algorithm::HS256));
+
+        let p256: Jwk = serde_json::from_value(json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+            "y": "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0",
+        }))
+        .unwrap();
+        assert!(key_allows(&p256, Algorithm::ES256));
+        assert!(!key_allows(&p256, Algorithm::ES384));
+    }
+
+    #[tokio::test]
+    async fn github_identity_comes_from_the_user_api() {
+        let (client, stub, _) = provider(OidcProviderKind::Github).await;
+
+        let claims = client
+            .exchange_code("code", "verifier", NONCE)
+            .await
+            .unwrap();
+
+        assert_eq!(claims.sub, "42");
+        assert_eq!(claims.email.as_deref(), Some("octocat@example.com"));
+        assert!(claims.email_verified);
+        assert_eq!(claims.preferred_username.as_deref(), Some("octocat"));
+        assert_eq!(stub.jwks_fetches.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::Result;
+
+const BUNDLED_CORPUS: &str = include_str!("breached-sha1.txt");
+const PREFIX_LENGTH: usize = 5;
+
+/// Offline breached-password lookup using the k-anonymity layout of Pwned Passwords: SHA-1
+/// hashes are split into a 5-character prefix and the remaining suffix.
+///
+/// The bundled list is only a sample of about 130 of the most common leaked passwords, so
+/// operators who rely on this check must supply a real corpus, such as the Pwned Passwords
+/// download. `PASSWORD_BREACH_CORPUS` may point at a `HASH:COUNT` file (loaded into memory)
+/// or at a directory of per-prefix range files (`<PREFIX>` or `<PREFIX>.txt` containing
+/// `SUFFIX:COUNT` lines) that are read on demand.
+pub struct BreachCorpus {
+    hashes: HashMap<String, HashSet<String>>,
+    range_dir: Option<PathBuf>,
+}
+
+impl BreachCorpus {
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let mut corpus = Self {
+            hashes: HashMap::new(),
+            range_dir: None,
+        };
+        corpus.add_hashes(BUNDLED_CORPUS);
+
+        if let Some(path) = path.map(Path::new) {
+            if path.is_dir() {
+                corpus.range_dir = Some(path.to_path_buf());
+            } else {
+                let contents = fs::read_to_string(path).map_err(|err| {
+                    format!("Failed to read breach corpus {}: {}", path.display(), err)
+                })?;
+                corpus.add_hashes(&contents);
+            }
+        }
+
+        Ok(corpus)
+    }
+
+    pub async fn contains(&self, password: &str) -> bool {
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+        if self
+            .hashes
+            .get(prefix)
+            .is_some_and(|suffixes| suffixes.contains(suffix))
+        {
+            return true;
+        }
+
+        let Some(range_dir) = &self.range_dir else {
+            return false;
+        };
+
+        for file_name in [prefix.to_string(), format!("{}.txt", prefix)] {
+            if let Ok(range) = tokio::fs::read_to_string(range_dir.join(file_name)).await {
+                return range.lines().any(|line| {
+                    let (candidate, count) = line.trim().split_once(':').unwrap_or((line, "1"));
+                    candidate.eq_ignore_ascii_case(suffix) && count.trim() != "0"
+                });
+            }
+        }
+
+        false
+    }
+
+    fn add_hashes(&mut self, contents: &str) {
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let hash = line.split(':').next().unwrap_or_default().to_uppercase();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+
+            let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+            self.hashes
+                .entry(prefix.to_string())
+                .or_default()
+                .insert(suffix.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn sha1_hex(password: &str) -> String {
+        Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}", name, Uuid::now_v7()))
+    }
+
+    #[tokio::test]
+    async fn bundled_sample_is_always_checked() {
+        let corpus = BreachCorpus::load(None).unwrap();
+
+        assert!(corpus.contains("password").await);
+        assert!(!corpus.contains("quiet-harbor-lantern-42").await);
+    }
+
+    #[tokio::test]
+    async fn hash_files_add_to_the_bundled_sample() {
+        let path = temp_path("breach-corpus");
+        fs::write(
+            &path,
+            format!(
+                "# comment\n{}:42\nnot-a-hash:1\n",
+                sha1_hex("quiet-harbor-lantern-42").to_lowercase()
+            ),
+        )
+        .unwrap();
+
+        let corpus = BreachCorpus::load(path.to_str()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(corpus.contains("quiet-harbor-lantern-42").await);
+        assert!(corpus.contains("password").await);
+        assert!(!corpus.contains("still-unbreached-99").await);
+    }
+
+    #[tokio::test]
+    async fn range_directories_are_read_by_prefix() {
+        let dir = temp_path("breach-ranges");
+        fs::create_dir_all(&dir).unwrap();
+
+        let breached = sha1_hex("quiet-harbor-lantern-42");
+        let (prefix, suffix) = breached.split_at(PREFIX_LENGTH);
+        fs::write(dir.join(format!("{prefix}.txt")), format!("{suffix}:3\r\n")).unwrap();
+
+        let padded = sha1_hex("still-unbreached-99");
+        let (prefix, suffix) = padded.split_at(PREFIX_LENGTH);
+        fs::write(dir.join(prefix), format!("{suffix}:0\n")).unwrap();
+
+        let corpus = BreachCorpus::load(dir.to_str()).unwrap();
+
+        assert!(corpus.contains("quiet-harbor-lantern-42").await);
+        assert!(!corpus.contains("still-unbreached-99").await);
+        assert!(!corpus.contains("never-listed-anywhere-7").await);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_corpus_file_is_an_error() {
+        assert!(BreachCorpus::load(temp_path("missing-corpus").to_str()).is_err());
+    }
+}
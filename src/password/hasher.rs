@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use argon2::{
+    password_hash::{self, rand_core::OsRng, PasswordHasher as _, SaltString},
+    Algorithm, Argon2, KeyId, Params, ParamsBuilder, PasswordHash, PasswordVerifier, Version,
+};
+
+use crate::{config::PasswordHashConfig, Error, Result};
+
+/// Argon2id hashing with the configured cost parameters and optional pepper.
+///
+/// Peppered hashes carry the pepper id as the PHC `keyid`, so hashes made before a pepper
+/// was configured, or with a retired one, still verify and get picked up by `needs_rehash`.
+pub struct PasswordHasher {
+    params: Params,
+    pepper: Option<Vec<u8>>,
+    previous_peppers: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PasswordHasher {
+    pub fn new(config: PasswordHashConfig) -> Result<Self> {
+        let mut builder = ParamsBuilder::new();
+        builder
+            .m_cost(config.memory_kib)
+            .t_cost(config.iterations)
+            .p_cost(config.parallelism);
+
+        if config.pepper.is_some() {
+            builder.keyid(
+                KeyId::new(config.pepper_id.as_bytes()).map_err(password_hash::Error::from)?,
+            );
+        }
+
+        let params = builder.build().map_err(password_hash::Error::from)?;
+        let pepper = config.pepper.map(String::into_bytes);
+
+        let mut previous_peppers = HashMap::new();
+        for entry in &config.previous_peppers {
+            let (id, pepper) = entry
+                .split_once(':')
+                .filter(|(id, pepper)| !id.is_empty() && !pepper.is_empty())
+                .ok_or("Previous peppers must look like <pepper_id>:<pepper>".to_string())?;
+            KeyId::new(id.as_bytes()).map_err(password_hash::Error::from)?;
+            previous_peppers.insert(id.as_bytes().to_vec(), pepper.as_bytes().to_vec());
+        }
+
+        Ok(Self {
+            params,
+            pepper,
+            previous_peppers,
+        })
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        Ok(self
+            .argon2(self.pepper.as_deref())?
+            .hash_password(password.as_bytes(), &salt)?
+            .to_string())
+    }
+
+    pub fn verify(&self, password: &str, hash: &str) -> Result<()> {
+        let parsed_hash = PasswordHash::new(hash).map_err(|_| Error::InternalServerError)?;
+        let keyid = Params::try_from(&parsed_hash)
+            .map_err(|_| Error::InternalServerError)?
+            .keyid()
+            .to_vec();
+
+        let pepper = if keyid.is_empty() {
+            None
+        } else if keyid == self.params.keyid() {
+            self.pepper.as_deref()
+        } else if let Some(pepper) = self.previous_peppers.get(&keyid) {
+            Some(pepper.as_slice())
+        } else {
+            tracing::error!(
+                key_id = %String::from_utf8_lossy(&keyid),
+                "Password hash was peppered with a key id that is not configured"
+            );
+            return Err(Error::InternalServerError);
+        };
+
+        self.argon2(pepper)?
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .map_err(|_| Error::BadRequest("Invalid password!".to_string()))
+    }
+
+    /// Whether `hash` was made with a different algorithm, cost or pepper than the current one.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(hash) else {
+            return false;
+        };
+        let Ok(params) = Params::try_from(&parsed_hash) else {
+            return false;
+        };
+
+        parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+            || params.keyid() != self.params.keyid()
+    }
+
+    fn argon2<'a>(&'a self, pepper: Option<&'a [u8]>) -> Result<Argon2<'a>> {
+        match pepper {
+            Some(pepper) => Ok(Argon2::new_with_secret(
+                pepper,
+                Algorithm::Argon2id,
+                Version::V0x13,
+                self.params.clone(),
+            )
+            .map_err(password_hash::Error::from)?),
+            None => Ok(Argon2::new(
+                Algorithm::Argon2id,
+                Version::V0x13,
+                self.params.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "quiet-harbor-lantern-42";
+
+    fn hasher(pepper: Option<(&str, &str)>, previous_peppers: &[&str]) -> PasswordHasher {
+        PasswordHasher::new(PasswordHashConfig {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+            pepper: pepper.map(|(_, pepper)| pepper.to_string()),
+            pepper_id: pepper.map_or("p1", |(id, _)| id).to_string(),
+            previous_peppers: previous_peppers
+                .iter()
+                .map(|entry| entry.to_string())
+                .collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn hashes_verify_only_the_right_password() {
+        let hasher = hasher(Some(("p1", "pepper-one")), &[]);
+        let hash = hasher.hash(PASSWORD).unwrap();
+
+        assert!(hasher.verify(PASSWORD, &hash).is_ok());
+        assert!(matches!(
+            hasher.verify("wrong-password", &hash),
+            Err(Error::BadRequest(_))
+        ));
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn unpeppered_hashes_verify_and_need_a_rehash_once_a_pepper_is_set() {
+        let hash = hasher(None, &[]).hash(PASSWORD).unwrap();
+        let peppered = hasher(Some(("p1", "pepper-one")), &[]);
+
+        assert!(peppered.verify(PASSWORD, &hash).is_ok());
+        assert!(peppered.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn hashes_from_a_previous_pepper_verify_and_need_a_rehash() {
+        let hash = hasher(Some(("p1", "pepper-one")), &[])
+            .hash(PASSWORD)
+            .unwrap();
+        let rotated = hasher(Some(("p2", "pepper-two")), &["p1:pepper-one"]);
+
+        assert!(rotated.verify(PASSWORD, &hash).is_ok());
+        assert!(matches!(
+            rotated.verify("wrong-password", &hash),
+            Err(Error::BadRequest(_))
+        ));
+        assert!(rotated.needs_rehash(&hash));
+        assert!(!rotated.needs_rehash(&rotated.hash(PASSWORD).unwrap()));
+    }
+
+    #[test]
+    fn hashes_from_an_unknown_pepper_are_an_internal_error() {
+        let hash = hasher(Some(("p1", "pepper-one")), &[])
+            .hash(PASSWORD)
+            .unwrap();
+
+        assert!(matches!(
+            hasher(Some(("p2", "pepper-two")), &[]).verify(PASSWORD, &hash),
+            Err(Error::InternalServerError)
+        ));
+        assert!(matches!(
+            hasher(Some(("p2", "pepper-two")), &["p1:not-the-pepper"]).verify(PASSWORD, &hash),
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_previous_peppers_are_rejected() {
+        for entry in ["no-separator", ":pepper", "p1:", "much-too-long-id:pepper"] {
+            let config = PasswordHashConfig {
+                previous_peppers: vec![entry.to_string()],
+                ..PasswordHashConfig::default()
+            };
+            assert!(PasswordHasher::new(config).is_err(), "{entry}");
+        }
+    }
+}
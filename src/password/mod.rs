@@ -0,0 +1,4 @@
+pub mod breach;
+pub mod hasher;
+pub mod policy;
+pub mod strength;
@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{config::PasswordPolicyConfig, Error, Result};
+
+use super::{breach::BreachCorpus, strength::estimate};
+
+const MIN_PERSONAL_TOKEN_LENGTH: usize = 3;
+
+pub struct PasswordPolicy {
+    config: PasswordPolicyConfig,
+    corpus: BreachCorpus,
+}
+
+impl PasswordPolicy {
+    pub fn new(config: PasswordPolicyConfig) -> Result<Self> {
+        let corpus = BreachCorpus::load(config.breach_corpus.as_deref())?;
+        if config.check_breached && config.breach_corpus.is_none() {
+            tracing::warn!(
+                "No breach corpus configured; only the bundled sample of breached passwords \
+                 is checked. Set PASSWORD_BREACH_CORPUS to a Pwned Passwords download."
+            );
+        }
+
+        Ok(Self { config, corpus })
+    }
+
+    /// Checks `password` against the policy; `user_inputs` are the account's name and email.
+    /// Every failed rule is reported under `field`.
+    pub async fn validate(
+        &self,
+        field: &'static str,
+        password: &str,
+        user_inputs: &[&str],
+    ) -> Result<()> {
+        let mut messages = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.config.min_length {
+            messages.push(format!(
+                "Password must be at least {} characters",
+                self.config.min_length
+            ));
+        }
+
+        if length > self.config.max_length {
+            messages.push(format!(
+                "Password must be at most {} characters",
+                self.config.max_length
+            ));
+        }
+
+        if contains_personal_info(password, user_inputs) {
+            messages.push("Password must not contain your name or email".to_string());
+        }
+
+        let strength = estimate(password);
+        if strength.score < self.config.min_score {
+            messages.push(format!("Password is too weak. {}", strength.feedback));
+        }
+
+        if self.config.check_breached && self.corpus.contains(password).await {
+            messages.push(
+                "This password has appeared in a data breach. Please choose a different one."
+                    .to_string(),
+            );
+        }
+
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let mut errors = ValidationErrors::new();
+        for message in messages {
+            errors.add(
+                field,
+                ValidationError::new("password_policy").with_message(Cow::from(message)),
+            );
+        }
+
+        Err(Error::Validation(errors))
+    }
+}
+
+fn contains_personal_info(password: &str, user_inputs: &[&str]) -> bool {
+    let password = password.to_lowercase();
+
+    user_inputs
+        .iter()
+        .flat_map(|input| {
+            // Only the local part of an email is personal; the domain is shared by many.
+            let input = input.trim().to_lowercase();
+            let input = input.split('@').next().unwrap_or_default().to_string();
+            let parts: Vec<String> = input
+                .split(|c: char| c.is_whitespace() || "._-+".contains(c))
+                .map(str::to_string)
+                .collect();
+
+            std::iter::once(input).chain(parts)
+        })
+        .filter(|token| token.chars().count() >= MIN_PERSONAL_TOKEN_LENGTH)
+        .any(|token| password.contains(&token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRONG: &str = "quiet-harbor-lantern-42";
+
+    fn policy(config: PasswordPolicyConfig) -> PasswordPolicy {
+        PasswordPolicy::new(config).unwrap()
+    }
+
+    async fn problems(policy: &PasswordPolicy, password: &str) -> Vec<String> {
+        match policy
+            .validate("password", password, &["Ada Lovelace", "ada.l@example.com"])
+            .await
+        {
+            Ok(()) => Vec::new(),
+            Err(Error::Validation(errors)) => errors.field_errors()["password"]
+                .iter()
+                .map(|error| error.message.as_deref().unwrap_or_default().to_string())
+                .collect(),
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn strong_passwords_pass() {
+        let policy = policy(PasswordPolicyConfig::default());
+
+        assert!(problems(&policy, STRONG).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn length_limits_are_enforced() {
+        let policy = policy(PasswordPolicyConfig {
+            min_length: 10,
+            max_length: 24,
+            ..PasswordPolicyConfig::default()
+        });
+
+        assert_eq!(
+            problems(&policy, "T7#kq9!V").await,
+            ["Password must be at least 10 characters"]
+        );
+        assert_eq!(
+            problems(&policy, &format!("{STRONG}-{STRONG}")).await,
+            ["Password must be at most 24 characters"]
+        );
+    }
+
+    #[tokio::test]
+    async fn names_and_email_local_parts_are_rejected() {
+        let policy = policy(PasswordPolicyConfig::default());
+
+        for password in [
+            "lovelace-harbor-42",
+            "Ada.L-quiet-harbor",
+            "harbor-ada.l-42x",
+        ] {
+            assert!(
+                problems(&policy, password)
+                    .await
+                    .contains(&"Password must not contain your name or email".to_string()),
+                "{password}"
+            );
+        }
+        assert!(problems(&policy, "example-harbor-lantern-42")
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn weak_passwords_are_rejected_below_the_minimum_score() {
+        let strict = policy(PasswordPolicyConfig {
+            check_breached: false,
+            ..PasswordPolicyConfig::default()
+        });
+        let lenient = policy(PasswordPolicyConfig {
+            min_length: 1,
+            min_score: 0,
+            check_breached: false,
+            ..PasswordPolicyConfig::default()
+        });
+
+        let problems_found = problems(&strict, "aaaaaaaaaaaa").await;
+        assert_eq!(problems_found.len(), 1);
+        assert!(problems_found[0].starts_with("Password is too weak."));
+        assert!(problems(&lenient, "aaaaaaaaaaaa").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn breached_passwords_are_rejected_only_when_checked() {
+        let breached =
+            "This password has appeared in a data breach. Please choose a different one.";
+        let checked = policy(PasswordPolicyConfig {
+            min_length: 1,
+            min_score: 0,
+            ..PasswordPolicyConfig::default()
+        });
+        let unchecked = policy(PasswordPolicyConfig {
+            min_length: 1,
+            min_score: 0,
+            check_breached: false,
+            ..PasswordPolicyConfig::default()
+        });
+
+        assert_eq!(problems(&checked, "password").await, [breached]);
+        assert!(problems(&unchecked, "password").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn every_failed_rule_is_reported() {
+        let policy = policy(PasswordPolicyConfig::default());
+
+        assert_eq!(problems(&policy, "ada123").await.len(), 3);
+    }
+}
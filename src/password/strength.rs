@@ -0,0 +1,223 @@
+const COMMON_PASSWORDS: &str = include_str!("common-passwords.txt");
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const MIN_WORD_LENGTH: usize = 4;
+
+pub struct Strength {
+    /// 0 (trivially guessable) to 4 (very unguessable), on the same scale as zxcvbn.
+    pub score: u8,
+    pub feedback: &'static str,
+}
+
+/// Rough guess-count estimate: common passwords are ranked by popularity, everything else is
+/// brute force over the character classes in use, with repeats, sequences, keyboard walks
+/// and dictionary words discounted.
+pub fn estimate(password: &str) -> Strength {
+    let lowercase = password.to_lowercase();
+    let core = lowercase.trim_end_matches(|c: char| !c.is_alphabetic());
+    let suffix: Vec<char> = lowercase[core.len()..].chars().collect();
+
+    let (log_guesses, feedback) = if let Some(rank) = common_rank(&lowercase) {
+        (
+            ((rank + 1) as f64).log10(),
+            "This is a commonly used password.",
+        )
+    } else if let Some(rank) = common_rank(core) {
+        (
+            ((rank + 1) as f64).log10() + pattern_log_guesses(&suffix).0,
+            "Adding numbers or symbols to a common password doesn't make it much stronger.",
+        )
+    } else {
+        let chars: Vec<char> = password.chars().collect();
+        let (mut log_guesses, predictable) = pattern_log_guesses(&chars);
+
+        let normalized: String = lowercase.chars().map(unleet).collect();
+        let word_length = longest_common_word(&normalized);
+        log_guesses -= word_length as f64 * 0.8 * charset_log(&chars);
+
+        let feedback = if predictable * 2 >= chars.len() {
+            "Avoid repeated characters, sequences and keyboard patterns."
+        } else if word_length > 0 {
+            "Avoid common words and names."
+        } else {
+            "Add another word or a few more characters."
+        };
+
+        (log_guesses.max(0.0), feedback)
+    };
+
+    Strength {
+        score: score(log_guesses),
+        feedback,
+    }
+}
+
+/// Maps log10 of the guesses needed onto zxcvbn's 0-4 scale.
+fn score(log_guesses: f64) -> u8 {
+    match log_guesses {
+        guesses if guesses < 3.0 => 0,
+        guesses if guesses < 6.0 => 1,
+        guesses if guesses < 8.0 => 2,
+        guesses if guesses < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+fn common_rank(candidate: &str) -> Option<usize> {
+    if candidate.is_empty() {
+        return None;
+    }
+
+    COMMON_PASSWORDS
+        .lines()
+        .position(|common| common.chars().map(unleet).eq(candidate.chars().map(unleet)))
+}
+
+fn longest_common_word(normalized: &str) -> usize {
+    COMMON_PASSWORDS
+        .lines()
+        .filter(|word| word.len() >= MIN_WORD_LENGTH && word.chars().all(char::is_alphabetic))
+        .filter(|word| normalized.contains(*word))
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Returns log10 of the brute-force guesses and how many characters were predictable.
+fn pattern_log_guesses(chars: &[char]) -> (f64, usize) {
+    let mut effective_length = 0.0;
+    let mut predictable = 0;
+
+    for (index, &current) in chars.iter().enumerate() {
+        let follows_pattern = index > 0 && is_predictable(chars[index - 1], current);
+        if follows_pattern {
+            predictable += 1;
+            effective_length += 0.2;
+        } else {
+            effective_length += 1.0;
+        }
+    }
+
+    (effective_length * charset_log(chars), predictable)
+}
+
+fn is_predictable(previous: char, current: char) -> bool {
+    let (previous, current) = (previous.to_ascii_lowercase(), current.to_ascii_lowercase());
+
+    if previous == current {
+        return true;
+    }
+
+    if previous.is_ascii_alphanumeric()
+        && current.is_ascii_alphanumeric()
+        && (previous as i32 - current as i32).abs() == 1
+    {
+        return true;
+    }
+
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| match (row.find(previous), row.find(current)) {
+            (Some(a), Some(b)) => a.abs_diff(b) == 1,
+            _ => false,
+        })
+}
+
+fn charset_log(chars: &[char]) -> f64 {
+    let mut size = 0;
+    if chars.iter().any(char::is_ascii_lowercase) {
+        size += 26;
+    }
+    if chars.iter().any(char::is_ascii_uppercase) {
+        size += 26;
+    }
+    if chars.iter().any(char::is_ascii_digit) {
+        size += 10;
+    }
+    if chars
+        .iter()
+        .any(|c| c.is_ascii() && !c.is_ascii_alphanumeric())
+    {
+        size += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100;
+    }
+
+    (size.max(1) as f64).log10()
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_change_at_each_threshold() {
+        for (log_guesses, expected) in [
+            (0.0, 0),
+            (2.99, 0),
+            (3.0, 1),
+            (5.99, 1),
+            (6.0, 2),
+            (7.99, 2),
+            (8.0, 3),
+            (9.99, 3),
+            (10.0, 4),
+            (20.0, 4),
+        ] {
+            assert_eq!(score(log_guesses), expected, "{log_guesses}");
+        }
+    }
+
+    #[test]
+    fn common_passwords_score_zero_even_in_leetspeak() {
+        for password in ["password", "p@ssw0rd", "qwertyuiop", "1234567890"] {
+            let strength = estimate(password);
+            assert_eq!(strength.score, 0, "{password}");
+            assert_eq!(strength.feedback, "This is a commonly used password.");
+        }
+    }
+
+    #[test]
+    fn suffixes_barely_help_a_common_password() {
+        let strength = estimate("Password1!");
+
+        assert!(strength.score <= 1);
+        assert!(strength.feedback.starts_with("Adding numbers or symbols"));
+    }
+
+    #[test]
+    fn repeats_sequences_and_common_words_are_discounted() {
+        for password in ["aaaaaaaaaaaa", "abcdefghij"] {
+            let strength = estimate(password);
+            assert!(strength.score <= 1, "{password}");
+            assert_eq!(
+                strength.feedback,
+                "Avoid repeated characters, sequences and keyboard patterns."
+            );
+        }
+
+        let strength = estimate("monkey7kq");
+        assert!(strength.score <= 1);
+        assert_eq!(strength.feedback, "Avoid common words and names.");
+    }
+
+    #[test]
+    fn length_and_variety_raise_the_score() {
+        assert_eq!(estimate("kq9v").score, 2);
+        assert_eq!(estimate("kq9vx3").score, 3);
+        assert_eq!(estimate("quiet-harbor-lantern-42").score, 4);
+        assert_eq!(estimate("T7#kq9!Vz2@wLp").score, 4);
+    }
+}
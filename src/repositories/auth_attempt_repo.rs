@@ -0,0 +1,236 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::users::AuthAction, Result};
+
+use super::PostgresRepo;
+
+#[async_trait]
+pub trait AuthAttemptRepository: Send + Sync {
+    async fn record_attempt(&self, action: AuthAction, key: &str, failed: bool) -> Result<()>;
+    async fn count_attempts(
+        &self,
+        action: AuthAction,
+        key: &str,
+        since: DateTime<Utc>,
+        failed_only: bool,
+    ) -> Result<i64>;
+    async fn clear_failures(&self, action: AuthAction, key: &str) -> Result<()>;
+    async fn lock_account(&self, key: &str, until: DateTime<Utc>) -> Result<bool>;
+    async fn locked_until(&self, key: &str) -> Result<Option<DateTime<Utc>>>;
+    async fn prune_attempts(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+#[async_trait]
+impl AuthAttemptRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn record_attempt(&self, action: AuthAction, key: &str, failed: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO auth_attempts (id, action, key, failed)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(Uuid::now_v7())
+        .bind(action)
+        .bind(key)
+        .bind(failed)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn count_attempts(
+        &self,
+        action: AuthAction,
+        key: &str,
+        since: DateTime<Utc>,
+        failed_only: bool,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM auth_attempts
+            WHERE key = $1 AND action = $2 AND created_at > $3 AND (failed OR NOT $4)
+            "#,
+        )
+        .bind(key)
+        .bind(action)
+        .bind(since)
+        .bind(failed_only)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    #[instrument(skip_all)]
+    async fn clear_failures(&self, action: AuthAction, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM auth_attempts WHERE key = $1 AND action = $2 AND failed")
+            .bind(key)
+            .bind(action)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn lock_account(&self, key: &str, until: DateTime<Utc>) -> Result<bool> {
+        let locked = sqlx::query_scalar::<_, String>(
+            r#"
+            INSERT INTO account_lockouts (key, locked_until)
+            VALUES ($1, $2)
+            ON CONFLICT (key) DO UPDATE
+            SET locked_until = EXCLUDED.locked_until, created_at = NOW()
+            WHERE account_lockouts.locked_until <= NOW()
+            RETURNING key
+            "#,
+        )
+        .bind(key)
+        .bind(until)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(locked.is_some())
+    }
+
+    #[instrument(skip_all)]
+    async fn locked_until(&self, key: &str) -> Result<Option<DateTime<Utc>>> {
+        let locked_until = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT locked_until FROM account_lockouts WHERE key = $1 AND locked_until > NOW()",
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(locked_until)
+    }
+
+    #[instrument(skip_all)]
+    async fn prune_attempts(&self, before: DateTime<Utc>) -> Result<u64> {
+        let attempts = sqlx::query("DELETE FROM auth_attempts WHERE created_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM account_lockouts WHERE locked_until < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(attempts.rows_affected())
+    }
+}
+
+type AttemptLog = VecDeque<(DateTime<Utc>, bool)>;
+
+#[derive(Default)]
+pub struct MemoryAttemptStore {
+    state: Mutex<MemoryAttempts>,
+}
+
+#[derive(Default)]
+struct MemoryAttempts {
+    attempts: HashMap<(AuthAction, String), AttemptLog>,
+    lockouts: HashMap<String, DateTime<Utc>>,
+}
+
+#[async_trait]
+impl AuthAttemptRepository for MemoryAttemptStore {
+    #[instrument(skip_all)]
+    async fn record_attempt(&self, action: AuthAction, key: &str, failed: bool) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .attempts
+            .entry((action, key.to_string()))
+            .or_default()
+            .push_back((Utc::now(), failed));
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn count_attempts(
+        &self,
+        action: AuthAction,
+        key: &str,
+        since: DateTime<Utc>,
+        failed_only: bool,
+    ) -> Result<i64> {
+        let state = self.state.lock().unwrap();
+        let count = state
+            .attempts
+            .get(&(action, key.to_string()))
+            .map(|attempts| {
+                attempts
+                    .iter()
+                    .filter(|(at, failed)| *at > since && (*failed || !failed_only))
+                    .count()
+            })
+            .unwrap_or_default();
+
+        Ok(count as i64)
+    }
+
+    #[instrument(skip_all)]
+    async fn clear_failures(&self, action: AuthAction, key: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(attempts) = state.attempts.get_mut(&(action, key.to_string())) {
+            attempts.retain(|(_, failed)| !failed);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn lock_account(&self, key: &str, until: DateTime<Utc>) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        let now = Utc::now();
+
+        match state.lockouts.get(key) {
+            Some(locked_until) if *locked_until > now => Ok(false),
+            _ => {
+                state.lockouts.insert(key.to_string(), until);
+                Ok(true)
+            }
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn locked_until(&self, key: &str) -> Result<Option<DateTime<Utc>>> {
+        let state = self.state.lock().unwrap();
+
+        Ok(state
+            .lockouts
+            .get(key)
+            .copied()
+            .filter(|locked_until| *locked_until > Utc::now()))
+    }
+
+    #[instrument(skip_all)]
+    async fn prune_attempts(&self, before: DateTime<Utc>) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let now = Utc::now();
+        let mut pruned = 0;
+
+        state.attempts.retain(|_, attempts| {
+            while attempts.front().is_some_and(|(at, _)| *at < before) {
+                attempts.pop_front();
+                pruned += 1;
+            }
+            !attempts.is_empty()
+        });
+        state.lockouts.retain(|_, locked_until| *locked_until > now);
+
+        Ok(pruned)
+    }
+}
@@ -1,98 +1,410 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use tracing::instrument;
 use uuid::Uuid;
 
-use crate::{models::users::User, Result};
+use crate::{
+    mail::sendmail::OutgoingEmail,
+    models::users::{EmailChangeRequest, TokenPurpose, User},
+    Error, Result,
+};
 
-use super::PostgresRepo;
+use super::{
+    outbox_repo::enqueue_email,
+    token_repo::{consume_user_token, insert_user_token, revoke_user_tokens},
+    PostgresRepo,
+};
 
 #[async_trait]
 pub trait AuthRepository: Send + Sync {
+    #[allow(clippy::too_many_arguments)]
     async fn create_user(
         &self,
         name: String,
         email: String,
         password: String,
-        verification_token: String,
-        token_expires_at: Option<DateTime<Utc>>,
+        locale: &str,
+        verification_token_hash: &str,
+        token_expires_at: DateTime<Utc>,
+        verification_email: &OutgoingEmail,
     ) -> Result<User>;
-    async fn add_verifed_token(
+    async fn issue_user_token(
         &self,
         user_id: Uuid,
+        purpose: TokenPurpose,
+        token_hash: &str,
         token_expires_at: DateTime<Utc>,
-        token: &str,
+        email: &OutgoingEmail,
     ) -> Result<()>;
-
-    async fn verifed_token(&self, token: &str) -> Result<()>;
+    async fn verify_user_email(
+        &self,
+        token_hash: &str,
+        welcome_email: &OutgoingEmail,
+    ) -> Result<bool>;
+    async fn reset_user_password(&self, token_hash: &str, password_hash: &str) -> Result<bool>;
+    async fn consume_magic_link(&self, token_hash: &str) -> Result<Option<User>>;
+    async fn create_email_change(
+        &self,
+        request: &EmailChangeRequest,
+        confirm_token_hash: &str,
+        confirmation_email: &OutgoingEmail,
+        notice_email: &OutgoingEmail,
+    ) -> Result<()>;
+    async fn confirm_email_change(&self, confirm_token_hash: &str) -> Result<Option<User>>;
+    async fn cancel_email_change(&self, cancel_token_hash: &str) -> Result<bool>;
+    async fn email_reserved(&self, email: &str) -> Result<bool>;
 }
 
 #[async_trait]
 impl AuthRepository for PostgresRepo {
+    #[instrument(skip_all)]
     async fn create_user(
         &self,
         name: String,
         email: String,
         password: String,
-        verification_token: String,
-        token_expires_at: Option<DateTime<Utc>>,
+        locale: &str,
+        verification_token_hash: &str,
+        token_expires_at: DateTime<Utc>,
+        verification_email: &OutgoingEmail,
     ) -> Result<User> {
+        let mut tx = self.pool.begin().await?;
+
         let user = sqlx::query_as::<_, User>(
             r#"
-            INSERT INTO users (id, name, email, password, verification_token, token_expires_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role
+            INSERT INTO users (id, name, email, password, locale)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
             "#,
         )
         .bind(Uuid::now_v7())
         .bind(name)
-        .bind(email)
+        .bind(&email)
         .bind(password)
-        .bind(verification_token)
-        .bind(token_expires_at)
-        .fetch_one(&self.pool)
+        .bind(locale)
+        .fetch_one(&mut *tx)
         .await?;
 
+        insert_user_token(
+            &mut tx,
+            user.id,
+            TokenPurpose::VerifyEmail,
+            verification_token_hash,
+            Some(&email),
+            token_expires_at,
+        )
+        .await?;
+
+        enqueue_email(&mut tx, verification_email).await?;
+        tx.commit().await?;
+
         Ok(user)
     }
 
-    async fn add_verifed_token(
+    #[instrument(skip_all, fields(user_id = %user_id))]
+    async fn issue_user_token(
         &self,
         user_id: Uuid,
+        purpose: TokenPurpose,
+        token_hash: &str,
         token_expires_at: DateTime<Utc>,
-        token: &str,
+        email: &OutgoingEmail,
     ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        insert_user_token(
+            &mut tx,
+            user_id,
+            purpose,
+            token_hash,
+            Some(&email.to),
+            token_expires_at,
+        )
+        .await?;
+
+        enqueue_email(&mut tx, email).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn verify_user_email(
+        &self,
+        token_hash: &str,
+        welcome_email: &OutgoingEmail,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(token) =
+            consume_user_token(&mut tx, TokenPurpose::VerifyEmail, token_hash).await?
+        else {
+            return Ok(false);
+        };
+
         sqlx::query(
             r#"
             UPDATE users
-            SET verification_token = $1,
-                token_expires_at = $2
-            WHERE id = $3
+            SET verified = true,
+                updated_at = Now()
+            WHERE id = $1
             "#,
         )
-        .bind(token)
-        .bind(token_expires_at)
-        .bind(user_id)
-        .execute(&self.pool)
+        .bind(token.user_id)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(())
+        enqueue_email(&mut tx, welcome_email).await?;
+        tx.commit().await?;
+
+        Ok(true)
     }
 
-    async fn verifed_token(&self, token: &str) -> Result<()> {
+    #[instrument(skip_all)]
+    async fn reset_user_password(&self, token_hash: &str, password_hash: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(token) =
+            consume_user_token(&mut tx, TokenPurpose::ResetPassword, token_hash).await?
+        else {
+            return Ok(false);
+        };
+
         sqlx::query(
             r#"
             UPDATE users
-            SET verified = true,
-                updated_at = Now(),
-                verification_token = NULL,
-                token_expires_at = NULL
-            WHERE verification_token = $1
+            SET password = $1, updated_at = Now()
+            WHERE id = $2
+            "#,
+        )
+        .bind(password_hash)
+        .bind(token.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    #[instrument(skip_all)]
+    async fn consume_magic_link(&self, token_hash: &str) -> Result<Option<User>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(token) = consume_user_token(&mut tx, TokenPurpose::MagicLink, token_hash).await?
+        else {
+            return Ok(None);
+        };
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
+            FROM users
+            WHERE id = $1 AND email = $2 AND verified AND magic_link_enabled
+            "#,
+        )
+        .bind(token.user_id)
+        .bind(&token.email)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    #[instrument(skip_all)]
+    async fn create_email_change(
+        &self,
+        request: &EmailChangeRequest,
+        confirm_token_hash: &str,
+        confirmation_email: &OutgoingEmail,
+        notice_email: &OutgoingEmail,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE email_change_requests
+            SET cancelled_at = NOW()
+            WHERE user_id = $1 AND confirmed_at IS NULL AND cancelled_at IS NULL
+            "#,
+        )
+        .bind(request.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_change_requests (id, user_id, new_email, cancel_token_hash, expires_at, cancel_until)
+            VALUES ($1, $2, $3, $4, $5, $6)
             "#,
         )
-        .bind(token)
-        .execute(&self.pool)
+        .bind(request.id)
+        .bind(request.user_id)
+        .bind(&request.new_email)
+        .bind(&request.cancel_token_hash)
+        .bind(request.expires_at)
+        .bind(request.cancel_until)
+        .execute(&mut *tx)
+        .await?;
+
+        insert_user_token(
+            &mut tx,
+            request.user_id,
+            TokenPurpose::ChangeEmail,
+            confirm_token_hash,
+            Some(&request.new_email),
+            request.expires_at,
+        )
         .await?;
 
+        enqueue_email(&mut tx, confirmation_email).await?;
+        enqueue_email(&mut tx, notice_email).await?;
+        tx.commit().await?;
+
         Ok(())
     }
+
+    #[instrument(skip_all)]
+    async fn confirm_email_change(&self, confirm_token_hash: &str) -> Result<Option<User>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(token) =
+            consume_user_token(&mut tx, TokenPurpose::ChangeEmail, confirm_token_hash).await?
+        else {
+            return Ok(None);
+        };
+
+        let request = sqlx::query_as::<_, EmailChangeRequest>(
+            r#"
+            SELECT id, user_id, new_email, cancel_token_hash, expires_at, cancel_until
+            FROM email_change_requests
+            WHERE user_id = $1
+              AND new_email = $2
+              AND confirmed_at IS NULL
+              AND cancelled_at IS NULL
+              AND expires_at > NOW()
+            FOR UPDATE
+            "#,
+        )
+        .bind(token.user_id)
+        .bind(&token.email)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(request) = request else {
+            return Ok(None);
+        };
+
+        // The old address is kept so a cancel inside the window can restore it.
+        sqlx::query(
+            r#"
+            UPDATE email_change_requests
+            SET confirmed_at = NOW(),
+                old_email = (SELECT email FROM users WHERE id = $2)
+            WHERE id = $1
+            "#,
+        )
+        .bind(request.id)
+        .bind(request.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let user = set_user_email(&mut tx, request.user_id, &request.new_email).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE email_change_requests
+            SET cancelled_at = NOW()
+            WHERE LOWER(new_email) = LOWER($1)
+              AND id <> $2
+              AND confirmed_at IS NULL
+              AND cancelled_at IS NULL
+            "#,
+        )
+        .bind(&request.new_email)
+        .bind(request.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(user))
+    }
+
+    #[instrument(skip_all)]
+    async fn cancel_email_change(&self, cancel_token_hash: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let cancelled = sqlx::query_as::<_, (Uuid, Option<String>)>(
+            r#"
+            UPDATE email_change_requests
+            SET cancelled_at = NOW()
+            WHERE cancel_token_hash = $1 AND cancelled_at IS NULL AND cancel_until > NOW()
+            RETURNING user_id, old_email
+            "#,
+        )
+        .bind(cancel_token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((user_id, old_email)) = cancelled else {
+            return Ok(false);
+        };
+
+        // Already confirmed: put the old address back and drop anything that could have been
+        // sent to the new one.
+        if let Some(old_email) = old_email {
+            set_user_email(&mut tx, user_id, &old_email).await?;
+            revoke_user_tokens(&mut tx, user_id, TokenPurpose::ResetPassword).await?;
+            revoke_user_tokens(&mut tx, user_id, TokenPurpose::MagicLink).await?;
+        }
+
+        revoke_user_tokens(&mut tx, user_id, TokenPurpose::ChangeEmail).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    #[instrument(skip_all)]
+    async fn email_reserved(&self, email: &str) -> Result<bool> {
+        let reserved = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM email_change_requests
+                WHERE LOWER(old_email) = LOWER($1)
+                  AND cancelled_at IS NULL
+                  AND cancel_until > NOW()
+            )
+            "#,
+        )
+        .bind(email)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(reserved)
+    }
+}
+
+async fn set_user_email(conn: &mut PgConnection, user_id: Uuid, email: &str) -> Result<User> {
+    sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET email = $1, updated_at = Now()
+        WHERE id = $2
+        RETURNING id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
+        "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            Error::BadRequest("Unavailable.".to_string())
+        }
+        err => Error::DatabaseError(err),
+    })
 }
@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    mail::sendmail::OutgoingEmail,
+    models::digest::{DigestPost, DigestRecipient, DigestRecipientKind, DigestVideo},
+    Result,
+};
+
+use super::{outbox_repo::enqueue_email, PostgresRepo};
+
+#[async_trait]
+pub trait DigestRepository: Send + Sync {
+    async fn due_digest_recipients(&self, limit: i64) -> Result<Vec<DigestRecipient>>;
+    async fn digest_posts(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<DigestPost>>;
+    async fn digest_videos(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<DigestVideo>>;
+    async fn record_digest(
+        &self,
+        recipient: &DigestRecipient,
+        period_end: DateTime<Utc>,
+        post_ids: &[Uuid],
+        video_ids: &[Uuid],
+        email: Option<&OutgoingEmail>,
+    ) -> Result<bool>;
+}
+
+#[async_trait]
+impl DigestRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn due_digest_recipients(&self, limit: i64) -> Result<Vec<DigestRecipient>> {
+        let recipients = sqlx::query_as::<_, DigestRecipient>(
+            r#"
+            SELECT kind, id, email, name, locale, frequency, since
+            FROM (
+                SELECT 'subscriber'::digest_recipient AS kind, s.id, s.email, u.name, s.locale,
+                       s.digest_frequency AS frequency,
+                       COALESCE(s.last_digest_at, s.confirmed_at, s.created_at) AS since
+                FROM subscribers s
+                LEFT JOIN users u ON u.id = s.user_id
+                WHERE s.status = 'confirmed' AND s.digest_frequency <> 'off'
+                UNION ALL
+                SELECT 'user'::digest_recipient, u.id, u.email, u.name, u.locale,
+                       u.digest_frequency,
+                       COALESCE(u.last_digest_at, u.created_at)
+                FROM users u
+                WHERE u.verified
+                  AND u.digest_frequency <> 'off'
+                  AND NOT EXISTS (
+                      SELECT 1 FROM subscribers s
+                      WHERE s.email = LOWER(u.email) AND s.status = 'confirmed'
+                  )
+            ) recipients
+            WHERE since <= NOW() - make_interval(days => CASE frequency WHEN 'daily' THEN 1 ELSE 7 END)
+            ORDER BY since
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(recipients)
+    }
+
+    #[instrument(skip_all)]
+    async fn digest_posts(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<DigestPost>> {
+        let posts = sqlx::query_as::<_, DigestPost>(
+            r#"
+            SELECT id, url, description, author_name, created_at
+            FROM news_posts
+            WHERE created_at > $1 AND created_at <= $2
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    #[instrument(skip_all)]
+    async fn digest_videos(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<DigestVideo>> {
+        let videos = sqlx::query_as::<_, DigestVideo>(
+            r#"
+            SELECT id, title, youtube_id, duration, created_at
+            FROM videos
+            WHERE created_at > $1 AND created_at <= $2
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(videos)
+    }
+
+    #[instrument(skip_all)]
+    async fn record_digest(
+        &self,
+        recipient: &DigestRecipient,
+        period_end: DateTime<Utc>,
+        post_ids: &[Uuid],
+        video_ids: &[Uuid],
+        email: Option<&OutgoingEmail>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(email) = email {
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO digest_deliveries (id, recipient_kind, recipient_id, email, period_start, period_end, post_ids, video_ids)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (recipient_kind, recipient_id, period_start) DO NOTHING
+                "#,
+            )
+            .bind(Uuid::now_v7())
+            .bind(recipient.kind)
+            .bind(recipient.id)
+            .bind(&recipient.email)
+            .bind(recipient.since)
+            .bind(period_end)
+            .bind(post_ids)
+            .bind(video_ids)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if inserted == 0 {
+                return Ok(false);
+            }
+
+            enqueue_email(&mut tx, email).await?;
+        }
+
+        let query = match recipient.kind {
+            DigestRecipientKind::User => "UPDATE users SET last_digest_at = $1 WHERE id = $2",
+            DigestRecipientKind::Subscriber => {
+                "UPDATE subscribers SET last_digest_at = $1, updated_at = NOW() WHERE id = $2"
+            }
+        };
+
+        sqlx::query(query)
+            .bind(period_end)
+            .bind(recipient.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(email.is_some())
+    }
+}
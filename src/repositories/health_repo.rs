@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::Result;
+
+use super::{PostgresRepo, MIGRATOR};
+
+#[async_trait]
+pub trait HealthRepository: Send + Sync {
+    async fn ping(&self) -> Result<()>;
+    async fn pending_migrations(&self) -> Result<usize>;
+}
+
+#[async_trait]
+impl HealthRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn pending_migrations(&self) -> Result<usize> {
+        let applied: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let pending = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .filter(|migration| !applied.contains(&migration.version))
+            .count();
+
+        Ok(pending)
+    }
+}
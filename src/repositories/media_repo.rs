@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{models::media::ImageMetadata, Result};
+
+use super::PostgresRepo;
+
+#[async_trait]
+pub trait MediaRepository: Send + Sync {
+    async fn get_image_metadata(&self, path: &str) -> Result<Option<ImageMetadata>>;
+    async fn get_images_metadata(&self, paths: &[String]) -> Result<Vec<ImageMetadata>>;
+    async fn upsert_image_metadata(&self, metadata: &ImageMetadata) -> Result<()>;
+}
+
+#[async_trait]
+impl MediaRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn get_image_metadata(&self, path: &str) -> Result<Option<ImageMetadata>> {
+        let metadata = sqlx::query_as::<_, ImageMetadata>(
+            r#"
+            SELECT path, width, height, dominant_color, blurhash, lqip, file_size, file_modified_at
+            FROM image_metadata
+            WHERE path = $1
+            "#,
+        )
+        .bind(path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(metadata)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_images_metadata(&self, paths: &[String]) -> Result<Vec<ImageMetadata>> {
+        let metadata = sqlx::query_as::<_, ImageMetadata>(
+            r#"
+            SELECT path, width, height, dominant_color, blurhash, lqip, file_size, file_modified_at
+            FROM image_metadata
+            WHERE path = ANY($1)
+            "#,
+        )
+        .bind(paths)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(metadata)
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_image_metadata(&self, metadata: &ImageMetadata) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO image_metadata (path, width, height, dominant_color, blurhash, lqip, file_size, file_modified_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (path) DO UPDATE
+            SET width = EXCLUDED.width,
+                height = EXCLUDED.height,
+                dominant_color = EXCLUDED.dominant_color,
+                blurhash = EXCLUDED.blurhash,
+                lqip = EXCLUDED.lqip,
+                file_size = EXCLUDED.file_size,
+                file_modified_at = EXCLUDED.file_modified_at,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(&metadata.path)
+        .bind(metadata.width)
+        .bind(metadata.height)
+        .bind(&metadata.dominant_color)
+        .bind(&metadata.blurhash)
+        .bind(&metadata.lqip)
+        .bind(metadata.file_size)
+        .bind(metadata.file_modified_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,10 +1,20 @@
-use sqlx::PgPool;
+use sqlx::{migrate::Migrator, PgPool};
 
+pub mod auth_attempt_repo;
 pub mod auth_repo;
+pub mod digest_repo;
+pub mod health_repo;
+pub mod media_repo;
 pub mod news_post_repo;
+pub mod newsletter_repo;
+pub mod oidc_repo;
+pub mod outbox_repo;
+pub mod token_repo;
 pub mod user_repo;
 pub mod videos_repo;
 
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
 #[derive(Clone)]
 pub struct PostgresRepo {
     pool: PgPool,
@@ -15,3 +25,78 @@ impl PostgresRepo {
         Self { pool }
     }
 }
+
+/// A freshly migrated database of its own on the server `TEST_DATABASE_URL` points at, dropped
+/// again with the guard. Tests that need one are `#[ignore]`d; run them with
+/// `cargo test -- --include-ignored`.
+#[cfg(test)]
+pub struct TestDatabase {
+    pub repo: PostgresRepo,
+    url: String,
+    name: String,
+}
+
+#[cfg(test)]
+impl TestDatabase {
+    pub async fn create() -> Self {
+        use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+        let url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must point at a Postgres server for database tests");
+        let name = format!("blog_test_{}", uuid::Uuid::now_v7().simple());
+
+        let admin = PgPool::connect(&url).await.unwrap();
+        sqlx::query(&format!("CREATE DATABASE {}", name))
+            .execute(&admin)
+            .await
+            .unwrap();
+        admin.close().await;
+
+        let options = url.parse::<PgConnectOptions>().unwrap().database(&name);
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        Self {
+            repo: PostgresRepo::new(pool),
+            url,
+            name,
+        }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.repo.pool
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let url = self.url.clone();
+        let name = self.name.clone();
+
+        // The test's runtime may be shutting down around us, so the drop gets one of its own.
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let admin = PgPool::connect(&url).await?;
+                    sqlx::query(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name))
+                        .execute(&admin)
+                        .await?;
+                    admin.close().await;
+                    Ok::<_, sqlx::Error>(())
+                })
+        })
+        .join();
+
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("Failed to drop test database {}: {:?}", self.name, dropped);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
     Error, Result,
 };
 use async_trait::async_trait;
+use tracing::instrument;
 use uuid::Uuid;
 
 #[async_trait]
@@ -15,12 +16,14 @@ pub trait NewsPostsRepository: Sync + Send {
         description: &str,
         author_id: &str,
         author_name: &str,
+        cover_image: Option<&str>,
     ) -> Result<NewsPost>;
     async fn update_news_post(
         &self,
         post_id: &str,
         url: Option<&str>,
         description: Option<&str>,
+        cover_image: Option<&str>,
     ) -> Result<NewsPost>;
     async fn delete_news_post(&self, post_id: &str) -> Result<()>;
     async fn create_comment(
@@ -38,10 +41,11 @@ pub trait NewsPostsRepository: Sync + Send {
 
 #[async_trait]
 impl NewsPostsRepository for PostgresRepo {
+    #[instrument(skip_all)]
     async fn get_news_posts(&self) -> Result<Vec<NewsPost>> {
         let posts = sqlx::query_as::<_, NewsPost>(
             r#"
-            SELECT id, author_id, author_name, url, description, created_at FROM news_posts
+            SELECT id, author_id, author_name, url, description, created_at, cover_image FROM news_posts
             "#,
         )
         .fetch_all(&self.pool)
@@ -49,21 +53,23 @@ impl NewsPostsRepository for PostgresRepo {
         Ok(posts)
     }
 
+    #[instrument(skip_all, fields(user_id = %author_id))]
     async fn create_news_post(
         &self,
         url: &str,
         description: &str,
         author_id: &str,
         author_name: &str,
+        cover_image: Option<&str>,
     ) -> Result<NewsPost> {
         let id = Uuid::now_v7();
         let author_id = Uuid::parse_str(author_id).unwrap();
 
         let post = sqlx::query_as::<_, NewsPost>(
             r#"
-            INSERT INTO news_posts (id, url, description, author_id, author_name, created_at)
-            VALUES ($1, $2, $3, $4, $5, NOW())
-            RETURNING id, url, description, author_id, author_name, created_at
+            INSERT INTO news_posts (id, url, description, author_id, author_name, cover_image, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            RETURNING id, url, description, author_id, author_name, created_at, cover_image
             "#,
         )
         .bind(id)
@@ -71,17 +77,20 @@ impl NewsPostsRepository for PostgresRepo {
         .bind(description)
         .bind(author_id)
         .bind(author_name)
+        .bind(cover_image)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(post)
     }
 
+    #[instrument(skip_all)]
     async fn update_news_post(
         &self,
         post_id: &str,
         url: Option<&str>,
         description: Option<&str>,
+        cover_image: Option<&str>,
     ) -> Result<NewsPost> {
         let post_id = Uuid::parse_str(post_id).unwrap();
 
@@ -89,20 +98,23 @@ impl NewsPostsRepository for PostgresRepo {
             r#"
             UPDATE news_posts
             SET url = COALESCE($2, url),
-                description = COALESCE($3, description)
+                description = COALESCE($3, description),
+                cover_image = COALESCE($4, cover_image)
             WHERE id = $1
-            RETURNING id, url, description, author_id, author_name, created_at
+            RETURNING id, url, description, author_id, author_name, created_at, cover_image
             "#,
         )
         .bind(post_id)
         .bind(url)
         .bind(description)
+        .bind(cover_image)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(post)
     }
 
+    #[instrument(skip_all, fields(user_id = %author_id))]
     async fn create_comment(
         &self,
         post_id: &str,
@@ -133,6 +145,7 @@ impl NewsPostsRepository for PostgresRepo {
         Ok(comment)
     }
 
+    #[instrument(skip_all)]
     async fn update_comment(&self, comment_id: &str, content: Option<&str>) -> Result<PostComment> {
         let comment_id = Uuid::parse_str(comment_id).unwrap();
 
@@ -152,6 +165,7 @@ impl NewsPostsRepository for PostgresRepo {
         Ok(comment)
     }
 
+    #[instrument(skip_all)]
     async fn delete_comment(&self, comment_id: &str) -> Result<()> {
         let comment_id = Uuid::parse_str(comment_id).unwrap();
 
@@ -167,6 +181,7 @@ impl NewsPostsRepository for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     async fn delete_news_post(&self, post_id: &str) -> Result<()> {
         let post_id = Uuid::parse_str(post_id).unwrap();
 
@@ -182,6 +197,7 @@ impl NewsPostsRepository for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     async fn get_posts_with_comments(&self, post_id: &str) -> Result<PostCommentWithComments> {
         let post_id = Uuid::parse_str(post_id).unwrap();
 
@@ -193,6 +209,7 @@ impl NewsPostsRepository for PostgresRepo {
             author_id: Uuid,
             author_name: String,
             created_at: chrono::DateTime<chrono::Utc>,
+            cover_image: Option<String>,
             comments: serde_json::Value,
         }
 
@@ -205,7 +222,8 @@ impl NewsPostsRepository for PostgresRepo {
                     np.description,
                     np.author_id,
                     u.name as author_name,
-                    np.created_at
+                    np.created_at,
+                    np.cover_image
                 FROM news_posts np
                 JOIN users u ON np.author_id = u.id
                 WHERE np.id = $1
@@ -217,6 +235,7 @@ impl NewsPostsRepository for PostgresRepo {
                 pd.author_id,
                 pd.author_name,
                 pd.created_at,
+                pd.cover_image,
                 COALESCE(
                     json_agg(
                         json_build_object(
@@ -232,7 +251,7 @@ impl NewsPostsRepository for PostgresRepo {
             FROM post_data pd
             LEFT JOIN post_comments pc ON pd.id = pc.news_post_id
             LEFT JOIN users u2 ON pc.author_id = u2.id
-            GROUP BY pd.id, pd.url, pd.description, pd.author_id, pd.author_name, pd.created_at
+            GROUP BY pd.id, pd.url, pd.description, pd.author_id, pd.author_name, pd.created_at, pd.cover_image
             "#,
         )
         .bind(post_id)
@@ -249,10 +268,13 @@ impl NewsPostsRepository for PostgresRepo {
             author_id: temp_post.author_id,
             author_name: temp_post.author_name,
             created_at: temp_post.created_at,
+            cover_image: temp_post.cover_image,
+            cover: None,
             comments,
         })
     }
 
+    #[instrument(skip_all)]
     async fn get_all_posts_with_comments(&self) -> Result<Vec<PostCommentWithComments>> {
         #[derive(sqlx::FromRow)]
         struct TempPost {
@@ -262,6 +284,7 @@ impl NewsPostsRepository for PostgresRepo {
             author_id: Uuid,
             author_name: String,
             created_at: chrono::DateTime<chrono::Utc>,
+            cover_image: Option<String>,
             comments: serde_json::Value,
         }
 
@@ -274,7 +297,8 @@ impl NewsPostsRepository for PostgresRepo {
                     np.description,
                     np.author_id,
                     u.name as author_name,
-                    np.created_at
+                    np.created_at,
+                    np.cover_image
                 FROM news_posts np
                 JOIN users u ON np.author_id = u.id
             )
@@ -285,6 +309,7 @@ impl NewsPostsRepository for PostgresRepo {
                 pd.author_id,
                 pd.author_name,
                 pd.created_at,
+                pd.cover_image,
                 COALESCE(
                     json_agg(
                         json_build_object(
@@ -300,7 +325,7 @@ impl NewsPostsRepository for PostgresRepo {
             FROM post_data pd
             LEFT JOIN post_comments pc ON pd.id = pc.news_post_id
             LEFT JOIN users u2 ON pc.author_id = u2.id
-            GROUP BY pd.id, pd.url, pd.description, pd.author_id, pd.author_name, pd.created_at
+            GROUP BY pd.id, pd.url, pd.description, pd.author_id, pd.author_name, pd.created_at, pd.cover_image
             "#,
         )
         .fetch_all(&self.pool)
@@ -319,6 +344,8 @@ impl NewsPostsRepository for PostgresRepo {
                     author_id: temp_post.author_id,
                     author_name: temp_post.author_name,
                     created_at: temp_post.created_at,
+                    cover_image: temp_post.cover_image,
+                    cover: None,
                     comments,
                 }
             })
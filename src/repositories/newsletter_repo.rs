@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    mail::sendmail::OutgoingEmail,
+    models::{
+        digest::DigestFrequency,
+        newsletter::{NewsletterIssue, Subscriber, SubscriberStatus},
+    },
+    Result,
+};
+
+use super::{
+    outbox_repo::{enqueue_email, enqueue_email_at},
+    PostgresRepo,
+};
+
+const SUBSCRIBER_COLUMNS: &str =
+    "id, email, user_id, locale, status, digest_frequency, confirmed_at, unsubscribed_at, created_at";
+
+#[async_trait]
+pub trait NewsletterRepository: Send + Sync {
+    async fn subscribe(
+        &self,
+        email: &str,
+        locale: &str,
+        confirmation_token_hash: &str,
+        expires_at: DateTime<Utc>,
+        resend_after: DateTime<Utc>,
+        confirmation_email: &OutgoingEmail,
+    ) -> Result<Option<Subscriber>>;
+    async fn confirm_subscriber(&self, confirmation_token_hash: &str)
+        -> Result<Option<Subscriber>>;
+    async fn unsubscribe(&self, subscriber_id: Uuid) -> Result<Option<Subscriber>>;
+    async fn update_subscriber_digest(
+        &self,
+        subscriber_id: Uuid,
+        frequency: DigestFrequency,
+    ) -> Result<Option<Subscriber>>;
+    async fn list_subscribers(&self, status: Option<SubscriberStatus>) -> Result<Vec<Subscriber>>;
+    async fn create_issue(
+        &self,
+        issue: &NewsletterIssue,
+        emails: &[(OutgoingEmail, DateTime<Utc>)],
+    ) -> Result<NewsletterIssue>;
+    async fn list_issues(&self) -> Result<Vec<NewsletterIssue>>;
+}
+
+#[async_trait]
+impl NewsletterRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn subscribe(
+        &self,
+        email: &str,
+        locale: &str,
+        confirmation_token_hash: &str,
+        expires_at: DateTime<Utc>,
+        resend_after: DateTime<Utc>,
+        confirmation_email: &OutgoingEmail,
+    ) -> Result<Option<Subscriber>> {
+        let mut tx = self.pool.begin().await?;
+
+        let subscriber = sqlx::query_as::<_, Subscriber>(&format!(
+            r#"
+            INSERT INTO subscribers (id, email, user_id, locale, confirmation_token_hash, confirmation_expires_at, confirmation_sent_at)
+            VALUES ($1, $2, (SELECT id FROM users WHERE LOWER(email) = $2), $3, $4, $5, NOW())
+            ON CONFLICT (email) DO UPDATE
+            SET status = 'pending',
+                locale = EXCLUDED.locale,
+                confirmation_token_hash = EXCLUDED.confirmation_token_hash,
+                confirmation_expires_at = EXCLUDED.confirmation_expires_at,
+                confirmation_sent_at = NOW(),
+                unsubscribed_at = NULL,
+                updated_at = NOW()
+            WHERE subscribers.status <> 'confirmed'
+              AND (subscribers.confirmation_sent_at IS NULL OR subscribers.confirmation_sent_at < $6)
+            RETURNING {SUBSCRIBER_COLUMNS}
+            "#
+        ))
+        .bind(Uuid::now_v7())
+        .bind(email)
+        .bind(locale)
+        .bind(confirmation_token_hash)
+        .bind(expires_at)
+        .bind(resend_after)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if subscriber.is_some() {
+            enqueue_email(&mut tx, confirmation_email).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(subscriber)
+    }
+
+    #[instrument(skip_all)]
+    async fn confirm_subscriber(
+        &self,
+        confirmation_token_hash: &str,
+    ) -> Result<Option<Subscriber>> {
+        let subscriber = sqlx::query_as::<_, Subscriber>(&format!(
+            r#"
+            UPDATE subscribers
+            SET status = 'confirmed',
+                confirmation_token_hash = NULL,
+                confirmation_expires_at = NULL,
+                confirmed_at = NOW(),
+                updated_at = NOW()
+            WHERE confirmation_token_hash = $1
+              AND status = 'pending'
+              AND confirmation_expires_at > NOW()
+            RETURNING {SUBSCRIBER_COLUMNS}
+            "#
+        ))
+        .bind(confirmation_token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(subscriber)
+    }
+
+    #[instrument(skip_all)]
+    async fn unsubscribe(&self, subscriber_id: Uuid) -> Result<Option<Subscriber>> {
+        let subscriber = sqlx::query_as::<_, Subscriber>(&format!(
+            r#"
+            UPDATE subscribers
+            SET status = 'unsubscribed',
+                confirmation_token_hash = NULL,
+                confirmation_expires_at = NULL,
+                unsubscribed_at = COALESCE(unsubscribed_at, NOW()),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING {SUBSCRIBER_COLUMNS}
+            "#
+        ))
+        .bind(subscriber_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(subscriber)
+    }
+
+    #[instrument(skip_all)]
+    async fn update_subscriber_digest(
+        &self,
+        subscriber_id: Uuid,
+        frequency: DigestFrequency,
+    ) -> Result<Option<Subscriber>> {
+        let subscriber = sqlx::query_as::<_, Subscriber>(&format!(
+            r#"
+            UPDATE subscribers
+            SET digest_frequency = $1, updated_at = NOW()
+            WHERE id = $2 AND status = 'confirmed'
+            RETURNING {SUBSCRIBER_COLUMNS}
+            "#
+        ))
+        .bind(frequency)
+        .bind(subscriber_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(subscriber)
+    }
+
+    #[instrument(skip_all)]
+    async fn list_subscribers(&self, status: Option<SubscriberStatus>) -> Result<Vec<Subscriber>> {
+        let subscribers = sqlx::query_as::<_, Subscriber>(&format!(
+            r#"
+            SELECT {SUBSCRIBER_COLUMNS}
+            FROM subscribers
+            WHERE $1::subscriber_status IS NULL OR status = $1
+            ORDER BY created_at
+            "#
+        ))
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(subscribers)
+    }
+
+    #[instrument(skip_all)]
+    async fn create_issue(
+        &self,
+        issue: &NewsletterIssue,
+        emails: &[(OutgoingEmail, DateTime<Utc>)],
+    ) -> Result<NewsletterIssue> {
+        let mut tx = self.pool.begin().await?;
+
+        let issue = sqlx::query_as::<_, NewsletterIssue>(
+            r#"
+            INSERT INTO newsletter_issues (id, subject, html_content, text_content, recipients, sent_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, subject, html_content, text_content, recipients, sent_by, created_at
+            "#,
+        )
+        .bind(issue.id)
+        .bind(&issue.subject)
+        .bind(&issue.html_content)
+        .bind(&issue.text_content)
+        .bind(issue.recipients)
+        .bind(issue.sent_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (email, send_at) in emails {
+            enqueue_email_at(&mut tx, email, *send_at).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(issue)
+    }
+
+    #[instrument(skip_all)]
+    async fn list_issues(&self) -> Result<Vec<NewsletterIssue>> {
+        let issues = sqlx::query_as::<_, NewsletterIssue>(
+            r#"
+            SELECT id, subject, html_content, text_content, recipients, sent_by, created_at
+            FROM newsletter_issues
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(issues)
+    }
+}
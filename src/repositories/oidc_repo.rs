@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    models::{
+        oidc::{OidcState, UserIdentity},
+        users::{TokenPurpose, User},
+    },
+    Error, Result,
+};
+
+use super::{token_repo::revoke_user_tokens, PostgresRepo};
+
+#[async_trait]
+pub trait OidcRepository: Send + Sync {
+    async fn create_oidc_state(&self, state: &OidcState) -> Result<()>;
+    async fn consume_oidc_state(
+        &self,
+        provider: &str,
+        state_hash: &str,
+    ) -> Result<Option<OidcState>>;
+    async fn find_identity_user(&self, provider: &str, subject: &str) -> Result<Option<User>>;
+    async fn link_identity(
+        &self,
+        identity: &UserIdentity,
+        replace_password: Option<&str>,
+    ) -> Result<User>;
+    async fn create_oidc_user(
+        &self,
+        name: &str,
+        password_hash: &str,
+        locale: &str,
+        identity: &UserIdentity,
+    ) -> Result<User>;
+}
+
+#[async_trait]
+impl OidcRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn create_oidc_state(&self, state: &OidcState) -> Result<()> {
+        sqlx::query("DELETE FROM oidc_states WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oidc_states (state_hash, provider, nonce, pkce_verifier, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&state.state_hash)
+        .bind(&state.provider)
+        .bind(&state.nonce)
+        .bind(&state.pkce_verifier)
+        .bind(state.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn consume_oidc_state(
+        &self,
+        provider: &str,
+        state_hash: &str,
+    ) -> Result<Option<OidcState>> {
+        let state = sqlx::query_as::<_, OidcState>(
+            r#"
+            DELETE FROM oidc_states
+            WHERE state_hash = $1 AND provider = $2 AND expires_at > NOW()
+            RETURNING state_hash, provider, nonce, pkce_verifier, expires_at
+            "#,
+        )
+        .bind(state_hash)
+        .bind(provider)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    #[instrument(skip_all)]
+    async fn find_identity_user(&self, provider: &str, subject: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            WITH identity AS (
+                UPDATE user_identities
+                SET last_login_at = NOW()
+                WHERE provider = $1 AND subject = $2
+                RETURNING user_id
+            )
+            SELECT u.id, u.name, u.email, u.password, u.verified, u.created_at, u.updated_at, u.role, u.locale, u.magic_link_enabled
+            FROM users u
+            JOIN identity i ON i.user_id = u.id
+            "#,
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    #[instrument(skip_all, fields(user_id = %identity.user_id))]
+    async fn link_identity(
+        &self,
+        identity: &UserIdentity,
+        replace_password: Option<&str>,
+    ) -> Result<User> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_identities (id, user_id, provider, subject, email)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(identity.id)
+        .bind(identity.user_id)
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(&identity.email)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => Error::BadRequest(
+                "Another account from this provider is already linked.".to_string(),
+            ),
+            err => Error::DatabaseError(err),
+        })?;
+
+        // An unverified account may have been registered by someone who doesn't own the
+        // mailbox, so the provider's verification also takes away that password.
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET verified = true,
+                password = COALESCE($1, password),
+                updated_at = Now()
+            WHERE id = $2
+            RETURNING id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
+            "#,
+        )
+        .bind(replace_password)
+        .bind(identity.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if replace_password.is_some() {
+            revoke_user_tokens(&mut tx, identity.user_id, TokenPurpose::VerifyEmail).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    #[instrument(skip_all, fields(user_id = %identity.user_id))]
+    async fn create_oidc_user(
+        &self,
+        name: &str,
+        password_hash: &str,
+        locale: &str,
+        identity: &UserIdentity,
+    ) -> Result<User> {
+        let mut tx = self.pool.begin().await?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (id, name, email, password, locale, verified)
+            VALUES ($1, $2, $3, $4, $5, true)
+            RETURNING id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
+            "#,
+        )
+        .bind(identity.user_id)
+        .bind(name)
+        .bind(&identity.email)
+        .bind(password_hash)
+        .bind(locale)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                Error::BadRequest("Unavailable.".to_string())
+            }
+            err => Error::DatabaseError(err),
+        })?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_identities (id, user_id, provider, subject, email)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(identity.id)
+        .bind(user.id)
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(&identity.email)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+}
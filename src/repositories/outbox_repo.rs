@@ -0,0 +1,282 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, PgConnection};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    mail::sendmail::OutgoingEmail,
+    models::outbox::{ClaimedEmail, EmailStatus, OutboxEmail},
+    Result,
+};
+
+use super::PostgresRepo;
+
+const OUTBOX_COLUMNS: &str =
+    "id, recipient, subject, status, attempts, last_error, next_attempt_at, sent_at, created_at";
+const CLAIMED_COLUMNS: &str = "id, recipient, subject, html_body, text_body, headers, attempts";
+
+pub async fn enqueue_email(conn: &mut PgConnection, email: &OutgoingEmail) -> Result<()> {
+    enqueue_email_at(conn, email, Utc::now()).await
+}
+
+pub async fn enqueue_email_at(
+    conn: &mut PgConnection,
+    email: &OutgoingEmail,
+    send_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO email_outbox (id, recipient, subject, html_body, text_body, headers, next_attempt_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(Uuid::now_v7())
+    .bind(&email.to)
+    .bind(&email.subject)
+    .bind(&email.html_body)
+    .bind(&email.text_body)
+    .bind(Json(&email.headers))
+    .bind(send_at)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    async fn queue_email(&self, email: &OutgoingEmail) -> Result<()>;
+    async fn claim_emails(&self, limit: i64, lease_seconds: i64) -> Result<Vec<ClaimedEmail>>;
+    async fn mark_email_sent(&self, email_id: Uuid) -> Result<()>;
+    async fn release_emails(&self, email_ids: &[Uuid]) -> Result<()>;
+    async fn mark_email_failed(
+        &self,
+        email_id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+        max_attempts: i32,
+    ) -> Result<()>;
+    async fn list_emails(
+        &self,
+        status: Option<EmailStatus>,
+        limit: i64,
+    ) -> Result<Vec<OutboxEmail>>;
+    async fn get_email(&self, email_id: Uuid) -> Result<Option<OutboxEmail>>;
+    async fn retry_email(&self, email_id: Uuid) -> Result<Option<OutboxEmail>>;
+    async fn count_unsent_emails(&self) -> Result<Vec<(EmailStatus, i64)>>;
+    async fn purge_emails(
+        &self,
+        dead_before: DateTime<Utc>,
+        sent_before: DateTime<Utc>,
+    ) -> Result<(u64, u64)>;
+}
+
+#[async_trait]
+impl OutboxRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn queue_email(&self, email: &OutgoingEmail) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        enqueue_email(&mut conn, email).await
+    }
+
+    #[instrument(skip_all)]
+    async fn claim_emails(&self, limit: i64, lease_seconds: i64) -> Result<Vec<ClaimedEmail>> {
+        let emails = sqlx::query_as::<_, ClaimedEmail>(&format!(
+            r#"
+            UPDATE email_outbox
+            SET status = 'sending',
+                locked_until = NOW() + make_interval(secs => $2),
+                updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM email_outbox
+                WHERE (status = 'pending' AND next_attempt_at <= NOW())
+                   OR (status = 'sending' AND locked_until < NOW())
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {CLAIMED_COLUMNS}
+            "#
+        ))
+        .bind(limit)
+        .bind(lease_seconds as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(emails)
+    }
+
+    #[instrument(skip_all)]
+    async fn mark_email_sent(&self, email_id: Uuid) -> Result<()> {
+        // The content carries single-use links and unsubscribe tokens; nothing needs it once
+        // the email is out.
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET status = 'sent',
+                html_body = '',
+                text_body = NULL,
+                headers = '{}',
+                attempts = attempts + 1,
+                last_error = NULL,
+                locked_until = NULL,
+                sent_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(email_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn release_emails(&self, email_ids: &[Uuid]) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET status = 'pending',
+                locked_until = NULL,
+                updated_at = NOW()
+            WHERE id = ANY($1) AND status = 'sending'
+            "#,
+        )
+        .bind(email_ids)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn mark_email_failed(
+        &self,
+        email_id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+        max_attempts: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET attempts = attempts + 1,
+                status = CASE WHEN attempts + 1 >= $4 THEN 'dead'::email_status ELSE 'pending'::email_status END,
+                last_error = $2,
+                next_attempt_at = $3,
+                locked_until = NULL,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(email_id)
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(max_attempts)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn list_emails(
+        &self,
+        status: Option<EmailStatus>,
+        limit: i64,
+    ) -> Result<Vec<OutboxEmail>> {
+        let emails = sqlx::query_as::<_, OutboxEmail>(&format!(
+            r#"
+            SELECT {OUTBOX_COLUMNS}
+            FROM email_outbox
+            WHERE $1::email_status IS NULL OR status = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(emails)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_email(&self, email_id: Uuid) -> Result<Option<OutboxEmail>> {
+        let email = sqlx::query_as::<_, OutboxEmail>(&format!(
+            "SELECT {OUTBOX_COLUMNS} FROM email_outbox WHERE id = $1"
+        ))
+        .bind(email_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(email)
+    }
+
+    #[instrument(skip_all)]
+    async fn retry_email(&self, email_id: Uuid) -> Result<Option<OutboxEmail>> {
+        let email = sqlx::query_as::<_, OutboxEmail>(&format!(
+            r#"
+            UPDATE email_outbox
+            SET status = 'pending',
+                attempts = 0,
+                next_attempt_at = NOW(),
+                locked_until = NULL,
+                updated_at = NOW()
+            WHERE id = $1 AND status IN ('dead', 'pending') AND html_body <> ''
+            RETURNING {OUTBOX_COLUMNS}
+            "#
+        ))
+        .bind(email_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(email)
+    }
+
+    #[instrument(skip_all)]
+    async fn count_unsent_emails(&self) -> Result<Vec<(EmailStatus, i64)>> {
+        let counts = sqlx::query_as::<_, (EmailStatus, i64)>(
+            "SELECT status, COUNT(*) FROM email_outbox WHERE status <> 'sent' GROUP BY status",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    #[instrument(skip_all)]
+    async fn purge_emails(
+        &self,
+        dead_before: DateTime<Utc>,
+        sent_before: DateTime<Utc>,
+    ) -> Result<(u64, u64)> {
+        let scrubbed = sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET html_body = '',
+                text_body = NULL,
+                headers = '{}',
+                updated_at = NOW()
+            WHERE status = 'dead' AND updated_at < $1 AND html_body <> ''
+            "#,
+        )
+        .bind(dead_before)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        let deleted =
+            sqlx::query("DELETE FROM email_outbox WHERE status = 'sent' AND sent_at < $1")
+                .bind(sent_before)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+        Ok((scrubbed, deleted))
+    }
+}
@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::users::{TokenPurpose, UserToken},
+    Result,
+};
+
+use super::PostgresRepo;
+
+const USER_TOKEN_COLUMNS: &str = "user_id, email, created_at";
+
+pub async fn insert_user_token(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    purpose: TokenPurpose,
+    token_hash: &str,
+    email: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    revoke_user_tokens(&mut *conn, user_id, purpose).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_tokens (id, user_id, purpose, token_hash, email, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(Uuid::now_v7())
+    .bind(user_id)
+    .bind(purpose)
+    .bind(token_hash)
+    .bind(email)
+    .bind(expires_at)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn consume_user_token(
+    conn: &mut PgConnection,
+    purpose: TokenPurpose,
+    token_hash: &str,
+) -> Result<Option<UserToken>> {
+    let token = sqlx::query_as::<_, UserToken>(&format!(
+        r#"
+        UPDATE user_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1
+          AND purpose = $2
+          AND used_at IS NULL
+          AND expires_at > NOW()
+        RETURNING {USER_TOKEN_COLUMNS}
+        "#
+    ))
+    .bind(token_hash)
+    .bind(purpose)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(token)
+}
+
+pub async fn revoke_user_tokens(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    purpose: TokenPurpose,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE user_tokens
+        SET used_at = NOW()
+        WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+pub trait UserTokenRepository: Send + Sync {
+    async fn find_user_token(
+        &self,
+        purpose: TokenPurpose,
+        token_hash: &str,
+    ) -> Result<Option<UserToken>>;
+    async fn recent_user_tokens(
+        &self,
+        user_id: Uuid,
+        purpose: TokenPurpose,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<UserToken>>;
+}
+
+#[async_trait]
+impl UserTokenRepository for PostgresRepo {
+    #[instrument(skip_all)]
+    async fn find_user_token(
+        &self,
+        purpose: TokenPurpose,
+        token_hash: &str,
+    ) -> Result<Option<UserToken>> {
+        let token = sqlx::query_as::<_, UserToken>(&format!(
+            r#"
+            SELECT {USER_TOKEN_COLUMNS}
+            FROM user_tokens
+            WHERE token_hash = $1
+              AND purpose = $2
+              AND used_at IS NULL
+              AND expires_at > NOW()
+            "#
+        ))
+        .bind(token_hash)
+        .bind(purpose)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    #[instrument(skip_all, fields(user_id = %user_id))]
+    async fn recent_user_tokens(
+        &self,
+        user_id: Uuid,
+        purpose: TokenPurpose,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<UserToken>> {
+        let tokens = sqlx::query_as::<_, UserToken>(&format!(
+            r#"
+            SELECT {USER_TOKEN_COLUMNS}
+            FROM user_tokens
+            WHERE user_id = $1 AND purpose = $2 AND created_at >= $3
+            ORDER BY created_at DESC
+            "#
+        ))
+        .bind(user_id)
+        .bind(purpose)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+}
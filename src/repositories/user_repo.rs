@@ -1,9 +1,16 @@
 use async_trait::async_trait;
+use tracing::instrument;
 use uuid::Uuid;
 
-use crate::{models::users::User, Result};
+use crate::{
+    models::{
+        digest::DigestFrequency,
+        users::{TokenPurpose, User},
+    },
+    Result,
+};
 
-use super::PostgresRepo;
+use super::{token_repo::revoke_user_tokens, PostgresRepo};
 
 #[async_trait]
 pub trait UserRepository: Send + Sync {
@@ -12,58 +19,55 @@ pub trait UserRepository: Send + Sync {
         user_id: Option<Uuid>,
         name: Option<&str>,
         email: Option<&str>,
-        token: Option<&str>,
     ) -> Result<Option<User>>;
 
     async fn update_password(&self, user_id: Uuid, new_password: &str) -> Result<()>;
     async fn update_username(&self, user_id: Uuid, new_username: &str) -> Result<User>;
+    async fn update_locale(&self, user_id: Uuid, locale: &str) -> Result<()>;
+    async fn update_digest_frequency(
+        &self,
+        user_id: Uuid,
+        frequency: DigestFrequency,
+    ) -> Result<()>;
+    async fn update_magic_link(&self, user_id: Uuid, enabled: bool) -> Result<()>;
     async fn delete_user(&self, user_id: Uuid) -> Result<()>;
 }
 
 #[async_trait]
 impl UserRepository for PostgresRepo {
+    #[instrument(skip_all, fields(user_id = ?user_id))]
     async fn get_user(
         &self,
         user_id: Option<Uuid>,
         name: Option<&str>,
         email: Option<&str>,
-        token: Option<&str>,
     ) -> Result<Option<User>> {
         let mut user: Option<User> = None;
 
         if let Some(user_id) = user_id {
             user = sqlx::query_as::<_, User>(
-                r#"SELECT id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role FROM users WHERE id = $1"#,
+                r#"SELECT id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled FROM users WHERE id = $1"#,
             )
             .bind(user_id)
             .fetch_optional(&self.pool).await?;
         } else if let Some(name) = name {
             user = sqlx::query_as::<_, User>(
-                r#"SELECT id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role FROM users WHERE name = $1"#,
+                r#"SELECT id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled FROM users WHERE name = $1"#,
             )
             .bind(name)
             .fetch_optional(&self.pool).await?;
         } else if let Some(email) = email {
             user = sqlx::query_as::<_, User>(
-                r#"SELECT id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role FROM users WHERE email = $1"#,
+                r#"SELECT id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled FROM users WHERE LOWER(email) = LOWER($1)"#,
             )
             .bind(email)
             .fetch_optional(&self.pool).await?;
-        } else if let Some(token) = token {
-            user = sqlx::query_as::<_, User>(
-                r#"
-                SELECT id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role
-                FROM users
-                WHERE verification_token = $1"#,
-            )
-            .bind(token)
-            .fetch_optional(&self.pool)
-            .await?;
         }
 
         Ok(user)
     }
 
+    #[instrument(skip_all, fields(user_id = %user_id))]
     async fn update_password(&self, user_id: Uuid, new_password: &str) -> Result<()> {
         sqlx::query(
             r#"
@@ -79,14 +83,15 @@ impl UserRepository for PostgresRepo {
 
         Ok(())
     }
+    #[instrument(skip_all, fields(user_id = %user_id))]
     async fn update_username(&self, user_id: Uuid, new_username: &str) -> Result<User> {
         let user = sqlx::query_as::<_, User>(
             r#"
             UPDATE users
             SET name = $1, updated_at = Now()
             WHERE id = $2
-            RETURNING id, name, email, password, verified, created_at, updated_at, verification_token, token_expires_at, role
-            "#
+            RETURNING id, name, email, password, verified, created_at, updated_at, role, locale, magic_link_enabled
+            "#,
         )
         .bind(new_username)
         .bind(user_id)
@@ -96,6 +101,72 @@ impl UserRepository for PostgresRepo {
         Ok(user)
     }
 
+    #[instrument(skip_all, fields(user_id = %user_id))]
+    async fn update_locale(&self, user_id: Uuid, locale: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET locale = $1, updated_at = Now()
+            WHERE id = $2
+            "#,
+        )
+        .bind(locale)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(user_id = %user_id))]
+    async fn update_digest_frequency(
+        &self,
+        user_id: Uuid,
+        frequency: DigestFrequency,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET digest_frequency = $1,
+                last_digest_at = COALESCE(last_digest_at, NOW()),
+                updated_at = Now()
+            WHERE id = $2
+            "#,
+        )
+        .bind(frequency)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(user_id = %user_id))]
+    async fn update_magic_link(&self, user_id: Uuid, enabled: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET magic_link_enabled = $1, updated_at = Now()
+            WHERE id = $2
+            "#,
+        )
+        .bind(enabled)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if !enabled {
+            revoke_user_tokens(&mut tx, user_id, TokenPurpose::MagicLink).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(user_id = %user_id))]
     async fn delete_user(&self, user_id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM users WHERE id = $1")
             .bind(user_id)
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
@@ -31,12 +32,13 @@ pub trait VideosRepository: Send + Sync {
     async fn delete_video(&self, video_id: Uuid) -> Result<()>;
     async fn remove_category_from_video(&self, video_id: Uuid, category_id: Uuid) -> Result<()>;
     async fn create_category(&self, category_id: Uuid, category: &str) -> Result<CreateCategory>;
-    async fn delete_category(&self, category_id: Uuid) -> Result<()>;
+    async fn delete_category(&self, category_id: Uuid) -> Result<bool>;
     async fn get_video_by_youtube_id(&self, youtube_id: &str) -> Result<ResponseVideo>;
 }
 
 #[async_trait]
 impl VideosRepository for PostgresRepo {
+    #[instrument(skip_all)]
     async fn videos(&self) -> Result<Vec<Video>> {
         #[derive(sqlx::FromRow)]
         struct TempVideo {
@@ -82,6 +84,7 @@ impl VideosRepository for PostgresRepo {
         Ok(videos)
     }
 
+    #[instrument(skip_all)]
     async fn create_video(
         &self,
         id: Uuid,
@@ -105,6 +108,7 @@ impl VideosRepository for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     async fn update_video(
         &self,
         video_id: Uuid,
@@ -134,6 +138,7 @@ impl VideosRepository for PostgresRepo {
 
         Ok(())
     }
+    #[instrument(skip_all)]
     async fn add_category_to_video(&self, video_id: Uuid, category_id: Uuid) -> Result<()> {
         sqlx::query(
             r#"
@@ -149,6 +154,7 @@ impl VideosRepository for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     async fn create_category(&self, category_id: Uuid, category: &str) -> Result<CreateCategory> {
         let category = sqlx::query_as::<_, CreateCategory>(
             r#"
@@ -165,8 +171,9 @@ impl VideosRepository for PostgresRepo {
         Ok(category)
     }
 
-    async fn delete_category(&self, category_id: Uuid) -> Result<()> {
-        sqlx::query(
+    #[instrument(skip_all)]
+    async fn delete_category(&self, category_id: Uuid) -> Result<bool> {
+        let deleted = sqlx::query(
             r#"
             DELETE FROM categories
             WHERE id = $1;
@@ -174,11 +181,13 @@ impl VideosRepository for PostgresRepo {
         )
         .bind(category_id)
         .execute(&self.pool)
-        .await?;
+        .await?
+        .rows_affected();
 
-        Ok(())
+        Ok(deleted > 0)
     }
 
+    #[instrument(skip_all)]
     async fn get_video_by_youtube_id(&self, youtube_id: &str) -> Result<ResponseVideo> {
         let video = sqlx::query_as::<_, ResponseVideo>(
             r#"
@@ -194,6 +203,7 @@ impl VideosRepository for PostgresRepo {
         Ok(video)
     }
 
+    #[instrument(skip_all)]
     async fn delete_video(&self, video_id: Uuid) -> Result<()> {
         sqlx::query(
             r#"
@@ -208,6 +218,7 @@ impl VideosRepository for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     async fn remove_category_from_video(&self, video_id: Uuid, category_id: Uuid) -> Result<()> {
         sqlx::query(
             r#"
@@ -1,113 +1,282 @@
-use std::{io::Cursor, path::PathBuf, sync::Arc};
-
-use axum::{
-    extract::Query,
-    http::{HeaderMap, StatusCode},
-    middleware,
-    response::IntoResponse,
-    routing::get,
-    Extension, Router,
+use std::sync::Arc;
+
+use axum::{middleware, routing::get, Extension, Router};
+use tower_http::services::ServeDir;
+use utoipa::{
+    openapi::{security::SecurityRequirement, OpenApi as OpenApiDocument, Response},
+    OpenApi,
 };
-use image::{ImageFormat, ImageReader};
-use serde::Deserialize;
-use tower_http::{services::ServeDir, trace::TraceLayer};
+use utoipa_axum::{router::OpenApiRouter, router::UtoipaMethodRouterExt, routes};
 
 use crate::{
     handlers::{
-        auth::auth_handler, news_post::news_posts_handler, user::users_handler,
+        admin::admin_handler,
+        auth::auth_handler,
+        docs::{docs_router, ApiDoc},
+        health, jwks,
+        media::{self, media_handler},
+        metrics::{metrics, METRICS_PATH},
+        news_post::news_posts_handler,
+        newsletter::newsletter_handler,
+        user::users_handler,
         videos::videos_handler,
     },
-    middleware::auth,
+    middleware::{auth, rate_limit::RateLimitLayer},
     AppState,
 };
 
-#[derive(Deserialize)]
-struct ImageParams {
-    url: String,
-    w: Option<u32>,
+fn routes_static(assets_dir: &str) -> Router {
+    Router::new().nest_service("/images", ServeDir::new(assets_dir))
 }
 
-async fn handle_image_optimization(
-    Query(params): Query<ImageParams>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let decoded_url = urlencoding::decode(&params.url)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid URL encoding".to_string()))?;
+pub fn create_routes(app_state: Arc<AppState>) -> Router {
+    let (mut router, openapi) = api_routes(&app_state.config.media.assets_dir).split_for_parts();
 
-    let file_path = PathBuf::from(
-        decoded_url
-            .strip_prefix("http://localhost:8080/api/images/")
-            .ok_or((StatusCode::BAD_REQUEST, "Invalid image URL".to_string()))?,
-    );
+    if app_state.config.docs.enabled {
+        router = router.merge(docs_router(openapi));
+    }
 
-    if file_path.components().count() != 2 || file_path.is_absolute() {
-        return Err((StatusCode::FORBIDDEN, "Invalid file path".to_string()));
+    if app_state.metrics.is_some() && app_state.config.metrics.admin_port == 0 {
+        router = router.route(METRICS_PATH, get(metrics));
     }
 
-    let image_bytes = tokio::fs::read(format!("src/assets/{}", file_path.display()))
-        .await
-        .map_err(|e| (StatusCode::NOT_FOUND, format!("File not found: {}", e)))?;
-
-    let mut img = ImageReader::new(Cursor::new(image_bytes))
-        .with_guessed_format()
-        .map_err(|e| {
-            (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                format!("Image decode error: {}", e),
-            )
-        })?
-        .decode()
-        .map_err(|e| {
-            (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                format!("Image processing error: {}", e),
-            )
-        })?;
-
-    if let Some(width) = params.w {
-        img = img.resize(
-            width,
-            (width as f32 * (img.height() as f32 / img.width() as f32)) as u32,
-            image::imageops::FilterType::Lanczos3,
-        );
+    router.layer(Extension(app_state))
+}
+
+/// Every documented route; the OpenAPI document comes from the same `#[utoipa::path]`
+/// attributes the router is built from.
+pub fn api_routes(assets_dir: &str) -> OpenApiRouter {
+    let api_route = OpenApiRouter::new()
+        .nest("/auth", auth_handler().layer(RateLimitLayer::new("auth")))
+        .nest("/users", authenticated(users_handler()))
+        .nest("/posts", authenticated(news_posts_handler()))
+        .nest("/videos", videos_handler())
+        .nest("/media", media_handler())
+        .nest(
+            "/newsletter",
+            newsletter_handler().layer(RateLimitLayer::new("newsletter")),
+        )
+        .nest(
+            "/admin",
+            authenticated(admin_handler()).layer(RateLimitLayer::new("admin")),
+        )
+        .fallback_service(routes_static(assets_dir))
+        .layer(RateLimitLayer::new("default"));
+
+    let mut router = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(health::healthz))
+        .routes(routes!(health::readyz).layer(RateLimitLayer::new("health")))
+        .routes(routes!(jwks::jwks).layer(RateLimitLayer::new("default")))
+        .routes(routes!(media::handle_image_optimization).layer(RateLimitLayer::new("images")))
+        .nest("/api", api_route);
+
+    name_catch_all_params(router.get_openapi_mut());
+
+    router
+}
+
+/// Puts `router` behind the session middleware and documents that on each of its operations.
+fn authenticated(mut router: OpenApiRouter) -> OpenApiRouter {
+    let requirement = SecurityRequirement::new("api_key", Vec::<String>::new())
+        .add("session", Vec::<String>::new());
+
+    for item in router.get_openapi_mut().paths.paths.values_mut() {
+        for operation in [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.patch,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            operation.security = Some(vec![requirement.clone()]);
+            operation.responses.responses.insert(
+                "401".to_string(),
+                Response::new("Missing or invalid session").into(),
+            );
+        }
     }
 
-    let mut output_buf = Cursor::new(Vec::new());
-    img.write_to(&mut output_buf, ImageFormat::WebP)
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Image conversion error: {}", e),
-            )
-        })?;
-
-    let mut headers = HeaderMap::new();
-    headers.insert("content-type", "image/webp".parse().unwrap());
-    headers.insert(
-        "cache-control",
-        "public, max-age=31536000, immutable".parse().unwrap(),
-    );
-
-    Ok((StatusCode::OK, headers, output_buf.into_inner()))
+    router.layer(middleware::from_fn(auth))
 }
 
-fn routes_static() -> Router {
-    Router::new().nest_service("/images", ServeDir::new("src/assets"))
+/// axum's `{*path}` catch-all isn't valid in an OpenAPI path template.
+fn name_catch_all_params(openapi: &mut OpenApiDocument) {
+    let paths = std::mem::take(&mut openapi.paths.paths);
+    openapi.paths.paths = paths
+        .into_iter()
+        .map(|(path, item)| (path.replace("{*", "{"), item))
+        .collect();
 }
 
-pub fn create_routes(app_state: Arc<AppState>) -> Router {
-    let api_route = Router::new()
-        .nest("/auth", auth_handler())
-        .nest("/users", users_handler().layer(middleware::from_fn(auth)))
-        .nest(
-            "/posts",
-            news_posts_handler().layer(middleware::from_fn(auth)),
-        )
-        .nest("/videos", videos_handler())
-        .fallback_service(routes_static())
-        .layer(TraceLayer::new_for_http())
-        .layer(Extension(app_state));
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
 
-    Router::new()
-        .route("/_next/image", get(handle_image_optimization))
-        .nest("/api", api_route)
+    const ASSETS_DIR: &str = "src/assets";
+    const SPEC_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/openapi.json");
+    /// Every public route, method and path as clients call them; a change here is a
+    /// breaking API change.
+    const ROUTES: [(&str, &str); 60] = [
+        ("GET", "/.well-known/jwks.json"),
+        ("GET", "/_next/image"),
+        ("GET", "/api/admin/email-templates"),
+        ("GET", "/api/admin/email-templates/{name}/preview"),
+        ("POST", "/api/admin/email-templates/{name}/test"),
+        ("GET", "/api/admin/newsletter/issues"),
+        ("POST", "/api/admin/newsletter/issues"),
+        ("GET", "/api/admin/newsletter/subscribers"),
+        ("GET", "/api/admin/outbox"),
+        ("GET", "/api/admin/outbox/{id}"),
+        ("POST", "/api/admin/outbox/{id}/retry"),
+        ("GET", "/api/auth/cancel-email-change"),
+        ("GET", "/api/auth/confirm-email-change"),
+        ("POST", "/api/auth/forgot-password"),
+        ("POST", "/api/auth/login"),
+        ("POST", "/api/auth/magic-link"),
+        ("POST", "/api/auth/magic-link/consume"),
+        ("GET", "/api/auth/oidc/providers"),
+        ("GET", "/api/auth/oidc/{provider}/authorize"),
+        ("POST", "/api/auth/oidc/{provider}/callback"),
+        ("POST", "/api/auth/register"),
+        ("POST", "/api/auth/resend-verification"),
+        ("POST", "/api/auth/reset-password"),
+        ("GET", "/api/auth/verify-email"),
+        ("GET", "/api/media/articles/{slug}"),
+        ("GET", "/api/media/images/{path}"),
+        ("GET", "/api/newsletter/confirm"),
+        ("PUT", "/api/newsletter/preferences"),
+        ("POST", "/api/newsletter/subscribe"),
+        ("POST", "/api/newsletter/unsubscribe"),
+        ("POST", "/api/posts/create-comment/{id}"),
+        ("POST", "/api/posts/create-post/{id}"),
+        ("DELETE", "/api/posts/delete-comment/{id}"),
+        ("DELETE", "/api/posts/delete-post/{id}"),
+        ("GET", "/api/posts/get-all-posts-with-comments"),
+        ("GET", "/api/posts/get-posts"),
+        ("GET", "/api/posts/get-posts-with-comments/{id}"),
+        ("PUT", "/api/posts/update-comment/{id}"),
+        ("PUT", "/api/posts/update-post/{id}"),
+        ("DELETE", "/api/users/delete/{id}"),
+        ("PUT", "/api/users/digest"),
+        ("PUT", "/api/users/email"),
+        ("PUT", "/api/users/locale"),
+        ("PUT", "/api/users/magic-link"),
+        ("GET", "/api/users/me"),
+        ("PUT", "/api/users/role"),
+        ("PUT", "/api/users/update-password"),
+        ("PUT", "/api/users/update-username"),
+        ("GET", "/api/users/users"),
+        ("POST", "/api/videos/add-category-video/{id}"),
+        ("POST", "/api/videos/create-category"),
+        ("POST", "/api/videos/create-video"),
+        ("DELETE", "/api/videos/delete-category/{id}"),
+        ("DELETE", "/api/videos/delete-video/{id}"),
+        ("GET", "/api/videos/get-video/{id}"),
+        ("DELETE", "/api/videos/remove-category-video/{id}"),
+        ("PUT", "/api/videos/update-video/{id}"),
+        ("GET", "/api/videos/videos"),
+        ("GET", "/healthz"),
+        ("GET", "/readyz"),
+    ];
+
+    /// After changing a route or DTO, rerun with `UPDATE_OPENAPI=1` and commit the result.
+    #[test]
+    fn checked_in_spec_matches_routes() {
+        let generated = api_routes(ASSETS_DIR)
+            .into_openapi()
+            .to_pretty_json()
+            .unwrap()
+            + "\n";
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SPEC_FILE, &generated).unwrap();
+            return;
+        }
+
+        let checked_in = std::fs::read_to_string(SPEC_FILE).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "docs/openapi.json is out of date; run `UPDATE_OPENAPI=1 cargo test` and commit it"
+        );
+    }
+
+    #[tokio::test]
+    async fn documents_and_routes_exactly_the_known_operations() {
+        let (router, openapi) = api_routes(ASSETS_DIR).split_for_parts();
+
+        let documented: BTreeSet<(String, String)> = openapi
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                [
+                    ("GET", &item.get),
+                    ("PUT", &item.put),
+                    ("POST", &item.post),
+                    ("DELETE", &item.delete),
+                    ("PATCH", &item.patch),
+                ]
+                .into_iter()
+                .filter(|(_, operation)| operation.is_some())
+                .map(move |(method, _)| (method.to_string(), path.clone()))
+            })
+            .collect();
+        let expected: BTreeSet<(String, String)> = ROUTES
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()))
+            .collect();
+        assert_eq!(documented, expected);
+
+        for (path, item) in &openapi.paths.paths {
+            let uri = path
+                .split('/')
+                .map(|segment| {
+                    if segment.starts_with('{') {
+                        "x"
+                    } else {
+                        segment
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            for (method, operation) in [
+                (Method::GET, &item.get),
+                (Method::PUT, &item.put),
+                (Method::POST, &item.post),
+                (Method::DELETE, &item.delete),
+                (Method::PATCH, &item.patch),
+            ] {
+                if operation.is_none() {
+                    continue;
+                }
+
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let status = router.clone().oneshot(request).await.unwrap().status();
+
+                assert!(
+                    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                    "{} {} is documented but not routed ({})",
+                    method,
+                    path,
+                    status
+                );
+            }
+        }
+    }
 }
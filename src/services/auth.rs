@@ -1,79 +1,158 @@
-use std::env;
+use std::{net::IpAddr, sync::Arc};
 
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
-};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use serde::{Deserialize, Serialize};
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    mail::mails::{send_forgot_password_email, send_welcome_email},
-    models::users::User,
-    repositories::{auth_repo::AuthRepository, user_repo::UserRepository, PostgresRepo},
+    config::EmailChangeConfig,
+    jwt::token::{SessionToken, SessionTokens},
+    mail::{
+        mails::{
+            account_locked_email, email_change_confirmation_email, email_change_notice_email,
+            forgot_password_email, magic_link_email, verification_email, welcome_email,
+        },
+        templates::{normalize_locale, TemplateEngine},
+    },
+    models::users::{AuthAction, EmailChangeRequest, TokenPurpose, User},
+    password::{hasher::PasswordHasher, policy::PasswordPolicy},
+    repositories::{
+        auth_repo::AuthRepository, outbox_repo::OutboxRepository, token_repo::UserTokenRepository,
+        user_repo::UserRepository, PostgresRepo,
+    },
     Error, Result,
 };
 
+use super::{
+    auth_limiter::AuthLimiter,
+    user_tokens::{generate_token, hash_token},
+};
+
+const VERIFY_EMAIL_TTL_HOURS: i64 = 24;
+const RESET_PASSWORD_TTL_MINUTES: i64 = 30;
+const RESEND_VERIFICATION_COOLDOWN_SECS: i64 = 60;
+const RESEND_VERIFICATION_MAX_PER_HOUR: usize = 3;
+const MAGIC_LINK_TTL_MINUTES: i64 = 15;
+
 #[derive(Clone)]
 pub struct AuthService {
     repo: PostgresRepo,
-    jwt_secret: String,
-    jwt_expiration: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    iat: usize,
-    exp: usize,
+    tokens: SessionTokens,
+    templates: Arc<TemplateEngine>,
+    limiter: AuthLimiter,
+    password_policy: Arc<PasswordPolicy>,
+    password_hasher: Arc<PasswordHasher>,
+    email_change: EmailChangeConfig,
+    api_url: String,
 }
 
 impl AuthService {
-    pub fn new(repo: PostgresRepo, jwt_secret: String, jwt_expiration: i64) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: PostgresRepo,
+        tokens: SessionTokens,
+        templates: Arc<TemplateEngine>,
+        limiter: AuthLimiter,
+        password_policy: Arc<PasswordPolicy>,
+        password_hasher: Arc<PasswordHasher>,
+        email_change: EmailChangeConfig,
+        api_url: String,
+    ) -> Self {
         Self {
             repo,
-            jwt_secret,
-            jwt_expiration,
+            tokens,
+            templates,
+            limiter,
+            password_policy,
+            password_hasher,
+            email_change,
+            api_url,
         }
     }
 
-    pub async fn register(&self, name: String, email: String, password: String) -> Result<User> {
-        if self
-            .repo
-            .get_user(None, None, Some(&email), None)
-            .await?
-            .is_some()
-        {
+    #[instrument(skip_all)]
+    pub async fn register(
+        &self,
+        name: String,
+        email: String,
+        password: String,
+        locale: Option<String>,
+    ) -> Result<User> {
+        if self.email_taken(&email).await? {
             return Err(Error::BadRequest("Unavailable.".to_string()));
         }
 
-        let verification_token = Uuid::now_v7();
-        let expires_at = Utc::now() + Duration::hours(24);
+        self.password_policy
+            .validate("password", &password, &[&name, &email])
+            .await?;
+
+        let verification_token = generate_token();
+        let expires_at = Utc::now() + Duration::hours(VERIFY_EMAIL_TTL_HOURS);
 
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|_| Error::Unauthorized)?
-            .to_string();
+        let password_hash = self.password_hasher.hash(&password)?;
+
+        let locale = normalize_locale(locale.as_deref().unwrap_or_default());
+        let email_message = verification_email(
+            &self.templates,
+            &email,
+            &name,
+            &verification_token.token,
+            locale,
+        )?;
 
         self.repo
             .create_user(
                 name,
                 email,
                 password_hash,
-                verification_token.to_string(),
-                Some(expires_at),
+                locale,
+                &verification_token.hash,
+                expires_at,
+                &email_message,
             )
             .await
     }
 
-    pub async fn login(&self, email: &str, password: &str) -> Result<String> {
+    #[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+    pub async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<SessionToken> {
+        self.limiter
+            .check(AuthAction::Login, ip, Some(email))
+            .await?;
+
+        match self.verify_credentials(email, password).await {
+            Ok(user) => {
+                tracing::Span::current().record("user_id", tracing::field::display(user.id));
+                record_login("password", true);
+                self.limiter
+                    .record_success(AuthAction::Login, ip, Some(email))
+                    .await?;
+                self.session_token(&user)
+            }
+            Err(err @ Error::BadRequest(_)) => {
+                record_login("password", false);
+                if let Some(locked_minutes) = self
+                    .limiter
+                    .record_failure(AuthAction::Login, ip, Some(email))
+                    .await?
+                {
+                    self.notify_lockout(email, locked_minutes).await?;
+                }
+                Err(err)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn verify_credentials(&self, email: &str, password: &str) -> Result<User> {
         let user = self
             .repo
-            .get_user(None, None, Some(email), None)
+            .get_user(None, None, Some(email))
             .await?
             .ok_or(Error::BadRequest("Unavailable.".to_string()))?;
 
@@ -81,105 +160,674 @@ impl AuthService {
             return Err(Error::BadRequest("Unavailable.".to_string()));
         }
 
-        let argon2 = Argon2::default();
-        let parsed_hash =
-            PasswordHash::new(&user.password).map_err(|_| Error::InternalServerError)?;
-        argon2
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .map_err(|_| Error::BadRequest("Invalid password!".to_string()))?;
-        self.generate_token(user.id, 60 * 60)
+        self.password_hasher.verify(password, &user.password)?;
+
+        if self.password_hasher.needs_rehash(&user.password) {
+            self.upgrade_password_hash(&user, password).await;
+        }
+
+        Ok(user)
     }
 
-    fn generate_token(&self, user_id: Uuid, expires_in_seconds: i64) -> Result<String> {
-        let now = Utc::now();
-        let exp = (now + Duration::minutes(expires_in_seconds)).timestamp() as usize;
-        let iat = now.timestamp() as usize;
-        let claims = Claims {
-            sub: user_id.to_string(),
-            iat,
-            exp,
+    #[instrument(skip_all, fields(user_id = %user.id))]
+    async fn upgrade_password_hash(&self, user: &User, password: &str) {
+        let result = match self.password_hasher.hash(password) {
+            Ok(password_hash) => self.repo.update_password(user.id, &password_hash).await,
+            Err(err) => Err(err),
         };
 
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )
-        .map_err(|_| Error::InternalServerError)
+        match result {
+            Ok(()) => tracing::info!(user_id = %user.id, "Upgraded password hash"),
+            Err(err) => tracing::error!(error = ?err, "Failed to upgrade password hash"),
+        }
     }
 
-    pub async fn verify_email(&self, token: String) -> Result<String> {
-        let user = self.repo.get_user(None, None, None, Some(&token)).await?;
+    #[instrument(skip_all)]
+    async fn notify_lockout(&self, email: &str, locked_minutes: i64) -> Result<()> {
+        tracing::warn!(email, "Locked account after repeated failed sign-ins");
+
+        let Some(user) = self.repo.get_user(None, None, Some(email)).await? else {
+            return Ok(());
+        };
 
-        let user = user.ok_or(Error::BadRequest("Invalid data".to_string()))?;
+        let notice = account_locked_email(
+            &self.templates,
+            &user.email,
+            &user.name,
+            locked_minutes,
+            &user.locale,
+        )?;
 
-        if let Some(expires_at) = user.token_expires_at {
-            if expires_at < Utc::now() {
-                return Err(Error::BadRequest("Token expired".to_string()));
-            }
+        self.repo.queue_email(&notice).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn request_magic_link(&self, email: &str, ip: Option<IpAddr>) -> Result<()> {
+        self.limiter
+            .check(AuthAction::MagicLink, ip, Some(email))
+            .await?;
+        self.limiter
+            .record_success(AuthAction::MagicLink, ip, Some(email))
+            .await?;
+
+        let Some(user) = self.repo.get_user(None, None, Some(email)).await? else {
+            return Ok(());
+        };
+
+        if !user.verified || !user.magic_link_enabled {
+            return Ok(());
         }
 
-        self.repo.verifed_token(&token).await?;
-        send_welcome_email(&user.email, &user.name).await?;
+        let now = Utc::now();
+        let magic_token = generate_token();
+        let email_message = magic_link_email(
+            &self.templates,
+            &user.email,
+            &user.name,
+            &magic_token.token,
+            MAGIC_LINK_TTL_MINUTES,
+            &user.locale,
+        )?;
 
-        self.generate_token(user.id, self.jwt_expiration)
+        self.repo
+            .issue_user_token(
+                user.id,
+                TokenPurpose::MagicLink,
+                &magic_token.hash,
+                now + Duration::minutes(MAGIC_LINK_TTL_MINUTES),
+                &email_message,
+            )
+            .await
     }
 
-    pub async fn forgot_password(&self, email: String) -> Result<()> {
-        let user = self.repo.get_user(None, None, Some(&email), None).await?;
+    #[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+    pub async fn consume_magic_link(
+        &self,
+        token: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<SessionToken> {
+        self.limiter.check(AuthAction::MagicLink, ip, None).await?;
+
+        // A locked account keeps its link, so it still works once the lock expires.
+        let token_hash = hash_token(token);
+        if let Some(email) = self
+            .repo
+            .find_user_token(TokenPurpose::MagicLink, &token_hash)
+            .await?
+            .and_then(|pending| pending.email)
+        {
+            self.limiter.ensure_unlocked(&email).await?;
+        }
 
-        let user = user.ok_or(Error::BadRequest("Unavailable.".to_string()))?;
+        let Some(user) = self.repo.consume_magic_link(&token_hash).await? else {
+            record_login("magic_link", false);
+            self.limiter
+                .record_failure(AuthAction::MagicLink, ip, None)
+                .await?;
+            return Err(Error::BadRequest("Invalid or expired token".to_string()));
+        };
 
-        let verification_token = Uuid::now_v7().to_string();
-        let expires_at = Utc::now() + Duration::minutes(30);
+        tracing::Span::current().record("user_id", tracing::field::display(user.id));
+        record_login("magic_link", true);
 
-        let user_id =
-            Uuid::parse_str(&user.id.to_string()).map_err(|_| Error::InternalServerError)?;
+        self.session_token(&user)
+    }
+
+    pub fn session_token(&self, user: &User) -> Result<SessionToken> {
+        self.tokens.issue(user)
+    }
+
+    #[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+    pub async fn verify_email(&self, token: String) -> Result<SessionToken> {
+        let token_hash = hash_token(&token);
+        let user = self
+            .token_user(TokenPurpose::VerifyEmail, &token_hash)
+            .await?;
+        tracing::Span::current().record("user_id", tracing::field::display(user.id));
+
+        let welcome = welcome_email(&self.templates, &user.email, &user.name, &user.locale)?;
+        if !self.repo.verify_user_email(&token_hash, &welcome).await? {
+            return Err(Error::BadRequest("Invalid or expired token".to_string()));
+        }
+
+        self.session_token(&user)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn resend_verification(&self, email: &str) -> Result<()> {
+        let Some(user) = self.repo.get_user(None, None, Some(email)).await? else {
+            return Ok(());
+        };
+
+        if user.verified {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let recent = self
+            .repo
+            .recent_user_tokens(user.id, TokenPurpose::VerifyEmail, now - Duration::hours(1))
+            .await?;
+
+        let too_soon = recent.first().is_some_and(|token| {
+            token.created_at > now - Duration::seconds(RESEND_VERIFICATION_COOLDOWN_SECS)
+        });
+        // Throttled requests look like any other, so the response doesn't reveal that an
+        // unverified account exists.
+        if too_soon || recent.len() >= RESEND_VERIFICATION_MAX_PER_HOUR {
+            tracing::debug!("Skipping verification email; too many were sent recently");
+            return Ok(());
+        }
+
+        let verification_token = generate_token();
+        let email_message = verification_email(
+            &self.templates,
+            &user.email,
+            &user.name,
+            &verification_token.token,
+            &user.locale,
+        )?;
 
         self.repo
-            .add_verifed_token(user_id, expires_at, &verification_token)
+            .issue_user_token(
+                user.id,
+                TokenPurpose::VerifyEmail,
+                &verification_token.hash,
+                now + Duration::hours(VERIFY_EMAIL_TTL_HOURS),
+                &email_message,
+            )
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn forgot_password(&self, email: String, ip: Option<IpAddr>) -> Result<()> {
+        self.limiter
+            .check(AuthAction::ForgotPassword, ip, Some(&email))
             .await?;
+        self.limiter
+            .record_success(AuthAction::ForgotPassword, ip, Some(&email))
+            .await?;
+
+        let user = self.repo.get_user(None, None, Some(&email)).await?;
+
+        let user = user.ok_or(Error::BadRequest("Unavailable.".to_string()))?;
+
+        let reset_token = generate_token();
+        let expires_at = Utc::now() + Duration::minutes(RESET_PASSWORD_TTL_MINUTES);
 
         let reset_link = format!(
             "{}/confirm-auth/reset-password?token={}",
-            env::var("API_URL").expect("API_URL must be set"),
-            &verification_token
+            self.api_url, &reset_token.token
         );
+        let reset_email = forgot_password_email(
+            &self.templates,
+            &user.email,
+            &reset_link,
+            &user.name,
+            &user.locale,
+        )?;
+
+        self.repo
+            .issue_user_token(
+                user.id,
+                TokenPurpose::ResetPassword,
+                &reset_token.hash,
+                expires_at,
+                &reset_email,
+            )
+            .await
+    }
+
+    #[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+    pub async fn reset_password(
+        &self,
+        token: String,
+        new_password: String,
+        ip: Option<IpAddr>,
+    ) -> Result<()> {
+        self.limiter
+            .check(AuthAction::ResetPassword, ip, None)
+            .await?;
+
+        let token_hash = hash_token(&token);
+        let user = match self
+            .token_user(TokenPurpose::ResetPassword, &token_hash)
+            .await
+        {
+            Ok(user) => user,
+            Err(err) => {
+                self.limiter
+                    .record_failure(AuthAction::ResetPassword, ip, None)
+                    .await?;
+                return Err(err);
+            }
+        };
+        tracing::Span::current().record("user_id", tracing::field::display(user.id));
+
+        self.password_policy
+            .validate("new_password", &new_password, &[&user.name, &user.email])
+            .await?;
+
+        let password_hash = self.password_hasher.hash(&new_password)?;
+
+        if !self
+            .repo
+            .reset_user_password(&token_hash, &password_hash)
+            .await?
+        {
+            self.limiter
+                .record_failure(AuthAction::ResetPassword, ip, None)
+                .await?;
+            return Err(Error::BadRequest("Invalid or expired token".to_string()));
+        }
+
+        self.limiter
+            .record_success(AuthAction::ResetPassword, ip, None)
+            .await
+    }
+
+    #[instrument(skip_all)]
+    async fn token_user(&self, purpose: TokenPurpose, token_hash: &str) -> Result<User> {
+        let token = self
+            .repo
+            .find_user_token(purpose, token_hash)
+            .await?
+            .ok_or(Error::BadRequest("Invalid or expired token".to_string()))?;
+
+        self.repo
+            .get_user(Some(token.user_id), None, None)
+            .await?
+            .ok_or(Error::BadRequest("Invalid or expired token".to_string()))
+    }
+
+    #[instrument(skip_all, fields(user_id = %user.id))]
+    pub async fn request_email_change(
+        &self,
+        user: &User,
+        password: &str,
+        new_email: &str,
+    ) -> Result<()> {
+        self.password_hasher.verify(password, &user.password)?;
+
+        let new_email = new_email.trim().to_lowercase();
+        if new_email.eq_ignore_ascii_case(&user.email) {
+            return Err(Error::BadRequest(
+                "New email must be different from the current one".to_string(),
+            ));
+        }
+
+        if self.email_taken(&new_email).await? {
+            return Err(Error::BadRequest("Unavailable.".to_string()));
+        }
 
-        let email_sent = send_forgot_password_email(&user.email, &reset_link, &user.name).await;
+        let confirm_token = generate_token();
+        let cancel_token = generate_token();
+        let now = Utc::now();
+        let request = EmailChangeRequest {
+            id: Uuid::now_v7(),
+            user_id: user.id,
+            new_email,
+            cancel_token_hash: cancel_token.hash,
+            expires_at: now + Duration::hours(self.email_change.confirmation_ttl_hours),
+            cancel_until: now + Duration::hours(self.email_change.cancel_window_hours),
+        };
+
+        let confirmation_email = email_change_confirmation_email(
+            &self.templates,
+            &user.name,
+            &request.new_email,
+            &confirm_token.token,
+            self.email_change.confirmation_ttl_hours,
+            &user.locale,
+        )?;
+        let notice_email = email_change_notice_email(
+            &self.templates,
+            &user.email,
+            &user.name,
+            &request.new_email,
+            &cancel_token.token,
+            self.email_change.cancel_window_hours,
+            &user.locale,
+        )?;
+
+        self.repo
+            .create_email_change(
+                &request,
+                &confirm_token.hash,
+                &confirmation_email,
+                &notice_email,
+            )
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn confirm_email_change(&self, token: &str) -> Result<User> {
+        self.repo
+            .confirm_email_change(&hash_token(token))
+            .await?
+            .ok_or(Error::BadRequest("Invalid or expired token".to_string()))
+    }
+
+    /// An address is also taken while an account that changed away from it can still cancel.
+    async fn email_taken(&self, email: &str) -> Result<bool> {
+        Ok(self.repo.get_user(None, None, Some(email)).await?.is_some()
+            || self.repo.email_reserved(email).await?)
+    }
 
-        if email_sent.is_err() {
-            return Err(Error::InternalServerError);
+    #[instrument(skip_all)]
+    pub async fn cancel_email_change(&self, token: &str) -> Result<()> {
+        if !self.repo.cancel_email_change(&hash_token(token)).await? {
+            return Err(Error::BadRequest("Invalid or expired token".to_string()));
         }
 
         Ok(())
     }
+}
 
-    pub async fn reset_password(&self, token: String, new_password: String) -> Result<()> {
-        let user = self.repo.get_user(None, None, None, Some(&token)).await?;
+pub fn record_login(method: &'static str, success: bool) {
+    let result = if success { "success" } else { "failure" };
+    metrics::counter!("auth_logins_total", "method" => method, "result" => result).increment(1);
+}
 
-        let user = user.ok_or(Error::Unauthorized)?;
+#[cfg(test)]
+mod tests {
+    use lettre::Message;
+
+    use crate::{
+        config::{
+            AuthLimitConfig, JwtConfig, OutboxConfig, PasswordHashConfig, PasswordPolicyConfig,
+        },
+        jwt::keyring::Keyring,
+        mail::mailer::MemoryMailer,
+        repositories::TestDatabase,
+        services::outbox::OutboxService,
+        supervisor::ShutdownTrigger,
+    };
+
+    use super::*;
+
+    const PASSWORD: &str = "quiet-harbor-lantern-42";
+
+    struct Harness {
+        auth: AuthService,
+        outbox: OutboxService,
+        mailer: Arc<MemoryMailer>,
+        repo: PostgresRepo,
+    }
 
-        if let Some(expires_at) = user.token_expires_at {
-            if expires_at < Utc::now() {
-                return Err(Error::BadRequest("Token expired".to_string()));
+    impl Harness {
+        fn new(database: &TestDatabase) -> Self {
+            Self::build(database.repo.clone(), PasswordHashConfig::default())
+        }
+
+        /// Another instance over the same database, as after a restart with new settings.
+        fn build(repo: PostgresRepo, password_hash: PasswordHashConfig) -> Self {
+            let mailer = Arc::new(MemoryMailer::new("noreply@example.com".parse().unwrap()));
+            let jwt = JwtConfig {
+                secret: "test-secret".to_string(),
+                ..JwtConfig::default()
+            };
+            let keyring = Arc::new(Keyring::load(&jwt).unwrap());
+
+            let auth = AuthService::new(
+                repo.clone(),
+                SessionTokens::new(keyring, &jwt),
+                Arc::new(TemplateEngine::new(None, "https://blog.example.com").unwrap()),
+                AuthLimiter::new(Arc::new(repo.clone()), AuthLimitConfig::default()),
+                Arc::new(PasswordPolicy::new(PasswordPolicyConfig::default()).unwrap()),
+                Arc::new(PasswordHasher::new(password_hash).unwrap()),
+                EmailChangeConfig::default(),
+                "https://api.example.com".to_string(),
+            );
+            let outbox = OutboxService::new(repo.clone(), mailer.clone(), OutboxConfig::default());
+
+            Self {
+                auth,
+                outbox,
+                mailer,
+                repo,
             }
         }
 
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(new_password.as_bytes(), &salt)
-            .map_err(|_| Error::InternalServerError)?
-            .to_string();
+        /// Runs the outbox once and returns what it sent.
+        async fn deliver(&self) -> Vec<Message> {
+            let already_sent = self.mailer.sent().len();
+            self.outbox
+                .dispatch_batch(&ShutdownTrigger::new().subscribe())
+                .await
+                .unwrap();
 
-        let user_id =
-            Uuid::parse_str(&user.id.to_string()).map_err(|_| Error::InternalServerError)?;
+            self.mailer.sent().split_off(already_sent)
+        }
 
-        self.repo.update_password(user_id, &password_hash).await?;
+        async fn verified_user(&self, email: &str) -> User {
+            self.auth
+                .register(
+                    "Reader".to_string(),
+                    email.to_string(),
+                    PASSWORD.to_string(),
+                    None,
+                )
+                .await
+                .unwrap();
+            let sent = self.deliver().await;
+            let token = link_token(&sent[0], "/confirm-auth/verify-email");
+            self.auth.verify_email(token).await.unwrap();
+            self.deliver().await;
+
+            self.repo
+                .get_user(None, None, Some(email))
+                .await
+                .unwrap()
+                .unwrap()
+        }
+    }
 
-        self.repo.verifed_token(&token).await?;
+    fn recipient(message: &Message) -> &str {
+        message.headers().get_raw("To").unwrap()
+    }
 
-        Ok(())
+    /// Pulls the token out of the first `path?token=` link in the message, undoing the
+    /// quoted-printable line wrapping long links get.
+    fn link_token(message: &Message, path: &str) -> String {
+        let formatted = String::from_utf8(message.formatted())
+            .unwrap()
+            .replace("=\r\n", "")
+            .replace("=3D", "=");
+        let start = formatted
+            .find(&format!("{}?token=", path))
+            .unwrap_or_else(|| panic!("no {} link in the message", path))
+            + path.len()
+            + "?token=".len();
+
+        formatted[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn registration_mails_a_verification_link_and_then_a_welcome() {
+        let database = TestDatabase::create().await;
+        let harness = Harness::new(&database);
+
+        harness
+            .auth
+            .register(
+                "Reader".to_string(),
+                "reader@example.com".to_string(),
+                PASSWORD.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let sent = harness.deliver().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(recipient(&sent[0]), "reader@example.com");
+
+        let token = link_token(&sent[0], "/confirm-auth/verify-email");
+        harness.auth.verify_email(token.clone()).await.unwrap();
+
+        let sent = harness.deliver().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(recipient(&sent[0]), "reader@example.com");
+        assert_ne!(
+            sent[0].headers().get_raw("Subject"),
+            harness.mailer.sent()[0].headers().get_raw("Subject")
+        );
+
+        assert!(harness.auth.verify_email(token).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn email_change_mails_both_addresses_and_cancel_reverts_it() {
+        let database = TestDatabase::create().await;
+        let harness = Harness::new(&database);
+        let user = harness.verified_user("old@example.com").await;
+
+        harness
+            .auth
+            .request_email_change(&user, PASSWORD, "new@example.com")
+            .await
+            .unwrap();
+
+        let sent = harness.deliver().await;
+        assert_eq!(sent.len(), 2);
+        let confirmation = sent
+            .iter()
+            .find(|message| recipient(message) == "new@example.com")
+            .unwrap();
+        let notice = sent
+            .iter()
+            .find(|message| recipient(message) == "old@example.com")
+            .unwrap();
+
+        let confirm_token = link_token(confirmation, "/confirm-auth/confirm-email-change");
+        let cancel_token = link_token(notice, "/confirm-auth/cancel-email-change");
+
+        let changed = harness
+            .auth
+            .confirm_email_change(&confirm_token)
+            .await
+            .unwrap();
+        assert_eq!(changed.email, "new@example.com");
+        assert!(harness.auth.email_taken("old@example.com").await.unwrap());
+
+        harness
+            .auth
+            .cancel_email_change(&cancel_token)
+            .await
+            .unwrap();
+
+        let reverted = harness
+            .repo
+            .get_user(Some(user.id), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reverted.email, "old@example.com");
+        assert!(harness
+            .auth
+            .cancel_email_change(&cancel_token)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn email_change_to_a_taken_address_sends_nothing() {
+        let database = TestDatabase::create().await;
+        let harness = Harness::new(&database);
+        let user = harness.verified_user("first@example.com").await;
+        harness.verified_user("second@example.com").await;
+
+        for taken in ["second@example.com", " Second@Example.COM "] {
+            assert!(harness
+                .auth
+                .request_email_change(&user, PASSWORD, taken)
+                .await
+                .is_err());
+        }
+        assert!(harness.deliver().await.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn magic_links_of_locked_accounts_are_left_unused() {
+        let database = TestDatabase::create().await;
+        let harness = Harness::new(&database);
+        let user = harness.verified_user("locked@example.com").await;
+        harness.repo.update_magic_link(user.id, true).await.unwrap();
+        harness
+            .auth
+            .request_magic_link(&user.email, None)
+            .await
+            .unwrap();
+        let token = link_token(&harness.deliver().await[0], "/confirm-auth/magic-link");
+
+        for _ in 0..AuthLimitConfig::default().lockout_threshold {
+            assert!(harness
+                .auth
+                .login(&user.email, "wrong-password", None)
+                .await
+                .is_err());
+        }
+
+        assert!(matches!(
+            harness.auth.consume_magic_link(&token, None).await,
+            Err(Error::TooManyRequests(_))
+        ));
+        assert!(harness
+            .repo
+            .find_user_token(TokenPurpose::MagicLink, &hash_token(&token))
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn sign_in_rehashes_passwords_peppered_with_a_retired_pepper() {
+        let database = TestDatabase::create().await;
+        let harness = Harness::new(&database);
+        let peppered = |id: &str, pepper: &str, previous: &[&str]| PasswordHashConfig {
+            pepper: Some(pepper.to_string()),
+            pepper_id: id.to_string(),
+            previous_peppers: previous.iter().map(|entry| entry.to_string()).collect(),
+            ..PasswordHashConfig::default()
+        };
+
+        let before = Harness::build(harness.repo.clone(), peppered("p1", "pepper-one", &[]));
+        let user = before.verified_user("reader@example.com").await;
+
+        let after = Harness::build(
+            harness.repo.clone(),
+            peppered("p2", "pepper-two", &["p1:pepper-one"]),
+        );
+        assert!(after.auth.password_hasher.needs_rehash(&user.password));
+        after
+            .auth
+            .login("reader@example.com", PASSWORD, None)
+            .await
+            .unwrap();
+
+        let rehashed = harness
+            .repo
+            .get_user(Some(user.id), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(rehashed.password, user.password);
+        assert!(!after.auth.password_hasher.needs_rehash(&rehashed.password));
+
+        let retired = Harness::build(harness.repo.clone(), peppered("p2", "pepper-two", &[]));
+        retired
+            .auth
+            .login("reader@example.com", PASSWORD, None)
+            .await
+            .unwrap();
     }
 }
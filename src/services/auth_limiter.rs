@@ -0,0 +1,191 @@
+use std::{net::IpAddr, sync::Arc, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::instrument;
+
+use crate::{
+    config::AuthLimitConfig, models::users::AuthAction,
+    repositories::auth_attempt_repo::AuthAttemptRepository, supervisor::Shutdown, Error, Result,
+};
+
+#[derive(Clone)]
+pub struct AuthLimiter {
+    store: Arc<dyn AuthAttemptRepository>,
+    config: AuthLimitConfig,
+}
+
+impl AuthLimiter {
+    pub fn new(store: Arc<dyn AuthAttemptRepository>, config: AuthLimitConfig) -> Self {
+        Self { store, config }
+    }
+
+    pub async fn run_pruner(self, mut shutdown: Shutdown) {
+        let period = StdDuration::from_secs(self.config.window_secs.max(60) as u64);
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            if let Err(err) = self.store.prune_attempts(self.window_start()).await {
+                tracing::error!(error = ?err, "Failed to prune auth attempts");
+            }
+        }
+    }
+
+    /// Rejects the request when the IP or account is over its window limit or the account is
+    /// locked, then sleeps longer the more recent failures there are.
+    #[instrument(skip_all)]
+    pub async fn check(
+        &self,
+        action: AuthAction,
+        ip: Option<IpAddr>,
+        account: Option<&str>,
+    ) -> Result<()> {
+        let since = self.window_start();
+        let mut failures = 0;
+
+        if let Some(ip) = ip {
+            let key = ip_key(ip);
+            if self
+                .store
+                .count_attempts(action, &key, since, false)
+                .await?
+                >= self.config.ip_max_attempts
+            {
+                return Err(too_many_attempts());
+            }
+
+            failures = self.store.count_attempts(action, &key, since, true).await?;
+        }
+
+        if let Some(account) = account {
+            self.ensure_unlocked(account).await?;
+
+            // Sign-in attempts only count against the account when they fail, so a user who
+            // keeps getting their password right is never throttled; every email sent counts.
+            let key = account_key(account);
+            let account_failures = self.store.count_attempts(action, &key, since, true).await?;
+            let over_limit = match action {
+                AuthAction::MagicLink | AuthAction::ForgotPassword => {
+                    self.store
+                        .count_attempts(action, &key, since, false)
+                        .await?
+                        >= self.config.account_max_emails
+                }
+                AuthAction::Login | AuthAction::ResetPassword => {
+                    account_failures >= self.config.account_max_attempts
+                }
+            };
+            if over_limit {
+                return Err(too_many_attempts());
+            }
+
+            failures = failures.max(account_failures);
+        }
+
+        if failures > 0 {
+            let exponent = (failures - 1).min(16) as u32;
+            let delay = self
+                .config
+                .delay_base_ms
+                .saturating_mul(2u64.pow(exponent))
+                .min(self.config.delay_max_ms);
+            tokio::time::sleep(StdDuration::from_millis(delay)).await;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    pub async fn ensure_unlocked(&self, account: &str) -> Result<()> {
+        match self.store.locked_until(&account_key(account)).await? {
+            Some(locked_until) => Err(Error::TooManyRequests(format!(
+                "Too many failed attempts. Try again in {} minutes.",
+                minutes_until(locked_until)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn record_success(
+        &self,
+        action: AuthAction,
+        ip: Option<IpAddr>,
+        account: Option<&str>,
+    ) -> Result<()> {
+        if let Some(ip) = ip {
+            self.store
+                .record_attempt(action, &ip_key(ip), false)
+                .await?;
+        }
+
+        if let Some(account) = account {
+            let key = account_key(account);
+            self.store.record_attempt(action, &key, false).await?;
+            self.store.clear_failures(action, &key).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the lockout length in minutes when this failure locked the account.
+    #[instrument(skip_all)]
+    pub async fn record_failure(
+        &self,
+        action: AuthAction,
+        ip: Option<IpAddr>,
+        account: Option<&str>,
+    ) -> Result<Option<i64>> {
+        if let Some(ip) = ip {
+            self.store.record_attempt(action, &ip_key(ip), true).await?;
+        }
+
+        let Some(account) = account else {
+            return Ok(None);
+        };
+
+        let key = account_key(account);
+        self.store.record_attempt(action, &key, true).await?;
+
+        let failures = self
+            .store
+            .count_attempts(action, &key, self.window_start(), true)
+            .await?;
+        if failures < self.config.lockout_threshold {
+            return Ok(None);
+        }
+
+        let locked_until = Utc::now() + Duration::seconds(self.config.lockout_secs);
+        if !self.store.lock_account(&key, locked_until).await? {
+            return Ok(None);
+        }
+
+        self.store.clear_failures(action, &key).await?;
+
+        Ok(Some(minutes_until(locked_until)))
+    }
+
+    fn window_start(&self) -> DateTime<Utc> {
+        Utc::now() - Duration::seconds(self.config.window_secs)
+    }
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+fn account_key(account: &str) -> String {
+    format!("account:{}", account.trim().to_lowercase())
+}
+
+fn minutes_until(until: DateTime<Utc>) -> i64 {
+    ((until - Utc::now()).num_seconds() + 59).max(60) / 60
+}
+
+fn too_many_attempts() -> Error {
+    Error::TooManyRequests("Too many attempts. Please try again later.".to_string())
+}
@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tracing::instrument;
+
+use crate::{
+    config::DigestConfig,
+    mail::{mails::digest_email, templates::TemplateEngine},
+    models::digest::{DigestRecipient, DigestRecipientKind},
+    repositories::{digest_repo::DigestRepository, PostgresRepo},
+    supervisor::Shutdown,
+    Result,
+};
+
+use super::newsletter::NewsletterService;
+
+#[derive(Clone)]
+pub struct DigestService {
+    repo: PostgresRepo,
+    templates: Arc<TemplateEngine>,
+    newsletter: NewsletterService,
+    config: DigestConfig,
+}
+
+impl DigestService {
+    pub fn new(
+        repo: PostgresRepo,
+        templates: Arc<TemplateEngine>,
+        newsletter: NewsletterService,
+        config: DigestConfig,
+    ) -> Self {
+        Self {
+            repo,
+            templates,
+            newsletter,
+            config,
+        }
+    }
+
+    pub async fn run_scheduler(self, mut shutdown: Shutdown) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            match self.send_due_digests().await {
+                Ok(0) => {}
+                Ok(sent) => tracing::info!(sent, "Queued digest emails"),
+                Err(err) => tracing::error!(error = ?err, "Failed to send digests"),
+            }
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn send_due_digests(&self) -> Result<usize> {
+        let recipients = self
+            .repo
+            .due_digest_recipients(self.config.batch_size)
+            .await?;
+
+        let Some(oldest) = recipients.iter().map(|recipient| recipient.since).min() else {
+            return Ok(0);
+        };
+
+        let period_end = Utc::now();
+        let posts = self.repo.digest_posts(oldest, period_end).await?;
+        let videos = self.repo.digest_videos(oldest, period_end).await?;
+
+        let mut sent = 0;
+        for recipient in recipients {
+            let posts: Vec<_> = posts
+                .iter()
+                .filter(|post| post.created_at > recipient.since)
+                .take(self.config.max_items)
+                .cloned()
+                .collect();
+            let videos: Vec<_> = videos
+                .iter()
+                .filter(|video| video.created_at > recipient.since)
+                .take(self.config.max_items)
+                .cloned()
+                .collect();
+
+            let email = if posts.is_empty() && videos.is_empty() {
+                None
+            } else {
+                let (preferences_link, unsubscribe_link, headers) = self.links(&recipient);
+                let mut email = match digest_email(
+                    &self.templates,
+                    &recipient,
+                    &posts,
+                    &videos,
+                    &preferences_link,
+                    &unsubscribe_link,
+                ) {
+                    Ok(email) => email,
+                    Err(err) => {
+                        tracing::error!(
+                            recipient = %recipient.email,
+                            error = ?err,
+                            "Failed to render digest"
+                        );
+                        continue;
+                    }
+                };
+                email.headers = headers;
+
+                Some(email)
+            };
+
+            let post_ids: Vec<_> = posts.iter().map(|post| post.id).collect();
+            let video_ids: Vec<_> = videos.iter().map(|video| video.id).collect();
+
+            if self
+                .repo
+                .record_digest(
+                    &recipient,
+                    period_end,
+                    &post_ids,
+                    &video_ids,
+                    email.as_ref(),
+                )
+                .await?
+            {
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    fn links(&self, recipient: &DigestRecipient) -> (String, String, BTreeMap<String, String>) {
+        match recipient.kind {
+            DigestRecipientKind::Subscriber => {
+                let token = self.newsletter.unsubscribe_token(recipient.id);
+
+                (
+                    self.newsletter.preferences_link(&token),
+                    self.newsletter.unsubscribe_link(&token),
+                    self.newsletter.list_unsubscribe_headers(&token),
+                )
+            }
+            DigestRecipientKind::User => {
+                let settings_link =
+                    format!("{}/settings/notifications", self.templates.front_url());
+
+                (settings_link.clone(), settings_link, BTreeMap::new())
+            }
+        }
+    }
+}
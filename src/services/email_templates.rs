@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::{
+    mail::{
+        mailer::Mailer,
+        mails::sample_context,
+        sendmail::{render_email, send_email},
+        templates::{TemplateEngine, DEFAULT_LOCALE, LOCALES, TEMPLATE_NAMES},
+    },
+    models::email::{EmailPreviewDto, EmailTemplateInfo},
+    Error, Result,
+};
+
+#[derive(Clone)]
+pub struct EmailTemplatesService {
+    templates: Arc<TemplateEngine>,
+    mailer: Arc<dyn Mailer>,
+}
+
+impl EmailTemplatesService {
+    pub fn new(templates: Arc<TemplateEngine>, mailer: Arc<dyn Mailer>) -> Self {
+        Self { templates, mailer }
+    }
+
+    pub fn list_templates(&self) -> Vec<EmailTemplateInfo> {
+        TEMPLATE_NAMES
+            .iter()
+            .map(|name| EmailTemplateInfo {
+                name: name.to_string(),
+                locales: LOCALES.iter().map(|locale| locale.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    pub fn preview(&self, template_name: &str, locale: Option<&str>) -> Result<EmailPreviewDto> {
+        let context =
+            sample_context(template_name, self.templates.front_url()).ok_or(Error::NotFound)?;
+        let rendered =
+            self.templates
+                .render(template_name, locale.unwrap_or(DEFAULT_LOCALE), context)?;
+
+        Ok(EmailPreviewDto {
+            subject: rendered.subject,
+            html: rendered.html,
+            text: rendered.text,
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub async fn send_test(
+        &self,
+        template_name: &str,
+        to: &str,
+        locale: Option<&str>,
+    ) -> Result<()> {
+        let context =
+            sample_context(template_name, self.templates.front_url()).ok_or(Error::NotFound)?;
+        let mut email = render_email(
+            &self.templates,
+            to,
+            template_name,
+            locale.unwrap_or(DEFAULT_LOCALE),
+            context,
+        )?;
+        email.subject = format!("[Test] {}", email.subject);
+
+        send_email(self.mailer.as_ref(), &email).await
+    }
+}
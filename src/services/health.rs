@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    config::HealthConfig,
+    mail::mailer::Mailer,
+    models::health::{CheckResult, CheckStatus, Readiness},
+    repositories::{health_repo::HealthRepository, PostgresRepo},
+    Error,
+};
+
+/// A check fails with a message that is safe to show on the public `/readyz`; the
+/// underlying error is logged.
+type CheckOutcome = std::result::Result<(), String>;
+
+type Checks = BTreeMap<&'static str, CheckResult>;
+
+#[derive(Clone)]
+pub struct HealthService {
+    repo: PostgresRepo,
+    mailer: Arc<dyn Mailer>,
+    storage_dirs: Vec<PathBuf>,
+    config: HealthConfig,
+    shutting_down: Arc<AtomicBool>,
+    last_checks: Arc<Mutex<Option<(Instant, Checks)>>>,
+}
+
+impl HealthService {
+    pub fn new(
+        repo: PostgresRepo,
+        mailer: Arc<dyn Mailer>,
+        storage_dirs: Vec<PathBuf>,
+        config: HealthConfig,
+    ) -> Self {
+        Self {
+            repo,
+            mailer,
+            storage_dirs,
+            config,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            last_checks: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Fails readiness from now on so load balancers stop routing new traffic here.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub async fn readiness(&self) -> Readiness {
+        let shutting_down = self.shutting_down.load(Ordering::SeqCst);
+        let checks = self.cached_checks().await;
+
+        let ready = !shutting_down
+            && checks
+                .values()
+                .all(|check| check.status != CheckStatus::Failed);
+
+        Readiness {
+            ready,
+            shutting_down,
+            checks,
+        }
+    }
+
+    /// Requests that arrive while the checks run wait for that run instead of starting their own.
+    async fn cached_checks(&self) -> Checks {
+        let mut last_checks = self.last_checks.lock().await;
+
+        if let Some((checked_at, checks)) = last_checks.as_ref() {
+            if checked_at.elapsed() < self.config.cache_ttl {
+                return checks.clone();
+            }
+        }
+
+        let checks = self.run_checks().await;
+        *last_checks = Some((Instant::now(), checks.clone()));
+
+        checks
+    }
+
+    async fn run_checks(&self) -> Checks {
+        let (database, migrations, smtp, storage) = tokio::join!(
+            self.run_check("database", self.check_database()),
+            self.run_check("migrations", self.check_migrations()),
+            async {
+                if self.config.check_smtp {
+                    self.run_check("smtp", self.check_smtp()).await
+                } else {
+                    CheckResult {
+                        status: CheckStatus::Skipped,
+                        duration_ms: 0,
+                        error: None,
+                    }
+                }
+            },
+            self.run_check("storage", self.check_storage()),
+        );
+
+        BTreeMap::from([
+            ("database", database),
+            ("migrations", migrations),
+            ("smtp", smtp),
+            ("storage", storage),
+        ])
+    }
+
+    async fn run_check(
+        &self,
+        name: &'static str,
+        check: impl Future<Output = CheckOutcome>,
+    ) -> CheckResult {
+        let start = Instant::now();
+        let outcome = tokio::time::timeout(self.config.check_timeout, check)
+            .await
+            .unwrap_or_else(|_| {
+                tracing::warn!(check = name, "Readiness check timed out");
+                Err(format!(
+                    "timed out after {}ms",
+                    self.config.check_timeout.as_millis()
+                ))
+            });
+
+        CheckResult {
+            status: if outcome.is_ok() {
+                CheckStatus::Ok
+            } else {
+                CheckStatus::Failed
+            },
+            duration_ms: start.elapsed().as_millis() as u64,
+            error: outcome.err(),
+        }
+    }
+
+    async fn check_database(&self) -> CheckOutcome {
+        self.repo
+            .ping()
+            .await
+            .map_err(|err| failed("database", err, "database is unreachable"))
+    }
+
+    async fn check_migrations(&self) -> CheckOutcome {
+        let pending = self
+            .repo
+            .pending_migrations()
+            .await
+            .map_err(|err| failed("migrations", err, "could not read migration status"))?;
+
+        if pending > 0 {
+            return Err(format!("{} migration(s) pending", pending));
+        }
+
+        Ok(())
+    }
+
+    async fn check_smtp(&self) -> CheckOutcome {
+        self.mailer
+            .check_connection()
+            .await
+            .map_err(|err| failed("smtp", err, "SMTP server is unreachable"))
+    }
+
+    async fn check_storage(&self) -> CheckOutcome {
+        for dir in &self.storage_dirs {
+            let probe = dir.join(format!(".readyz-{}", Uuid::now_v7()));
+            let written = tokio::fs::write(&probe, b"ok").await;
+            let _ = tokio::fs::remove_file(&probe).await;
+
+            if let Err(err) = written {
+                tracing::warn!(check = "storage", dir = %dir.display(), error = ?err, "Readiness check failed");
+                return Err(format!("{} is not writable", dir.display()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn failed(check: &str, err: Error, message: &str) -> String {
+    tracing::warn!(check, error = ?err, "Readiness check failed");
+    message.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+
+    use crate::mail::mailer::MemoryMailer;
+
+    use super::*;
+
+    fn service(storage_dir: PathBuf, cache_ttl: Duration) -> HealthService {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://readyz@127.0.0.1:1/readyz")
+            .unwrap();
+
+        HealthService::new(
+            PostgresRepo::new(pool),
+            Arc::new(MemoryMailer::new("noreply@example.com".parse().unwrap())),
+            vec![storage_dir],
+            HealthConfig {
+                check_timeout: Duration::from_millis(500),
+                check_smtp: false,
+                cache_ttl,
+            },
+        )
+    }
+
+    fn storage_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, Uuid::now_v7()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn check_results_are_reused_within_the_ttl() {
+        let dir = storage_dir("readyz-cached");
+        let health = service(dir.clone(), Duration::from_secs(60));
+
+        let first = health.readiness().await;
+        assert_eq!(first.checks["storage"].status, CheckStatus::Ok);
+        assert_eq!(first.checks["database"].status, CheckStatus::Failed);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let second = health.readiness().await;
+        assert_eq!(second.checks["storage"].status, CheckStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn checks_rerun_once_the_ttl_is_over() {
+        let dir = storage_dir("readyz-expired");
+        let health = service(dir.clone(), Duration::ZERO);
+
+        assert_eq!(
+            health.readiness().await.checks["storage"].status,
+            CheckStatus::Ok
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            health.readiness().await.checks["storage"].status,
+            CheckStatus::Failed
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_is_reported_without_waiting_for_the_cache() {
+        let dir = storage_dir("readyz-shutdown");
+        let health = service(dir.clone(), Duration::from_secs(60));
+        health.readiness().await;
+
+        health.begin_shutdown();
+        let readiness = health.readiness().await;
+
+        assert!(readiness.shutting_down);
+        assert!(!readiness.ready);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    path::{Component, Path, PathBuf},
+    time::Instant,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use image::{imageops::FilterType, ImageFormat, ImageReader};
+use tokio::task::JoinSet;
+use tracing::instrument;
+
+use crate::{
+    config::MediaConfig,
+    models::media::{ArticleImages, ImageMetadata, ImageMetadataResponse, ImageVariant},
+    repositories::{media_repo::MediaRepository, PostgresRepo},
+    supervisor::Shutdown,
+    Error, Result,
+};
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+const LQIP_SIZE: u32 = 16;
+
+struct ComputedImage {
+    width: u32,
+    height: u32,
+    dominant_color: String,
+    blurhash: String,
+    lqip: String,
+}
+
+#[derive(Clone)]
+pub struct MediaService {
+    repo: PostgresRepo,
+    api_url: String,
+    config: MediaConfig,
+}
+
+impl MediaService {
+    pub fn new(repo: PostgresRepo, api_url: String, config: MediaConfig) -> Self {
+        Self {
+            repo,
+            api_url,
+            config,
+        }
+    }
+
+    pub fn assets_dir(&self) -> &Path {
+        Path::new(&self.config.assets_dir)
+    }
+
+    pub fn image_width(&self, width: u32) -> u32 {
+        snap_width(&self.config.allowed_widths, width)
+    }
+
+    pub fn asset_path(&self, url: &str) -> Option<PathBuf> {
+        let images_prefix = format!("{}/api/images/", self.api_url);
+        let path = PathBuf::from(url.strip_prefix(&images_prefix).unwrap_or(url));
+
+        let valid = path.components().count() == 2
+            && path.components().all(|c| matches!(c, Component::Normal(_)));
+
+        valid.then_some(path)
+    }
+
+    pub fn image_url(&self, path: &str) -> String {
+        format!("{}/api/images/{}", self.api_url, path)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn image_metadata(&self, path: &str) -> Result<ImageMetadataResponse> {
+        let (key, file) = self.resolve(path)?;
+        let cached = self.repo.get_image_metadata(&key).await?;
+
+        self.fresh_or_computed(key, file, cached).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn cover_metadata(&self, cover_image: Option<&str>) -> Option<ImageMetadataResponse> {
+        let cover_image = cover_image?;
+
+        match self.image_metadata(cover_image).await {
+            Ok(metadata) => Some(metadata),
+            Err(err) => {
+                warn_cover(cover_image, &err);
+                None
+            }
+        }
+    }
+
+    /// Like [`Self::cover_metadata`] for a whole list, with a single cache query; covers that
+    /// are missing or stale are computed concurrently.
+    #[instrument(skip_all)]
+    pub async fn covers_metadata(
+        &self,
+        cover_images: &[Option<&str>],
+    ) -> Vec<Option<ImageMetadataResponse>> {
+        let resolved: Vec<_> = cover_images
+            .iter()
+            .map(|cover_image| {
+                cover_image.map(|cover_image| (cover_image, self.resolve(cover_image)))
+            })
+            .collect();
+
+        let keys: Vec<String> = resolved
+            .iter()
+            .flatten()
+            .filter_map(|(_, resolved)| resolved.as_ref().ok())
+            .map(|(key, _)| key.clone())
+            .collect();
+        let cached: HashMap<String, ImageMetadata> =
+            match self.repo.get_images_metadata(&keys).await {
+                Ok(cached) => cached
+                    .into_iter()
+                    .map(|metadata| (metadata.path.clone(), metadata))
+                    .collect(),
+                Err(err) => {
+                    tracing::warn!(error = ?err, "Failed to load cached cover metadata");
+                    HashMap::new()
+                }
+            };
+
+        let mut covers = vec![None; cover_images.len()];
+        let mut tasks = JoinSet::new();
+        for (index, resolved) in resolved.into_iter().enumerate() {
+            match resolved {
+                None => {}
+                Some((cover_image, Err(err))) => warn_cover(cover_image, &err),
+                Some((cover_image, Ok((key, file)))) => {
+                    let service = self.clone();
+                    let cover_image = cover_image.to_string();
+                    let cached = cached.get(&key).cloned();
+                    tasks.spawn(async move {
+                        let metadata = service.fresh_or_computed(key, file, cached).await;
+                        (index, cover_image, metadata)
+                    });
+                }
+            }
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, _, Ok(metadata))) => covers[index] = Some(metadata),
+                Ok((_, cover_image, Err(err))) => warn_cover(&cover_image, &err),
+                Err(err) => tracing::warn!(error = ?err, "Cover metadata task failed"),
+            }
+        }
+
+        covers
+    }
+
+    /// The cache key and file of an image URL or path under the assets directory.
+    fn resolve(&self, path: &str) -> Result<(String, PathBuf)> {
+        let relative = self
+            .asset_path(path)
+            .ok_or(Error::BadRequest("Invalid image path".to_string()))?;
+        let file = self.assets_dir().join(&relative);
+
+        Ok((relative.to_string_lossy().to_string(), file))
+    }
+
+    /// Uses `cached` while it matches the file on disk, otherwise decodes the image off the
+    /// async runtime and stores the result.
+    async fn fresh_or_computed(
+        &self,
+        key: String,
+        file: PathBuf,
+        cached: Option<ImageMetadata>,
+    ) -> Result<ImageMetadataResponse> {
+        let file_info = tokio::fs::metadata(&file)
+            .await
+            .map_err(|_| Error::NotFound)?;
+        let file_size = file_info.len() as i64;
+        let file_modified_at: DateTime<Utc> = file_info
+            .modified()
+            .map_err(|_| Error::InternalServerError)?
+            .into();
+
+        let metadata = match cached {
+            Some(cached)
+                if cached.file_size == file_size
+                    && cached.file_modified_at.timestamp() == file_modified_at.timestamp() =>
+            {
+                metrics::counter!("image_metadata_cache_total", "result" => "hit").increment(1);
+                cached
+            }
+            _ => {
+                metrics::counter!("image_metadata_cache_total", "result" => "miss").increment(1);
+
+                let bytes = tokio::fs::read(&file).await.map_err(|_| Error::NotFound)?;
+                let start = Instant::now();
+                let computed = tokio::task::spawn_blocking(move || compute_metadata(&bytes))
+                    .await
+                    .map_err(|_| Error::InternalServerError)??;
+                metrics::histogram!("image_processing_duration_seconds", "operation" => "metadata")
+                    .record(start.elapsed().as_secs_f64());
+
+                let metadata = ImageMetadata {
+                    path: key,
+                    width: computed.width as i32,
+                    height: computed.height as i32,
+                    dominant_color: computed.dominant_color,
+                    blurhash: computed.blurhash,
+                    lqip: computed.lqip,
+                    file_size,
+                    file_modified_at,
+                };
+                self.repo.upsert_image_metadata(&metadata).await?;
+
+                metadata
+            }
+        };
+
+        Ok(self.to_response(metadata))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn article_images(&self, slug: &str) -> Result<ArticleImages> {
+        let mut components = Path::new(slug).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(Error::BadRequest("Invalid article".to_string()));
+        }
+
+        let mut entries = tokio::fs::read_dir(self.assets_dir().join(slug))
+            .await
+            .map_err(|_| Error::NotFound)?;
+
+        let mut file_names = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|_| Error::InternalServerError)?
+        {
+            let path = entry.path();
+            let is_image = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+
+            if is_image {
+                file_names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        file_names.sort();
+
+        let mut cover = None;
+        let mut images = Vec::with_capacity(file_names.len());
+        for file_name in file_names {
+            let metadata = self
+                .image_metadata(&format!("{}/{}", slug, file_name))
+                .await?;
+
+            if cover.is_none() && Path::new(&file_name).file_stem() == Some("cover".as_ref()) {
+                cover = Some(metadata.clone());
+            }
+            images.push(metadata);
+        }
+
+        Ok(ArticleImages {
+            slug: slug.to_string(),
+            cover: cover.or_else(|| images.first().cloned()),
+            images,
+        })
+    }
+
+    #[instrument(skip_all)]
+    /// Warms the metadata cache once and then idles, so the supervisor only restarts it
+    /// after a failure.
+    pub async fn run_cache_warmer(self, mut shutdown: Shutdown) {
+        if let Err(err) = self.warm_cache(&shutdown).await {
+            tracing::error!(error = ?err, "Failed to warm image metadata cache");
+            return;
+        }
+
+        shutdown.wait().await;
+    }
+
+    /// Stops between articles once shutdown starts.
+    pub async fn warm_cache(&self, shutdown: &Shutdown) -> Result<()> {
+        let mut entries = tokio::fs::read_dir(self.assets_dir())
+            .await
+            .map_err(|_| Error::InternalServerError)?;
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|_| Error::InternalServerError)?
+        {
+            if shutdown.is_triggered() {
+                break;
+            }
+
+            if !entry.path().is_dir() {
+                continue;
+            }
+
+            let slug = entry.file_name().to_string_lossy().to_string();
+            if let Err(err) = self.article_images(&slug).await {
+                tracing::error!(slug, error = ?err, "Failed to compute image metadata");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_response(&self, metadata: ImageMetadata) -> ImageMetadataResponse {
+        let src = self.image_url(&metadata.path);
+
+        let variants: Vec<ImageVariant> = self
+            .config
+            .allowed_widths
+            .iter()
+            .filter(|&&width| width <= metadata.width as u32)
+            .map(|&width| ImageVariant {
+                width,
+                url: format!(
+                    "{}/_next/image?url={}&w={}",
+                    self.api_url,
+                    urlencoding::encode(&src),
+                    width
+                ),
+            })
+            .collect();
+
+        let srcset = variants
+            .iter()
+            .map(|variant| format!("{} {}w", variant.url, variant.width))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        ImageMetadataResponse {
+            src,
+            width: metadata.width,
+            height: metadata.height,
+            dominant_color: metadata.dominant_color,
+            blurhash: metadata.blurhash,
+            lqip: metadata.lqip,
+            variants,
+            srcset,
+        }
+    }
+}
+
+fn warn_cover(cover_image: &str, err: &Error) {
+    tracing::warn!(
+        image = %cover_image,
+        error = ?err,
+        "Failed to load cover metadata"
+    );
+}
+
+/// Rounds up to the next allowed width so arbitrary widths can't each produce a new resize;
+/// anything wider than the largest allowed width gets that one.
+fn snap_width(allowed_widths: &[u32], width: u32) -> u32 {
+    allowed_widths
+        .iter()
+        .copied()
+        .filter(|&allowed| allowed >= width)
+        .min()
+        .or_else(|| allowed_widths.iter().copied().max())
+        .unwrap_or(width)
+}
+
+fn compute_metadata(bytes: &[u8]) -> Result<ComputedImage> {
+    let img = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| Error::BadRequest("Unknown image format".to_string()))?
+        .decode()?;
+
+    let (components_x, components_y) = if img.width() >= img.height() {
+        (4, 3)
+    } else {
+        (3, 4)
+    };
+    let thumb = img.thumbnail(32, 32).to_rgba8();
+    let blurhash = blurhash::encode(
+        components_x,
+        components_y,
+        thumb.width(),
+        thumb.height(),
+        thumb.as_raw(),
+    )
+    .map_err(|_| Error::InternalServerError)?;
+
+    let pixel = img.resize_exact(1, 1, FilterType::Triangle).to_rgb8();
+    let [r, g, b] = pixel.get_pixel(0, 0).0;
+    let dominant_color = format!("#{:02x}{:02x}{:02x}", r, g, b);
+
+    let mut lqip_buf = Cursor::new(Vec::new());
+    img.thumbnail(LQIP_SIZE, LQIP_SIZE)
+        .to_rgba8()
+        .write_to(&mut lqip_buf, ImageFormat::WebP)?;
+    let lqip = format!(
+        "data:image/webp;base64,{}",
+        STANDARD.encode(lqip_buf.into_inner())
+    );
+
+    Ok(ComputedImage {
+        width: img.width(),
+        height: img.height(),
+        dominant_color,
+        blurhash,
+        lqip,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_round_up_to_the_next_allowed_width() {
+        let allowed = [640, 64, 1920, 256];
+
+        assert_eq!(snap_width(&allowed, 1), 64);
+        assert_eq!(snap_width(&allowed, 64), 64);
+        assert_eq!(snap_width(&allowed, 65), 256);
+        assert_eq!(snap_width(&allowed, 1080), 1920);
+        assert_eq!(snap_width(&allowed, 4000), 1920);
+        assert_eq!(snap_width(&[], 300), 300);
+    }
+}
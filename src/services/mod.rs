@@ -1,4 +1,13 @@
 pub mod auth;
+pub mod auth_limiter;
+pub mod digest;
+pub mod email_templates;
+pub mod health;
+pub mod media;
+pub mod newsletter;
+pub mod oidc;
+pub mod outbox;
 pub mod posts;
 pub mod user;
+pub mod user_tokens;
 pub mod video;
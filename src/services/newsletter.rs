@@ -0,0 +1,312 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::NewsletterConfig,
+    mail::{
+        mails::{newsletter_confirmation_email, newsletter_issue_email},
+        templates::{TemplateEngine, DEFAULT_LOCALE, LOCALES},
+    },
+    models::{
+        digest::DigestFrequency,
+        newsletter::{NewsletterIssue, Subscriber, SubscriberStatus},
+    },
+    repositories::{newsletter_repo::NewsletterRepository, PostgresRepo},
+    Error, Result,
+};
+
+use super::user_tokens::{generate_token, hash_token};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Repeat sign-ups for a pending address only mail it again after this long.
+const CONFIRMATION_RESEND_COOLDOWN_MINUTES: i64 = 10;
+
+#[derive(Clone)]
+pub struct NewsletterService {
+    repo: PostgresRepo,
+    templates: Arc<TemplateEngine>,
+    config: NewsletterConfig,
+    api_url: String,
+}
+
+impl NewsletterService {
+    pub fn new(
+        repo: PostgresRepo,
+        templates: Arc<TemplateEngine>,
+        config: NewsletterConfig,
+        api_url: String,
+    ) -> Self {
+        Self {
+            repo,
+            templates,
+            config,
+            api_url,
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn subscribe(&self, email: &str, locale: Option<&str>) -> Result<()> {
+        let email = email.trim().to_lowercase();
+        let locale = locale
+            .and_then(|locale| {
+                LOCALES
+                    .iter()
+                    .find(|supported| supported.eq_ignore_ascii_case(locale))
+            })
+            .copied()
+            .unwrap_or(DEFAULT_LOCALE);
+
+        let confirmation_token = generate_token();
+        let now = Utc::now();
+        let expires_at = now + Duration::hours(self.config.confirmation_ttl_hours);
+        let confirmation_email = newsletter_confirmation_email(
+            &self.templates,
+            &email,
+            &confirmation_token.token,
+            self.config.confirmation_ttl_hours,
+            locale,
+        )?;
+
+        self.repo
+            .subscribe(
+                &email,
+                locale,
+                &confirmation_token.hash,
+                expires_at,
+                now - Duration::minutes(CONFIRMATION_RESEND_COOLDOWN_MINUTES),
+                &confirmation_email,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    pub async fn confirm(&self, confirmation_token: &str) -> Result<Subscriber> {
+        self.repo
+            .confirm_subscriber(&hash_token(confirmation_token))
+            .await?
+            .ok_or(Error::BadRequest(
+                "Invalid or expired confirmation token".to_string(),
+            ))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn unsubscribe(&self, token: &str) -> Result<Subscriber> {
+        let subscriber_id = self
+            .verify_unsubscribe_token(token)
+            .ok_or(Error::BadRequest("Invalid unsubscribe token".to_string()))?;
+
+        self.repo
+            .unsubscribe(subscriber_id)
+            .await?
+            .ok_or(Error::NotFound)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn update_digest_frequency(
+        &self,
+        token: &str,
+        frequency: DigestFrequency,
+    ) -> Result<Subscriber> {
+        let subscriber_id = self
+            .verify_unsubscribe_token(token)
+            .ok_or(Error::BadRequest("Invalid subscription token".to_string()))?;
+
+        self.repo
+            .update_subscriber_digest(subscriber_id, frequency)
+            .await?
+            .ok_or(Error::NotFound)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn list_subscribers(
+        &self,
+        status: Option<SubscriberStatus>,
+    ) -> Result<Vec<Subscriber>> {
+        self.repo.list_subscribers(status).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn list_issues(&self) -> Result<Vec<NewsletterIssue>> {
+        self.repo.list_issues().await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn send_issue(
+        &self,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        sent_by: Uuid,
+    ) -> Result<NewsletterIssue> {
+        let subscribers = self
+            .repo
+            .list_subscribers(Some(SubscriberStatus::Confirmed))
+            .await?;
+
+        let rate = self.config.send_rate_per_minute.max(1);
+        let started_at = Utc::now();
+
+        let mut emails = Vec::with_capacity(subscribers.len());
+        for (index, subscriber) in subscribers.iter().enumerate() {
+            let token = self.unsubscribe_token(subscriber.id);
+            let unsubscribe_link = self.unsubscribe_link(&token);
+
+            let mut email = newsletter_issue_email(
+                &self.templates,
+                &subscriber.email,
+                subject,
+                html_content,
+                text_content,
+                &unsubscribe_link,
+                &subscriber.locale,
+            )?;
+            email.headers = self.list_unsubscribe_headers(&token);
+
+            let send_at = started_at + Duration::milliseconds(index as i64 * 60_000 / rate);
+            emails.push((email, send_at));
+        }
+
+        let issue = NewsletterIssue {
+            id: Uuid::now_v7(),
+            subject: subject.to_string(),
+            html_content: html_content.to_string(),
+            text_content: text_content.to_string(),
+            recipients: emails.len() as i32,
+            sent_by: Some(sent_by),
+            created_at: started_at,
+        };
+
+        self.repo.create_issue(&issue, &emails).await
+    }
+
+    pub fn unsubscribe_link(&self, token: &str) -> String {
+        format!(
+            "{}/newsletter/unsubscribe?token={}",
+            self.templates.front_url(),
+            token
+        )
+    }
+
+    pub fn preferences_link(&self, token: &str) -> String {
+        format!(
+            "{}/newsletter/preferences?token={}",
+            self.templates.front_url(),
+            token
+        )
+    }
+
+    pub fn list_unsubscribe_headers(&self, token: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (
+                "List-Unsubscribe".to_string(),
+                format!(
+                    "<{}/api/newsletter/unsubscribe?token={}>",
+                    self.api_url, token
+                ),
+            ),
+            (
+                "List-Unsubscribe-Post".to_string(),
+                "List-Unsubscribe=One-Click".to_string(),
+            ),
+        ])
+    }
+
+    pub fn unsubscribe_token(&self, subscriber_id: Uuid) -> String {
+        let signature = self.sign(subscriber_id).finalize().into_bytes();
+
+        format!(
+            "{}.{}",
+            subscriber_id.simple(),
+            URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    fn verify_unsubscribe_token(&self, token: &str) -> Option<Uuid> {
+        let (subscriber_id, signature) = token.split_once('.')?;
+        let subscriber_id = Uuid::parse_str(subscriber_id).ok()?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        self.sign(subscriber_id)
+            .verify_slice(&signature)
+            .ok()
+            .map(|_| subscriber_id)
+    }
+
+    fn sign(&self, subscriber_id: Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.config.signing_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(b"newsletter-unsubscribe:");
+        mac.update(subscriber_id.as_bytes());
+
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::OutboxConfig, mail::mailer::MemoryMailer, repositories::TestDatabase,
+        services::outbox::OutboxService, supervisor::ShutdownTrigger,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn confirmation_is_throttled_and_confirms_by_the_mailed_token() {
+        let database = TestDatabase::create().await;
+        let repo = database.repo.clone();
+        let newsletter = NewsletterService::new(
+            repo.clone(),
+            Arc::new(TemplateEngine::new(None, "https://blog.example.com").unwrap()),
+            NewsletterConfig {
+                signing_secret: "test-secret".to_string(),
+                ..NewsletterConfig::default()
+            },
+            "https://api.example.com".to_string(),
+        );
+        let mailer = Arc::new(MemoryMailer::new("noreply@example.com".parse().unwrap()));
+        let outbox = OutboxService::new(repo, mailer.clone(), OutboxConfig::default());
+
+        newsletter
+            .subscribe("Reader@Example.com", None)
+            .await
+            .unwrap();
+        newsletter
+            .subscribe("reader@example.com", None)
+            .await
+            .unwrap();
+        outbox
+            .dispatch_batch(&ShutdownTrigger::new().subscribe())
+            .await
+            .unwrap();
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+
+        let formatted = String::from_utf8(sent[0].formatted())
+            .unwrap()
+            .replace("=\r\n", "")
+            .replace("=3D", "=");
+        let start = formatted.find("/newsletter/confirm?token=").unwrap()
+            + "/newsletter/confirm?token=".len();
+        let token: String = formatted[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+
+        assert!(newsletter.confirm(&hash_token(&token)).await.is_err());
+        let subscriber = newsletter.confirm(&token).await.unwrap();
+        assert_eq!(subscriber.email, "reader@example.com");
+        assert!(newsletter.confirm(&token).await.is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::{collections::HashMap, sync::Arc, time::Duration as StdDuration};
+
+use chrono::{Duration, Utc};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::OidcConfig,
+    jwt::token::SessionToken,
+    mail::templates::{normalize_locale, DEFAULT_LOCALE},
+    models::{
+        oidc::{OidcState, UserIdentity},
+        users::User,
+    },
+    oidc::client::{IdentityClaims, OidcClient},
+    password::hasher::PasswordHasher,
+    repositories::{oidc_repo::OidcRepository, user_repo::UserRepository, PostgresRepo},
+    Error, Result,
+};
+
+use super::{
+    auth::{record_login, AuthService},
+    user_tokens::{generate_token, hash_token},
+};
+
+const MAX_NAME_LENGTH: usize = 100;
+
+pub struct OidcAuthorization {
+    pub url: String,
+    /// Also handed to the browser in a cookie; the callback must present both.
+    pub state: String,
+}
+
+#[derive(Clone)]
+pub struct OidcService {
+    repo: PostgresRepo,
+    auth_service: AuthService,
+    clients: Arc<HashMap<String, OidcClient>>,
+    state_ttl_minutes: i64,
+    password_hasher: Arc<PasswordHasher>,
+}
+
+impl OidcService {
+    pub fn new(
+        repo: PostgresRepo,
+        auth_service: AuthService,
+        config: &OidcConfig,
+        password_hasher: Arc<PasswordHasher>,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(StdDuration::from_secs(config.http_timeout_secs))
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+
+        let clients = config
+            .providers
+            .values()
+            .map(|provider| {
+                let client = OidcClient::new(provider.clone(), http.clone());
+                (client.name().to_string(), client)
+            })
+            .collect();
+
+        Ok(Self {
+            repo,
+            auth_service,
+            clients: Arc::new(clients),
+            state_ttl_minutes: config.state_ttl_minutes,
+            password_hasher,
+        })
+    }
+
+    pub fn providers(&self) -> Vec<String> {
+        let mut providers: Vec<_> = self.clients.keys().cloned().collect();
+        providers.sort();
+
+        providers
+    }
+
+    #[instrument(skip_all)]
+    pub async fn authorization_url(&self, provider: &str) -> Result<OidcAuthorization> {
+        let client = self.clients.get(provider).ok_or(Error::NotFound)?;
+
+        let state = generate_token();
+        let nonce = generate_token().token;
+        let pkce_verifier = generate_token().token;
+
+        let authorization_url = client
+            .authorization_url(&state.token, &nonce, &pkce_verifier)
+            .await?;
+
+        self.repo
+            .create_oidc_state(&OidcState {
+                state_hash: state.hash,
+                provider: provider.to_string(),
+                nonce,
+                pkce_verifier,
+                expires_at: Utc::now() + Duration::minutes(self.state_ttl_minutes),
+            })
+            .await?;
+
+        Ok(OidcAuthorization {
+            url: authorization_url,
+            state: state.token,
+        })
+    }
+
+    pub fn state_ttl_minutes(&self) -> i64 {
+        self.state_ttl_minutes
+    }
+
+    /// `browser_state` is the state cookie set when the sign-in started.
+    #[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+    pub async fn sign_in(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        browser_state: Option<&str>,
+    ) -> Result<SessionToken> {
+        let client = self.clients.get(provider).ok_or(Error::NotFound)?;
+
+        let result = match check_state_binding(state, browser_state) {
+            Ok(()) => self.complete_sign_in(client, provider, code, state).await,
+            Err(err) => Err(err),
+        };
+        record_login("oidc", result.is_ok());
+
+        result
+    }
+
+    async fn complete_sign_in(
+        &self,
+        client: &OidcClient,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<SessionToken> {
+        let login_state = self
+            .repo
+            .consume_oidc_state(provider, &hash_token(state))
+            .await?
+            .ok_or(Error::BadRequest(
+                "Invalid or expired sign-in state".to_string(),
+            ))?;
+
+        let claims = client
+            .exchange_code(code, &login_state.pkce_verifier, &login_state.nonce)
+            .await?;
+
+        let user = self.resolve_user(provider, claims).await?;
+        tracing::Span::current().record("user_id", tracing::field::display(user.id));
+
+        self.auth_service.session_token(&user)
+    }
+
+    #[instrument(skip_all)]
+    async fn resolve_user(&self, provider: &str, claims: IdentityClaims) -> Result<User> {
+        if let Some(user) = self.repo.find_identity_user(provider, &claims.sub).await? {
+            return Ok(user);
+        }
+
+        let email = claims
+            .email
+            .clone()
+            .filter(|_| claims.email_verified)
+            .ok_or(Error::BadRequest(
+                "The identity provider did not return a verified email".to_string(),
+            ))?;
+
+        if let Some(user) = self.repo.get_user(None, None, Some(&email)).await? {
+            let identity = UserIdentity {
+                id: Uuid::now_v7(),
+                user_id: user.id,
+                provider: provider.to_string(),
+                subject: claims.sub,
+                email,
+            };
+            let replace_password = if user.verified {
+                None
+            } else {
+                Some(self.unusable_password()?)
+            };
+
+            return self
+                .repo
+                .link_identity(&identity, replace_password.as_deref())
+                .await;
+        }
+
+        let name = self.available_name(&claims, &email).await?;
+        let locale = claims
+            .locale
+            .as_deref()
+            .map(normalize_locale)
+            .unwrap_or(DEFAULT_LOCALE);
+        let identity = UserIdentity {
+            id: Uuid::now_v7(),
+            user_id: Uuid::now_v7(),
+            provider: provider.to_string(),
+            subject: claims.sub,
+            email,
+        };
+
+        self.repo
+            .create_oidc_user(&name, &self.unusable_password()?, locale, &identity)
+            .await
+    }
+
+    // Accounts created through a provider get a random password; a real one can be set through
+    // the forgot-password flow.
+    fn unusable_password(&self) -> Result<String> {
+        self.password_hasher.hash(&generate_token().token)
+    }
+
+    #[instrument(skip_all)]
+    async fn available_name(&self, claims: &IdentityClaims, email: &str) -> Result<String> {
+        let base: String = claims
+            .name
+            .as_deref()
+            .or(claims.preferred_username.as_deref())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or(email))
+            .trim()
+            .chars()
+            .take(MAX_NAME_LENGTH - 9)
+            .collect();
+        let base = if base.is_empty() { "reader" } else { &base };
+
+        if self.repo.get_user(None, Some(base), None).await?.is_none() {
+            return Ok(base.to_string());
+        }
+
+        loop {
+            let candidate = format!("{}-{}", base, &Uuid::now_v7().simple().to_string()[24..]);
+            if self
+                .repo
+                .get_user(None, Some(&candidate), None)
+                .await?
+                .is_none()
+            {
+                return Ok(candidate);
+            }
+        }
+    }
+}
+
+/// Without this, anyone could hand a victim the callback URL of a sign-in they started and log
+/// the victim into the attacker's account.
+fn check_state_binding(state: &str, browser_state: Option<&str>) -> Result<()> {
+    // Comparing digests keeps the comparison time independent of the secret.
+    match browser_state {
+        Some(browser_state) if hash_token(browser_state) == hash_token(state) => Ok(()),
+        _ => Err(Error::BadRequest(
+            "Invalid or expired sign-in state".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_must_match_the_browser_cookie() {
+        let state = generate_token().token;
+
+        assert!(check_state_binding(&state, Some(&state)).is_ok());
+        assert!(check_state_binding(&state, None).is_err());
+        assert!(check_state_binding(&state, Some("")).is_err());
+        assert!(check_state_binding(&state, Some(&generate_token().token)).is_err());
+    }
+}
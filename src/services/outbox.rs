@@ -0,0 +1,305 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::OutboxConfig,
+    mail::{
+        mailer::Mailer,
+        sendmail::{send_email, OutgoingEmail},
+    },
+    models::outbox::{EmailStatus, OutboxEmail},
+    repositories::{outbox_repo::OutboxRepository, PostgresRepo},
+    supervisor::Shutdown,
+    Error, Result,
+};
+
+#[derive(Clone)]
+pub struct OutboxService {
+    repo: PostgresRepo,
+    mailer: Arc<dyn Mailer>,
+    config: OutboxConfig,
+}
+
+impl OutboxService {
+    pub fn new(repo: PostgresRepo, mailer: Arc<dyn Mailer>, config: OutboxConfig) -> Self {
+        Self {
+            repo,
+            mailer,
+            config,
+        }
+    }
+
+    pub async fn run_dispatcher(self, mut shutdown: Shutdown) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            if let Err(err) = self.dispatch_batch(&shutdown).await {
+                tracing::error!(error = ?err, "Failed to dispatch email outbox");
+            }
+
+            if let Err(err) = self.record_queue_depth().await {
+                tracing::warn!(error = ?err, "Failed to count queued emails");
+            }
+        }
+    }
+
+    /// Scrubs the content of long-dead emails and deletes old sent ones.
+    pub async fn run_purger(self, mut shutdown: Shutdown) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.purge_interval_secs.max(60)));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            if let Err(err) = self.purge().await {
+                tracing::error!(error = ?err, "Failed to purge email outbox");
+            }
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn purge(&self) -> Result<()> {
+        let now = Utc::now();
+        let (scrubbed, deleted) = self
+            .repo
+            .purge_emails(
+                now - chrono::Duration::hours(self.config.dead_retention_hours),
+                now - chrono::Duration::days(self.config.sent_retention_days),
+            )
+            .await?;
+
+        if scrubbed > 0 || deleted > 0 {
+            tracing::info!(scrubbed, deleted, "Purged email outbox");
+        }
+
+        Ok(())
+    }
+
+    /// Sends one batch of due emails. On shutdown the rest of the batch is handed back
+    /// instead of waiting for its lease to expire.
+    #[instrument(skip_all)]
+    pub async fn dispatch_batch(&self, shutdown: &Shutdown) -> Result<usize> {
+        let emails = self
+            .repo
+            .claim_emails(self.config.batch_size, self.config.lease_secs)
+            .await?;
+        let claimed = emails.len();
+
+        for (index, email) in emails.iter().enumerate() {
+            if shutdown.is_triggered() {
+                let unsent: Vec<Uuid> = emails[index..].iter().map(|email| email.id).collect();
+                self.repo.release_emails(&unsent).await?;
+                tracing::info!(
+                    released = unsent.len(),
+                    "Released claimed emails on shutdown"
+                );
+                break;
+            }
+
+            let outgoing = OutgoingEmail {
+                to: email.recipient.clone(),
+                subject: email.subject.clone(),
+                html_body: email.html_body.clone(),
+                text_body: email.text_body.clone(),
+                headers: email.headers.0.clone(),
+            };
+
+            match send_email(self.mailer.as_ref(), &outgoing).await {
+                Ok(()) => {
+                    metrics::counter!("mail_sent_total").increment(1);
+                    self.repo.mark_email_sent(email.id).await?
+                }
+                Err(err) => {
+                    metrics::counter!("mail_send_failures_total").increment(1);
+                    let next_attempt_at = Utc::now() + self.backoff(email.attempts + 1);
+                    self.repo
+                        .mark_email_failed(
+                            email.id,
+                            &format!("{:?}", err),
+                            next_attempt_at,
+                            self.config.max_attempts,
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    #[instrument(skip_all)]
+    async fn record_queue_depth(&self) -> Result<()> {
+        let counts = self.repo.count_unsent_emails().await?;
+
+        for status in [
+            EmailStatus::Pending,
+            EmailStatus::Sending,
+            EmailStatus::Dead,
+        ] {
+            let count = counts
+                .iter()
+                .find(|(counted, _)| *counted == status)
+                .map_or(0, |(_, count)| *count);
+
+            metrics::gauge!("mail_outbox_emails", "status" => status.to_str()).set(count as f64);
+        }
+
+        Ok(())
+    }
+
+    fn backoff(&self, attempt: i32) -> chrono::Duration {
+        let exponent = attempt.saturating_sub(1).clamp(0, 30) as u32;
+        let seconds = self
+            .config
+            .base_backoff_secs
+            .saturating_mul(2_i64.saturating_pow(exponent))
+            .min(self.config.max_backoff_secs);
+
+        chrono::Duration::seconds(seconds)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn list_emails(
+        &self,
+        status: Option<EmailStatus>,
+        limit: Option<i64>,
+    ) -> Result<Vec<OutboxEmail>> {
+        let limit = limit.unwrap_or(50).clamp(1, 500);
+
+        self.repo.list_emails(status, limit).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_email(&self, email_id: &str) -> Result<OutboxEmail> {
+        let email_id = Uuid::parse_str(email_id).map_err(|_| Error::NotFound)?;
+
+        self.repo.get_email(email_id).await?.ok_or(Error::NotFound)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn retry_email(&self, email_id: &str) -> Result<OutboxEmail> {
+        let email_id = Uuid::parse_str(email_id).map_err(|_| Error::NotFound)?;
+
+        self.repo
+            .retry_email(email_id)
+            .await?
+            .ok_or(Error::BadRequest(
+                "Only pending or dead emails whose content has not been purged can be retried"
+                    .to_string(),
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        mail::{mailer::MemoryMailer, sendmail::OutgoingEmail},
+        repositories::TestDatabase,
+        supervisor::ShutdownTrigger,
+    };
+
+    use super::*;
+
+    fn email() -> OutgoingEmail {
+        OutgoingEmail {
+            to: "reader@example.com".to_string(),
+            subject: "Weekly digest".to_string(),
+            html_body: "<p>Hi</p>".to_string(),
+            text_body: Some("Hi".to_string()),
+            headers: BTreeMap::from([(
+                "List-Unsubscribe".to_string(),
+                "<https://api.example.com/unsubscribe?token=abc>".to_string(),
+            )]),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn queued_emails_are_sent_and_then_scrubbed() {
+        let database = TestDatabase::create().await;
+        let repo = database.repo.clone();
+        let mailer = Arc::new(MemoryMailer::new("noreply@example.com".parse().unwrap()));
+        let outbox = OutboxService::new(repo.clone(), mailer.clone(), OutboxConfig::default());
+        let trigger = ShutdownTrigger::new();
+
+        repo.queue_email(&email()).await.unwrap();
+        assert_eq!(
+            outbox.dispatch_batch(&trigger.subscribe()).await.unwrap(),
+            1
+        );
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].headers().get_raw("To"), Some("reader@example.com"));
+        assert_eq!(sent[0].headers().get_raw("Subject"), Some("Weekly digest"));
+        assert_eq!(
+            sent[0].headers().get_raw("List-Unsubscribe"),
+            Some("<https://api.example.com/unsubscribe?token=abc>")
+        );
+
+        let stored = outbox
+            .list_emails(Some(EmailStatus::Sent), None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        let (html_body, text_body, headers): (String, Option<String>, serde_json::Value) =
+            sqlx::query_as("SELECT html_body, text_body, headers FROM email_outbox")
+                .fetch_one(database.pool())
+                .await
+                .unwrap();
+        assert!(html_body.is_empty());
+        assert_eq!(text_body, None);
+        assert_eq!(headers, serde_json::json!({}));
+
+        assert_eq!(
+            outbox.dispatch_batch(&trigger.subscribe()).await.unwrap(),
+            0
+        );
+        assert_eq!(mailer.sent().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn claimed_emails_are_released_on_shutdown() {
+        let database = TestDatabase::create().await;
+        let repo = database.repo.clone();
+        let mailer = Arc::new(MemoryMailer::new("noreply@example.com".parse().unwrap()));
+        let outbox = OutboxService::new(repo.clone(), mailer.clone(), OutboxConfig::default());
+        let trigger = ShutdownTrigger::new();
+
+        repo.queue_email(&email()).await.unwrap();
+        trigger.trigger();
+        outbox.dispatch_batch(&trigger.subscribe()).await.unwrap();
+
+        assert!(mailer.sent().is_empty());
+        let pending = outbox
+            .list_emails(Some(EmailStatus::Pending), None)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+
+        assert_eq!(
+            outbox
+                .dispatch_batch(&ShutdownTrigger::new().subscribe())
+                .await
+                .unwrap(),
+            1
+        );
+        let sent = String::from_utf8(mailer.sent()[0].formatted()).unwrap();
+        assert!(sent.contains("<p>Hi</p>"));
+    }
+}
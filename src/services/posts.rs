@@ -1,24 +1,40 @@
+use tracing::instrument;
+
 use crate::{
     models::news_post::{CreateNewsPostDto, NewsPost, PostCommentWithComments},
     repositories::{news_post_repo::NewsPostsRepository, PostgresRepo},
     Result,
 };
 
+use super::media::MediaService;
+
 #[derive(Clone)]
 pub struct NewsPostsService {
     repo: PostgresRepo,
+    media: MediaService,
 }
 
 impl NewsPostsService {
-    pub fn new(repo: PostgresRepo) -> Self {
-        Self { repo }
+    pub fn new(repo: PostgresRepo, media: MediaService) -> Self {
+        Self { repo, media }
     }
+    #[instrument(skip_all)]
     pub async fn get_news_posts(&self) -> Result<Vec<NewsPost>> {
-        let newspost = self.repo.get_news_posts().await?;
+        let mut newspost = self.repo.get_news_posts().await?;
+
+        let cover_images: Vec<_> = newspost
+            .iter()
+            .map(|post| post.cover_image.as_deref())
+            .collect();
+        let covers = self.media.covers_metadata(&cover_images).await;
+        for (post, cover) in newspost.iter_mut().zip(covers) {
+            post.cover = cover;
+        }
 
         Ok(newspost)
     }
 
+    #[instrument(skip_all, fields(user_id = %author_id))]
     pub async fn create_news_post(
         &self,
         news_post: CreateNewsPostDto,
@@ -30,46 +46,67 @@ impl NewsPostsService {
                 &news_post.description,
                 author_id,
                 &news_post.author_name,
+                news_post.cover_image.as_deref(),
             )
             .await?;
 
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn update_news_post(
         &self,
         news_post_id: &str,
         update_news_post_url: Option<&str>,
         update_news_post_description: Option<&str>,
+        update_news_post_cover_image: Option<&str>,
     ) -> Result<()> {
         self.repo
             .update_news_post(
                 news_post_id,
                 update_news_post_url,
                 update_news_post_description,
+                update_news_post_cover_image,
             )
             .await?;
 
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn delete_news_post(&self, news_post_id: &str) -> Result<()> {
         self.repo.delete_news_post(news_post_id).await?;
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn get_posts_with_comments(&self, post_id: &str) -> Result<PostCommentWithComments> {
-        let posts = self.repo.get_posts_with_comments(post_id).await?;
+        let mut posts = self.repo.get_posts_with_comments(post_id).await?;
+        posts.cover = self
+            .media
+            .cover_metadata(posts.cover_image.as_deref())
+            .await;
 
         Ok(posts)
     }
 
+    #[instrument(skip_all)]
     pub async fn get_all_posts_with_comments(&self) -> Result<Vec<PostCommentWithComments>> {
-        let posts_with_comments = self.repo.get_all_posts_with_comments().await?;
+        let mut posts_with_comments = self.repo.get_all_posts_with_comments().await?;
+
+        let cover_images: Vec<_> = posts_with_comments
+            .iter()
+            .map(|post| post.cover_image.as_deref())
+            .collect();
+        let covers = self.media.covers_metadata(&cover_images).await;
+        for (post, cover) in posts_with_comments.iter_mut().zip(covers) {
+            post.cover = cover;
+        }
 
         Ok(posts_with_comments)
     }
 
+    #[instrument(skip_all, fields(user_id = %author_id))]
     pub async fn create_comment(
         &self,
         post_id: &str,
@@ -83,11 +120,13 @@ impl NewsPostsService {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn update_comment(&self, comment_id: &str, content: Option<&str>) -> Result<()> {
         self.repo.update_comment(comment_id, content).await?;
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn delete_comment(&self, comment_id: &str) -> Result<()> {
         self.repo.delete_comment(comment_id).await?;
 
@@ -1,58 +1,61 @@
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
-};
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    models::users::{NameUpdateDto, User, UserPasswordUpdateDto},
+    jwt::token::{Claims, SessionTokens},
+    mail::templates::LOCALES,
+    models::{
+        digest::DigestFrequency,
+        users::{NameUpdateDto, User, UserPasswordUpdateDto},
+    },
+    password::{hasher::PasswordHasher, policy::PasswordPolicy},
     repositories::{user_repo::UserRepository, PostgresRepo},
     Error, Result,
 };
 
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
-
 #[derive(Clone)]
 pub struct UserService {
     repo: PostgresRepo,
-    jwt_secret: String,
+    tokens: SessionTokens,
+    password_policy: Arc<PasswordPolicy>,
+    password_hasher: Arc<PasswordHasher>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    iat: usize,
-    exp: usize,
-}
 impl UserService {
-    pub fn new(repo: PostgresRepo, jwt_secret: String) -> Self {
-        Self { repo, jwt_secret }
+    pub fn new(
+        repo: PostgresRepo,
+        tokens: SessionTokens,
+        password_policy: Arc<PasswordPolicy>,
+        password_hasher: Arc<PasswordHasher>,
+    ) -> Self {
+        Self {
+            repo,
+            tokens,
+            password_policy,
+            password_hasher,
+        }
     }
 
+    #[instrument(skip_all, fields(user_id = ?user_id))]
     pub async fn get_user(
         &self,
         user_id: Option<Uuid>,
         name: Option<&str>,
         email: Option<&str>,
-        token: Option<&str>,
     ) -> Result<User> {
-        let user = self.repo.get_user(user_id, name, email, token).await?;
+        let user = self.repo.get_user(user_id, name, email).await?;
         let user = user.ok_or(Error::NotFound)?;
         Ok(user)
     }
 
-    pub fn decode_token<T: Into<String>>(&self, token: T) -> Result<Uuid> {
-        let decode = decode::<Claims>(
-            &token.into(),
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &Validation::new(Algorithm::HS256),
-        )
-        .map_err(|_| Error::NotFound)?;
-
-        Uuid::parse_str(&decode.claims.sub).map_err(|_| Error::Unauthorized)
+    pub fn decode_token<T: Into<String>>(&self, token: T) -> Result<Claims> {
+        self.tokens
+            .verify(&token.into())
+            .map_err(|_| Error::NotFound)
     }
 
+    #[instrument(skip_all, fields(user_id = %user_id))]
     pub async fn delete_user(&self, user_id: &str) -> Result<()> {
         let user_id = Uuid::parse_str(user_id).unwrap();
 
@@ -60,13 +63,10 @@ impl UserService {
         Ok(())
     }
 
+    #[instrument(skip_all, fields(user_id = %user.id))]
     pub async fn update_username(&self, user: &User, user_update: NameUpdateDto) -> Result<()> {
-        let argon2 = Argon2::default();
-        let parsed_hash =
-            PasswordHash::new(&user.password).map_err(|_| Error::InternalServerError)?;
-        argon2
-            .verify_password(user_update.password.as_bytes(), &parsed_hash)
-            .map_err(|_| Error::BadRequest("Invalid password!".to_string()))?;
+        self.password_hasher
+            .verify(&user_update.password, &user.password)?;
 
         self.repo
             .update_username(user.id, &user_update.name)
@@ -75,6 +75,34 @@ impl UserService {
         Ok(())
     }
 
+    #[instrument(skip_all, fields(user_id = %user.id))]
+    pub async fn update_locale(&self, user: &User, locale: &str) -> Result<()> {
+        let locale = LOCALES
+            .iter()
+            .find(|supported| supported.eq_ignore_ascii_case(locale))
+            .ok_or(Error::BadRequest(format!(
+                "Unsupported locale. Supported locales: {}",
+                LOCALES.join(", ")
+            )))?;
+
+        self.repo.update_locale(user.id, locale).await
+    }
+
+    #[instrument(skip_all, fields(user_id = %user.id))]
+    pub async fn update_digest_frequency(
+        &self,
+        user: &User,
+        frequency: DigestFrequency,
+    ) -> Result<()> {
+        self.repo.update_digest_frequency(user.id, frequency).await
+    }
+
+    #[instrument(skip_all, fields(user_id = %user.id))]
+    pub async fn update_magic_link(&self, user: &User, enabled: bool) -> Result<()> {
+        self.repo.update_magic_link(user.id, enabled).await
+    }
+
+    #[instrument(skip_all, fields(user_id = %user.id))]
     pub async fn update_user_password(
         &self,
         user: &User,
@@ -82,23 +110,22 @@ impl UserService {
     ) -> Result<()> {
         let user_id = uuid::Uuid::parse_str(&user.id.to_string()).unwrap();
 
-        let result = self.repo.get_user(Some(user_id), None, None, None).await?;
+        let result = self.repo.get_user(Some(user_id), None, None).await?;
 
         let user = result.ok_or(Error::BadRequest("Invalid password!".to_string()))?;
 
-        let argon2 = Argon2::default();
-        let parsed_hash =
-            PasswordHash::new(&user.password).map_err(|_| Error::InternalServerError)?;
-        argon2
-            .verify_password(user_update.old_password.as_bytes(), &parsed_hash)
-            .map_err(|_| Error::BadRequest("Invalid password!".to_string()))?;
-
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let hash_password = argon2
-            .hash_password(user_update.new_password.as_bytes(), &salt)
-            .map_err(|_| Error::Unauthorized)?
-            .to_string();
+        self.password_hasher
+            .verify(&user_update.old_password, &user.password)?;
+
+        self.password_policy
+            .validate(
+                "new_password",
+                &user_update.new_password,
+                &[&user.name, &user.email],
+            )
+            .await?;
+
+        let hash_password = self.password_hasher.hash(&user_update.new_password)?;
 
         self.repo.update_password(user_id, &hash_password).await?;
         Ok(())
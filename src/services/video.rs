@@ -1,9 +1,10 @@
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
     models::query::{ResponseVideo, Video},
     repositories::{videos_repo::VideosRepository, PostgresRepo},
-    Result,
+    Error, Result,
 };
 
 #[derive(Clone)]
@@ -15,11 +16,13 @@ impl VideosService {
     pub fn new(repo: PostgresRepo) -> Self {
         Self { repo }
     }
+    #[instrument(skip_all)]
     pub async fn videos(&self) -> Result<Vec<Video>> {
         let videos = self.repo.videos().await?;
         Ok(videos)
     }
 
+    #[instrument(skip_all)]
     pub async fn create_video(
         &self,
         title: &str,
@@ -34,6 +37,7 @@ impl VideosService {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn update_video(
         &self,
         video_id: &str,
@@ -50,6 +54,7 @@ impl VideosService {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn delete_video(&self, video_id: &str) -> Result<()> {
         let video_id = Uuid::parse_str(video_id).unwrap();
         self.repo.delete_video(video_id).await?;
@@ -57,6 +62,7 @@ impl VideosService {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn create_category(&self, category: &str) -> Result<()> {
         let category_id = Uuid::now_v7();
         self.repo.create_category(category_id, category).await?;
@@ -64,12 +70,18 @@ impl VideosService {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn delete_category(&self, category_id: &str) -> Result<()> {
-        let category_id = Uuid::parse_str(category_id).unwrap();
-        self.repo.delete_category(category_id).await?;
+        let category_id = Uuid::parse_str(category_id).map_err(|_| Error::NotFound)?;
+
+        if !self.repo.delete_category(category_id).await? {
+            return Err(Error::NotFound);
+        }
+
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn add_category_to_video(&self, video_id: &str, category_id: &str) -> Result<()> {
         let video_id = Uuid::parse_str(video_id).unwrap();
         let category_id = Uuid::parse_str(category_id).unwrap();
@@ -79,11 +91,13 @@ impl VideosService {
 
         Ok(())
     }
+    #[instrument(skip_all)]
     pub async fn get_video_by_youtube_id(&self, youtube_id: &str) -> Result<ResponseVideo> {
         let video = self.repo.get_video_by_youtube_id(youtube_id).await?;
 
         Ok(video)
     }
+    #[instrument(skip_all)]
     pub async fn remove_category_from_video(
         &self,
         video_id: &str,
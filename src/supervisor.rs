@@ -0,0 +1,121 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use tokio::{sync::watch, task::JoinSet};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A task that ran at least this long before failing starts over at `MIN_BACKOFF`.
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/// Fires once shutdown starts.
+pub struct ShutdownTrigger(watch::Sender<bool>);
+
+impl ShutdownTrigger {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self(sender)
+    }
+
+    pub fn subscribe(&self) -> Shutdown {
+        Shutdown(self.0.subscribe())
+    }
+
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// Handed to background tasks so they can stop between units of work.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    pub async fn wait(&mut self) {
+        // A dropped trigger means nobody is left to keep the task running either.
+        let _ = self.0.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Owns the long-running background tasks, restarts them when they panic or return early,
+/// and stops them together on shutdown.
+pub struct Supervisor {
+    tasks: JoinSet<()>,
+    shutdown: ShutdownTrigger,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            shutdown: ShutdownTrigger::new(),
+        }
+    }
+
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, task: F)
+    where
+        F: Fn(Shutdown) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut shutdown = self.shutdown.subscribe();
+
+        self.tasks.spawn(async move {
+            let mut backoff = MIN_BACKOFF;
+
+            loop {
+                let started = Instant::now();
+                // Spawned separately so a panic surfaces as a `JoinError` instead of
+                // taking the supervisor down with it.
+                let outcome = tokio::spawn(task(shutdown.clone())).await;
+
+                if shutdown.is_triggered() {
+                    break;
+                }
+
+                match outcome {
+                    Ok(()) => tracing::error!(task = name, "Background task exited unexpectedly"),
+                    Err(err) => {
+                        tracing::error!(task = name, error = %err, "Background task crashed")
+                    }
+                }
+                metrics::counter!("background_task_restarts_total", "task" => name).increment(1);
+
+                if started.elapsed() >= HEALTHY_RUN {
+                    backoff = MIN_BACKOFF;
+                }
+                tracing::info!(task = name, retry_in = ?backoff, "Restarting background task");
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = shutdown.wait() => break,
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+    }
+
+    /// Asks every task to stop and waits up to `timeout` for them to finish their current
+    /// unit of work; whatever is still running after that is aborted.
+    pub async fn shutdown(mut self, timeout: Duration) {
+        self.shutdown.trigger();
+
+        let drained = tokio::time::timeout(timeout, async {
+            while self.tasks.join_next().await.is_some() {}
+        })
+        .await;
+
+        if drained.is_err() {
+            tracing::warn!(
+                remaining = self.tasks.len(),
+                "Background tasks did not stop within the drain timeout"
+            );
+            self.tasks.abort_all();
+        }
+    }
+}
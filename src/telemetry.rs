@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{
+    filter::filter_fn, layer::Context, layer::SubscriberExt, registry::LookupSpan,
+    util::SubscriberInitExt, EnvFilter, Layer,
+};
+
+use crate::{
+    config::{LogFormat, MetricsConfig, TelemetryConfig},
+    supervisor::Shutdown,
+    Result,
+};
+
+const REPOSITORY_TARGET: &str = concat!(env!("CARGO_CRATE_NAME"), "::repositories::");
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Owns the OTLP pipeline and the Prometheus recorder, if configured; `shutdown` flushes
+/// buffered spans.
+pub struct Telemetry {
+    tracer_provider: Option<SdkTracerProvider>,
+    metrics: Option<PrometheusHandle>,
+}
+
+impl Telemetry {
+    pub fn init(config: &TelemetryConfig, metrics_config: &MetricsConfig) -> Result<Self> {
+        let filter = || {
+            EnvFilter::try_new(&config.log_filter)
+                .map_err(|err| format!("Invalid log filter {:?}: {}", config.log_filter, err))
+        };
+
+        let fmt_layer = match config.log_format {
+            LogFormat::Pretty => tracing_subscriber::fmt::layer().boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .boxed(),
+        };
+
+        let tracer_provider = match &config.otlp_endpoint {
+            Some(endpoint) => {
+                let exporter = SpanExporter::builder()
+                    .with_tonic()
+                    .with_endpoint(endpoint)
+                    .build()
+                    .map_err(|err| format!("Failed to build the OTLP exporter: {}", err))?;
+
+                Some(
+                    SdkTracerProvider::builder()
+                        .with_batch_exporter(exporter)
+                        .with_resource(
+                            Resource::builder()
+                                .with_service_name(config.service_name.clone())
+                                .build(),
+                        )
+                        .build(),
+                )
+            }
+            None => None,
+        };
+
+        let otel_layer = match &tracer_provider {
+            Some(provider) => Some(
+                tracing_opentelemetry::layer()
+                    .with_tracer(provider.tracer(config.service_name.clone()))
+                    .with_filter(filter()?),
+            ),
+            None => None,
+        };
+
+        let metrics = if metrics_config.enabled() {
+            let handle = PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), &LATENCY_BUCKETS)
+                .and_then(|builder| builder.install_recorder())
+                .map_err(|err| format!("Failed to install the metrics recorder: {}", err))?;
+            Some(handle)
+        } else {
+            None
+        };
+
+        // Repository spans are timed regardless of the log filter.
+        let query_timings = metrics.is_some().then(|| {
+            QueryTimings.with_filter(filter_fn(|metadata| {
+                metadata.is_span() && metadata.target().starts_with(REPOSITORY_TARGET)
+            }))
+        });
+
+        tracing_subscriber::registry()
+            .with(fmt_layer.with_filter(filter()?))
+            .with(otel_layer)
+            .with(query_timings)
+            .try_init()
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            tracer_provider,
+            metrics,
+        })
+    }
+
+    pub fn metrics(&self) -> Option<PrometheusHandle> {
+        self.metrics.clone()
+    }
+
+    pub fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider {
+            if let Err(err) = provider.shutdown() {
+                tracing::warn!(error = %err, "failed to flush spans to the OTLP collector");
+            }
+        }
+    }
+}
+
+/// Drains histogram samples so memory stays bounded between scrapes.
+pub async fn run_metrics_upkeep(handle: PrometheusHandle, mut shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+        handle.run_upkeep();
+    }
+}
+
+/// Records how long each repository call takes, using the spans they already open.
+struct QueryTimings;
+
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for QueryTimings
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(start) = span.extensions().get::<SpanStart>().map(|start| start.0) else {
+            return;
+        };
+
+        let metadata = span.metadata();
+        let repository = metadata
+            .target()
+            .strip_prefix(REPOSITORY_TARGET)
+            .unwrap_or(metadata.target());
+
+        metrics::histogram!(
+            "db_query_duration_seconds",
+            "repository" => repository,
+            "query" => metadata.name(),
+        )
+        .record(start.elapsed().as_secs_f64());
+    }
+}